@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::net::{fetch_json, CancelHandle, FetchError};
+
+// The documents the CDN serves, with only the fields we read. Most fields are
+// optional, since the feed leaves out whatever doesn't apply to an item.
+//
+// Several nodes are keyed by the kind of item (program, series, set, ...), or by
+// aspect ratio. Those are BTreeMaps, so that "the first one" is the same on every load.
+
+// https://cd-static.bamgrid.com/dp-117731241344/home.json
+#[derive(Clone, Debug, Deserialize)]
+pub struct HomeDocument {
+    pub data: HomeData,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HomeData {
+    #[serde(rename = "StandardCollection")]
+    pub standard_collection: StandardCollection,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StandardCollection {
+    pub containers: Vec<Lenient<Container>>,
+}
+
+// A row of the home feed.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Container {
+    pub set: SetRef,
+}
+
+// The set of a container. Curated sets come inline while SetRefs only point to
+// theirs, but they share the fields we read.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetRef {
+    pub ref_id: Option<String>,
+    // Only for SetRefs: the type of the referenced set.
+    pub ref_type: Option<String>,
+    #[serde(rename = "type")]
+    pub set_type: Option<String>,
+    #[serde(default)]
+    pub text: Text,
+    pub availability: Option<Availability>,
+    pub load_priority: Option<i64>,
+    // Only for CuratedSets: their first page.
+    pub items: Option<Vec<Lenient<Tile>>>,
+    pub meta: Option<SetMeta>,
+}
+
+// In seconds since the Unix epoch.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Availability {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+}
+
+// A page of a set, from `data.<set type>` of its document. See `fetch_set`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CuratedSet {
+    pub items: Vec<Lenient<Tile>>,
+    #[serde(default)]
+    pub meta: SetMeta,
+}
+
+// How many items the set has in total, and where this page starts.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SetMeta {
+    pub hits: Option<u64>,
+    pub offset: Option<u64>,
+    pub page_size: Option<u64>,
+}
+
+// An item of a set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tile {
+    // The kind of item, like "DmcVideo" or "DmcSeries".
+    #[serde(rename = "type")]
+    pub item_type: Option<String>,
+    pub content_id: Option<String>,
+    pub encoded_series_id: Option<String>,
+    #[serde(default)]
+    pub text: Text,
+    #[serde(default)]
+    pub image: Images,
+    #[serde(default)]
+    pub releases: Vec<Release>,
+    #[serde(default)]
+    pub ratings: Vec<Rating>,
+    // Only for items of live sets: what the channel is airing.
+    pub current_airing: Option<Airing>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Release {
+    pub release_year: Option<u32>,
+}
+
+// A content rating, like "PG-13".
+#[derive(Clone, Debug, Deserialize)]
+pub struct Rating {
+    pub value: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Airing {
+    #[serde(default)]
+    pub text: Text,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Text {
+    pub title: Option<TextField>,
+    pub description: Option<TextField>,
+    // The sub-brand of the item, like "Marvel".
+    pub brand: Option<TextField>,
+}
+
+// A piece of text, keyed by the kind of item.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TextField {
+    #[serde(default)]
+    pub full: BTreeMap<String, TextVariant>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TextVariant {
+    pub default: TextContent,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TextContent {
+    pub content: String,
+}
+
+// Keyed by aspect ratio, then by the kind of item.
+pub type ImageSet = BTreeMap<String, BTreeMap<String, ImageVariant>>;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Images {
+    #[serde(default)]
+    pub tile: ImageSet,
+    #[serde(default)]
+    pub background: ImageSet,
+    #[serde(default)]
+    pub hero_collection: ImageSet,
+    #[serde(default)]
+    pub logo: ImageSet,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ImageVariant {
+    pub default: ImageSource,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageSource {
+    pub url: Option<String>,
+    pub master_width: Option<f64>,
+    pub master_height: Option<f64>,
+}
+
+// An element of a list that doesn't match `T` is kept as-is instead of failing
+// the whole document, so that one malformed item doesn't take its set down.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Lenient<T> {
+    Parsed(T),
+    Malformed(Value),
+}
+
+// --- METHODS ---
+
+impl<T> Lenient<T> {
+    pub fn parsed(&self) -> Option<&T> {
+        match self {
+            Lenient::Parsed(value) => Some(value),
+            Lenient::Malformed(_) => None,
+        }
+    }
+}
+
+impl TextField {
+    // The text for items of `kind`.
+    pub fn get(&self, kind: &str) -> Option<&str> {
+        self.full
+            .get(kind)
+            .map(|variant| variant.default.content.as_str())
+    }
+
+    // The text for whichever kind comes first, for nodes only keyed by the kind
+    // of the item they're in.
+    pub fn first(&self) -> Option<&str> {
+        self.full
+            .values()
+            .next()
+            .map(|variant| variant.default.content.as_str())
+    }
+}
+
+// The image for whichever aspect ratio and kind come first.
+pub fn first_image(images: &ImageSet) -> Option<&ImageSource> {
+    let variants = images.values().next()?;
+    variants.values().next().map(|variant| &variant.default)
+}
+
+pub fn fetch_home(
+    url: &str,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<HomeDocument, FetchError> {
+    let json = fetch_json(url, timeout, cancel)?;
+    Ok(serde_json::from_value(json)?)
+}
+
+// Set documents are keyed by the type of the set, like "CuratedSet". Some SetRefs
+// give a `refType` that doesn't match the key of the set they point to, so a
+// document with a single set is fine whatever its key.
+pub fn fetch_set(
+    url: &str,
+    set_type: &str,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<CuratedSet, FetchError> {
+    parse_set(fetch_json(url, timeout, cancel)?, set_type)
+}
+
+// Same as `fetch_set`, for a set document that's already been fetched.
+fn parse_set(mut json: Value, set_type: &str) -> Result<CuratedSet, FetchError> {
+    let sets = json
+        .get_mut("data")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| FetchError::Schema("data is missing".to_string()))?;
+    let set = match sets.remove(set_type) {
+        Some(set) => set,
+        None if sets.len() == 1 => sets.values_mut().next().unwrap().take(),
+        None => return Err(FetchError::Schema(format!("data.{} is missing", set_type))),
+    };
+    Ok(serde_json::from_value(set)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::tile_json;
+
+    // A set document with a set of two tiles under each of `keys`.
+    fn set_document(keys: &[&str]) -> Value {
+        let mut sets = serde_json::Map::new();
+        for key in keys {
+            let items = json!([tile_json("Moana"), tile_json("Encanto")]);
+            sets.insert(key.to_string(), json!({ "items": items }));
+        }
+        json!({ "data": sets })
+    }
+
+    #[test]
+    fn sets_are_read_from_their_type() {
+        let set = parse_set(set_document(&["TrendingSet"]), "TrendingSet").unwrap();
+        assert_eq!(set.items.len(), 2);
+    }
+
+    #[test]
+    fn a_single_set_is_read_whatever_its_key() {
+        // The SetRef said CuratedSet, but the document has a PersonalizedCuratedSet.
+        let document = set_document(&["PersonalizedCuratedSet"]);
+        let set = parse_set(document, "CuratedSet").unwrap();
+        assert_eq!(set.items.len(), 2);
+    }
+
+    #[test]
+    fn a_mismatched_key_among_several_is_an_error() {
+        let document = set_document(&["TrendingSet", "PersonalizedCuratedSet"]);
+        match parse_set(document, "CuratedSet") {
+            Err(FetchError::Schema(message)) => assert_eq!(message, "data.CuratedSet is missing"),
+            other => panic!("expected a schema error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn documents_without_data_are_an_error() {
+        assert!(matches!(
+            parse_set(json!({ "errors": [] }), "CuratedSet"),
+            Err(FetchError::Schema(_))
+        ));
+    }
+
+    #[test]
+    fn sets_without_items_are_an_error_but_empty_sets_are_not() {
+        let missing = json!({ "data": { "CuratedSet": { "meta": { "hits": 0 } } } });
+        assert!(parse_set(missing, "CuratedSet").is_err());
+
+        let empty = json!({ "data": { "CuratedSet": { "items": [] } } });
+        let set = parse_set(empty, "CuratedSet").unwrap();
+        assert!(set.items.is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::config::{AppConfig, DEFAULT_API_BASE_URL, DEFAULT_COLLECTION_ID};
+
+// Environment variables read when the matching flag isn't passed.
+const CONFIG_VAR: &str = "DSC_CONFIG";
+const BASE_URL_VAR: &str = "DSC_API_BASE_URL";
+const COLLECTION_ID_VAR: &str = "DSC_COLLECTION_ID";
+
+// Where the feed is fetched from, so that the app can be pointed at a mock server
+// or another catalog. From `--api-base-url=<url>` and `--collection-id=<id>`, or
+// the DSC_API_BASE_URL and DSC_COLLECTION_ID environment variables, or the keys of
+// the same name in the TOML file passed with `--config=<path>` or DSC_CONFIG.
+// Flags win over the environment, which wins over the file.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ApiOptions {
+    // If None, DEFAULT_API_BASE_URL.
+    pub api_base_url: Option<String>,
+    // If None, DEFAULT_COLLECTION_ID.
+    pub collection_id: Option<String>,
+}
+
+impl ApiOptions {
+    pub fn from_env() -> Result<Self, String> {
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        Self::parse(
+            &args,
+            std::env::var(CONFIG_VAR).ok(),
+            std::env::var(BASE_URL_VAR).ok(),
+            std::env::var(COLLECTION_ID_VAR).ok(),
+        )
+    }
+
+    pub fn parse(
+        args: &[String],
+        env_config: Option<String>,
+        env_base_url: Option<String>,
+        env_collection_id: Option<String>,
+    ) -> Result<Self, String> {
+        let flag = |name: &str| {
+            let prefix = format!("--{}=", name);
+            args.iter()
+                .find_map(|arg| arg.strip_prefix(&prefix).map(|value| value.to_string()))
+        };
+        let file = match flag("config").or(env_config) {
+            Some(path) => Self::read_file(Path::new(&path))?,
+            None => Self::default(),
+        };
+        Ok(Self {
+            api_base_url: flag("api-base-url").or(env_base_url).or(file.api_base_url),
+            collection_id: flag("collection-id")
+                .or(env_collection_id)
+                .or(file.collection_id),
+        })
+    }
+
+    fn read_file(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    // Leaves `config` alone if neither option is set, so that URLs set some
+    // other way are kept.
+    pub fn apply(&self, config: &mut AppConfig) {
+        if self.api_base_url.is_none() && self.collection_id.is_none() {
+            return;
+        }
+        config.set_api(
+            self.api_base_url.as_deref().unwrap_or(DEFAULT_API_BASE_URL),
+            self.collection_id
+                .as_deref()
+                .unwrap_or(DEFAULT_COLLECTION_ID),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::offline_config;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // Writes `contents` to a config file of its own, named after `name`.
+    fn config_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn flags_win_over_the_environment() {
+        let options = ApiOptions::parse(
+            &args(&["--api-base-url=http://localhost:8000"]),
+            None,
+            Some("http://env.invalid".to_string()),
+            Some("env-collection".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            options.api_base_url.as_deref(),
+            Some("http://localhost:8000")
+        );
+        assert_eq!(options.collection_id.as_deref(), Some("env-collection"));
+    }
+
+    #[test]
+    fn the_environment_wins_over_the_file() {
+        let path = config_file(
+            "api-options",
+            "api_base_url = \"http://file.invalid\"\ncollection_id = \"file-collection\"\n",
+        );
+        let options = ApiOptions::parse(
+            &args(&[&format!("--config={}", path)]),
+            None,
+            None,
+            Some("env-collection".to_string()),
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(options.api_base_url.as_deref(), Some("http://file.invalid"));
+        assert_eq!(options.collection_id.as_deref(), Some("env-collection"));
+    }
+
+    #[test]
+    fn unknown_keys_and_missing_files_are_errors() {
+        let path = config_file("api-options-typo", "api_url = \"http://file.invalid\"\n");
+        let typo = ApiOptions::parse(&[], Some(path.clone()), None, None);
+        std::fs::remove_file(&path).unwrap();
+        assert!(typo.is_err());
+
+        let missing =
+            ApiOptions::parse(&args(&["--config=/nonexistent/api.toml"]), None, None, None);
+        assert!(missing.unwrap_err().starts_with("/nonexistent/api.toml: "));
+    }
+
+    #[test]
+    fn apply_fills_in_the_defaults() {
+        let mut config = offline_config();
+        config.set_api("http://localhost:8000", "mine");
+        ApiOptions::default().apply(&mut config);
+        assert_eq!(
+            config.collection_url,
+            "http://localhost:8000/mine/home.json"
+        );
+
+        let options = ApiOptions {
+            api_base_url: None,
+            collection_id: Some("other".to_string()),
+        };
+        options.apply(&mut config);
+        assert_eq!(
+            config.collection_url,
+            format!("{}/other/home.json", DEFAULT_API_BASE_URL)
+        );
+    }
+}
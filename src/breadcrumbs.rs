@@ -0,0 +1,85 @@
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::{theme, Color, Point, Rect};
+
+use crate::settings::BACKGROUND_COLOR;
+
+// How much room the trail takes at the top of the window. Pages are laid out
+// below it.
+pub const BREADCRUMB_HEIGHT: f64 = 40.0;
+
+const PADDING: f64 = 16.0;
+const FONT_SIZE: f64 = 15.0;
+const SEPARATOR: &str = "\u{203a}";
+const CURRENT_COLOR: Color = Color::rgb8(0x4d, 0xa3, 0xff);
+
+// The path from the grid to the page on top, like "Home › Search › Moana", drawn
+// across the top of the window. Every crumb can be clicked to go back to its level.
+// Like the overlays, this isn't a widget: the owner paints it with the current
+// path, and asks it which crumb a click landed on.
+#[derive(Default)]
+pub struct Breadcrumbs {
+    // Where each crumb was last painted, in the owner's coordinates.
+    pub crumb_rects: Vec<Rect>,
+}
+
+impl Breadcrumbs {
+    pub fn new() -> Self {
+        Self {
+            crumb_rects: Vec::new(),
+        }
+    }
+
+    // The index of the crumb under `point`, if any.
+    pub fn crumb_at(&self, point: Point) -> Option<usize> {
+        self.crumb_rects
+            .iter()
+            .position(|rect| rect.contains(point))
+    }
+
+    // Draws `crumbs` left to right along the top of an area `width` wide. The
+    // last one is the current level, and is highlighted.
+    pub fn paint(&mut self, ctx: &mut PaintCtx, width: f64, crumbs: &[String], env: &Env) {
+        self.crumb_rects.clear();
+        let bar_rect = Rect::new(0.0, 0.0, width, BREADCRUMB_HEIGHT);
+        ctx.fill(bar_rect, &env.get(&BACKGROUND_COLOR));
+
+        let text_color = env.get(&theme::TEXT_COLOR);
+        let separator_color = env.get(&theme::DISABLED_TEXT_COLOR);
+        let mut x = PADDING;
+        for (index, crumb) in crumbs.iter().enumerate() {
+            if index > 0 {
+                let separator = ctx
+                    .text()
+                    .new_text_layout(SEPARATOR)
+                    .font(FontFamily::SYSTEM_UI, FONT_SIZE)
+                    .text_color(separator_color.clone())
+                    .build()
+                    .unwrap();
+                let y = (BREADCRUMB_HEIGHT - separator.size().height) / 2.0;
+                x += PADDING / 2.0;
+                ctx.draw_text(&separator, Point::new(x, y));
+                x += separator.size().width + PADDING / 2.0;
+            }
+            let color = if index + 1 == crumbs.len() {
+                CURRENT_COLOR
+            } else {
+                text_color.clone()
+            };
+            let text = ctx
+                .text()
+                .new_text_layout(crumb.clone())
+                .font(FontFamily::SYSTEM_UI, FONT_SIZE)
+                .text_color(color)
+                .build()
+                .unwrap();
+            let text_size = text.size();
+            let origin = Point::new(x, (BREADCRUMB_HEIGHT - text_size.height) / 2.0);
+            ctx.draw_text(&text, origin);
+            // The whole height of the bar is clickable, not just the text.
+            self.crumb_rects
+                .push(Rect::new(x, 0.0, x + text_size.width, BREADCRUMB_HEIGHT));
+            x += text_size.width;
+        }
+    }
+}
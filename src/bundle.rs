@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use once_cell::sync::OnceCell;
+use serde_json::Value;
+
+use crate::api::HomeDocument;
+use crate::config::AppConfig;
+use crate::content_set::content_set_url;
+use crate::net::FetchError;
+use crate::root_widget::parse_container;
+
+// A whole catalog in one JSON document, for test harnesses and pipelines that
+// don't want to run a server: `{"home": <collection>, "sets": {<ref id>: <set>}}`.
+// Sets are optional; rows whose set isn't in the bundle fail to load.
+// Once installed, every JSON document is read from it instead of fetched.
+// Images are still fetched.
+static BUNDLE: OnceCell<HashMap<String, Value>> = OnceCell::new();
+
+// Reads a bundle and files its documents under the URLs they'd be fetched from.
+// Sets are only bundled as one page, so they shouldn't say there are more items.
+pub fn read_bundle(
+    reader: impl Read,
+    config: &AppConfig,
+) -> Result<HashMap<String, Value>, FetchError> {
+    let mut bundle: Value = serde_json::from_reader(reader)?;
+    let home = bundle["home"].take();
+    if home.is_null() {
+        return Err(FetchError::Schema("the bundle has no home".to_string()));
+    }
+    let mut sets = match bundle["sets"].take() {
+        Value::Object(sets) => sets,
+        Value::Null => Default::default(),
+        _ => {
+            return Err(FetchError::Schema(
+                "bundle.sets is not an object".to_string(),
+            ))
+        }
+    };
+
+    let mut documents = HashMap::new();
+    let parsed_home: HomeDocument = serde_json::from_value(home.clone())?;
+    for container in &parsed_home.data.standard_collection.containers {
+        if let Ok(metadata) = parse_container(container) {
+            if let Some(set) = sets.remove(&metadata.ref_id) {
+                documents.insert(content_set_url(config, &metadata, 0), set);
+            }
+        }
+    }
+    for ref_id in sets.keys() {
+        tracing::warn!("Set {} of the bundle isn't in its home", ref_id);
+    }
+    documents.insert(config.collection_url.clone(), home);
+    Ok(documents)
+}
+
+// Only the first call counts, and it has to happen before the first request.
+pub fn install_bundle(documents: HashMap<String, Value>) {
+    if BUNDLE.set(documents).is_err() {
+        tracing::warn!("A feed bundle was already installed, ignoring this one");
+    }
+}
+
+// None if no bundle is installed, in which case documents are fetched as usual.
+pub fn bundled_json(url: &str) -> Option<Result<Value, FetchError>> {
+    let documents = BUNDLE.get()?;
+    Some(
+        documents
+            .get(url)
+            .cloned()
+            .ok_or_else(|| FetchError::NotBundled(url.to_string())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{home_json, set_json};
+
+    fn bundle_config() -> AppConfig {
+        let mut config = AppConfig::default();
+        config.set_api("https://cdn.invalid", "collection");
+        config
+    }
+
+    #[test]
+    fn documents_are_filed_under_their_urls() {
+        let home = home_json(&[("trending", "Trending"), ("new", "New to Disney+")]);
+        let trending = set_json(&["Moana".to_string()]);
+        let bundle = json!({
+            "home": home,
+            "sets": { "trending": trending, "unlisted": set_json(&[]) }
+        });
+        let documents = read_bundle(bundle.to_string().as_bytes(), &bundle_config()).unwrap();
+
+        let mut urls: Vec<&str> = documents.keys().map(String::as_str).collect();
+        urls.sort_unstable();
+        // Sets that aren't in the home, like "unlisted", are left out. Rows whose
+        // set isn't bundled, like "new", don't get a document.
+        assert_eq!(
+            urls,
+            [
+                "https://cdn.invalid/collection/home.json",
+                "https://cdn.invalid/collection/sets/trending.json",
+            ]
+        );
+        assert_eq!(documents["https://cdn.invalid/collection/home.json"], home);
+        assert_eq!(
+            documents["https://cdn.invalid/collection/sets/trending.json"],
+            trending
+        );
+    }
+
+    #[test]
+    fn sets_are_optional() {
+        let bundle = json!({ "home": home_json(&[("trending", "Trending")]) });
+        let documents = read_bundle(bundle.to_string().as_bytes(), &bundle_config()).unwrap();
+        assert_eq!(documents.len(), 1);
+    }
+
+    #[test]
+    fn malformed_bundles_are_rejected() {
+        let config = bundle_config();
+        let no_home = json!({ "sets": {} });
+        assert!(matches!(
+            read_bundle(no_home.to_string().as_bytes(), &config),
+            Err(FetchError::Schema(_))
+        ));
+        let sets_array = json!({ "home": home_json(&[]), "sets": [] });
+        assert!(matches!(
+            read_bundle(sets_array.to_string().as_bytes(), &config),
+            Err(FetchError::Schema(_))
+        ));
+        assert!(matches!(
+            read_bundle(&b"{"[..], &config),
+            Err(FetchError::Json(_))
+        ));
+    }
+}
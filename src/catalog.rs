@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use crate::config::AppConfig;
+use crate::content_set::{content_set_url, load_content_set, ContentSetMetadata, ContentSetPage};
+use crate::net::{CancelHandle, FetchError};
+use crate::root_widget::load_collection;
+use crate::workers::{LoadPriority, WorkerPool};
+
+// In which order `stream_catalog` hands out rows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowOrder {
+    // As soon as each row resolves.
+    Completion,
+    // In the order of the feed. A slow row holds back the rows after it.
+    Feed,
+}
+
+// A row of the catalog, with the first page of its set.
+pub struct CatalogRow {
+    // Position of the row in the collection.
+    pub index: usize,
+    pub metadata: ContentSetMetadata,
+    // A row that fails to load is still yielded, so that one bad set doesn't
+    // end the stream.
+    pub page: Result<ContentSetPage, FetchError>,
+}
+
+// Fetches the collection, then yields every row once its set has loaded, for
+// callers without a window. The collection is fetched before this returns;
+// sets are fetched in the background, at most as many at a time as `workers` allows.
+// The stream ends once every row has been yielded.
+pub fn stream_catalog(
+    config: &AppConfig,
+    workers: Arc<WorkerPool>,
+    order: RowOrder,
+) -> Result<Receiver<CatalogRow>, FetchError> {
+    let cancel = CancelHandle::default();
+    let mut rows = load_collection(&config.collection_url, config.http.home_timeout, &cancel)?;
+    if let Some(row_limit) = config.row_limit {
+        rows.truncate(row_limit);
+    }
+
+    let (sender, receiver) = channel();
+    for (index, metadata) in rows.into_iter().enumerate() {
+        let sender = sender.clone();
+        let workers = workers.clone();
+        let timeout = config.http.set_timeout;
+        let dedupe = config.dedupe_tiles;
+        let url = content_set_url(config, &metadata, 0);
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            let priority = LoadPriority::new(metadata.load_priority, index);
+            let page = workers.run_with_priority(priority, || {
+                load_content_set(&url, &metadata.set_type, dedupe, timeout, &cancel)
+            });
+            // The receiver may have been dropped if the caller stopped early.
+            let _ = sender.send(CatalogRow {
+                index,
+                metadata,
+                page,
+            });
+        });
+    }
+
+    match order {
+        RowOrder::Completion => Ok(receiver),
+        RowOrder::Feed => Ok(in_feed_order(receiver)),
+    }
+}
+
+// Holds back rows that resolve early until every row before them has been yielded.
+fn in_feed_order(rows: Receiver<CatalogRow>) -> Receiver<CatalogRow> {
+    let (sender, receiver) = channel();
+    thread::spawn(move || {
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+        for row in rows {
+            pending.insert(row.index, row);
+            while let Some(row) = pending.remove(&next_index) {
+                if sender.send(row).is_err() {
+                    return;
+                }
+                next_index += 1;
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{home_json, home_path, set_json, set_path, test_config, MockServer};
+
+    // Three rows, the middle one of which has no set on the server.
+    fn catalog_server() -> MockServer {
+        let server = MockServer::start();
+        let rows = [
+            ("trending", "Trending"),
+            ("missing", "Missing"),
+            ("new", "New"),
+        ];
+        server.add(&home_path(), home_json(&rows).to_string());
+        let titles = vec!["Moana".to_string(), "Encanto".to_string()];
+        server.add(&set_path("trending"), set_json(&titles).to_string());
+        server.add(&set_path("new"), set_json(&titles).to_string());
+        server
+    }
+
+    #[test]
+    fn failed_rows_are_yielded_with_the_others() {
+        let server = catalog_server();
+        let config = test_config(&server);
+        let workers = Arc::new(WorkerPool::new(Some(2)));
+
+        let mut rows: Vec<CatalogRow> = stream_catalog(&config, workers, RowOrder::Completion)
+            .unwrap()
+            .into_iter()
+            .collect();
+        rows.sort_by_key(|row| row.index);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].page.as_ref().unwrap().tiles.len(), 2);
+        assert!(rows[1].page.is_err());
+        assert_eq!(rows[1].metadata.ref_id, "missing");
+        assert_eq!(rows[2].page.as_ref().unwrap().tiles.len(), 2);
+    }
+
+    #[test]
+    fn feed_order_waits_for_slow_rows() {
+        let server = catalog_server();
+        server.delay(&set_path("trending"), Duration::from_millis(200));
+        let config = test_config(&server);
+        let workers = Arc::new(WorkerPool::new(None));
+
+        let indices: Vec<usize> = stream_catalog(&config, workers, RowOrder::Feed)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.index)
+            .collect();
+        assert_eq!(indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn rows_with_a_priority_hint_are_fetched_first() {
+        let server = MockServer::start();
+        let rows = [("a", "A"), ("b", "B"), ("c", "C"), ("urgent", "Urgent")];
+        server.add_catalog(&rows, 2);
+        let mut home = home_json(&rows);
+        home["data"]["StandardCollection"]["containers"][3]["set"]["loadPriority"] = json!(10);
+        server.add(&home_path(), home.to_string());
+        let config = test_config(&server);
+
+        // Every row queues up behind a load holding the only slot, so they're
+        // let through by priority rather than by who asked first.
+        let workers = Arc::new(WorkerPool::new(Some(1)));
+        let (release, released) = channel::<()>();
+        let holder = {
+            let workers = workers.clone();
+            thread::spawn(move || workers.run(|| released.recv()))
+        };
+        let stream = stream_catalog(&config, workers, RowOrder::Completion).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        release.send(()).unwrap();
+        holder.join().unwrap().unwrap();
+        assert_eq!(stream.into_iter().count(), rows.len());
+
+        let set_requests: Vec<String> = server
+            .requested_paths()
+            .into_iter()
+            .filter(|path| *path != home_path())
+            .collect();
+        let expected: Vec<String> = ["urgent", "a", "b", "c"]
+            .iter()
+            .map(|ref_id| set_path(ref_id))
+            .collect();
+        assert_eq!(set_requests, expected);
+    }
+}
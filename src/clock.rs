@@ -0,0 +1,65 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+// Where widgets get the current time when they measure durations, eg to advance
+// an animation. Tests can swap in a FakeClock to control time exactly, instead
+// of counting frames or sleeping.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    // The time of day, for what's scheduled by the feed rather than measured.
+    fn wall_time(&self) -> SystemTime;
+}
+
+// Like SharedSelection, owned by the RootWidget and cloned into the widgets that need it.
+pub type SharedClock = Rc<dyn Clock>;
+
+// The default clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// A clock that only moves when told to.
+pub struct FakeClock {
+    now: Cell<Instant>,
+    wall_time: Cell<SystemTime>,
+}
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self {
+            now: Cell::new(Instant::now()),
+            wall_time: Cell::new(SystemTime::now()),
+        }
+    }
+
+    // Moves both times forward together.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+        self.wall_time.set(self.wall_time.get() + duration);
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+
+    fn wall_time(&self) -> SystemTime {
+        self.wall_time.get()
+    }
+}
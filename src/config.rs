@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use widget_cruncher::Size;
+
+use crate::keymap::KeyMap;
+use crate::paths::{cache_path, config_path};
+
+// Where the feed is served from by default. See `AppConfig::set_api`.
+pub const DEFAULT_API_BASE_URL: &str = "https://cd-static.bamgrid.com";
+pub const DEFAULT_COLLECTION_ID: &str = "dp-117731241344";
+
+// Settings shared by every widget of the app.
+// Widgets get an `Arc<AppConfig>` in their constructor and pass it down to their children.
+#[derive(Clone, Debug)]
+pub struct AppConfig {
+    pub collection_url: String,
+    // Where sets are fetched from. "{ref_id}" and "{set_type}" are replaced
+    // with those of the set. See `content_set::set_url`.
+    pub set_url_template: String,
+    // Where search results are fetched from. "{query}" is replaced with the
+    // query, percent-encoded. See `search::search_url`.
+    pub search_url_template: String,
+    pub http: HttpConfig,
+    pub text_direction: TextDirection,
+    // Tile selected at startup, as (row, column). If None, nothing is highlighted
+    // until the user presses a key.
+    pub initial_selection: Option<(usize, usize)>,
+    // Where keyboard focus starts out, relative to the initial selection.
+    pub startup_focus: StartupFocus,
+    // What the volume overlay starts at, between 0.0 and 1.0. The player plays
+    // streams at the overlay's value, not this one.
+    pub volume: f64,
+    // Silences the SoundHandler, without having to unset it.
+    pub sounds_muted: bool,
+    // How many tiles on each side of a row's selected tile have a live widget.
+    // Should cover at least the width of the window.
+    pub virtualization_margin: usize,
+    // Draws tiles as cards with their title instead of loading their image,
+    // for networks where only the JSON feed is affordable.
+    pub text_only: bool,
+    // Shown in rows whose items all lack a usable tile. If None, those rows get
+    // a "Content unavailable" card instead.
+    pub unavailable_artwork_url: Option<String>,
+    // Whether to drop items that repeat content already in the same page of a set.
+    // Off by default, so rows match the feed.
+    pub dedupe_tiles: bool,
+    // Width / height of tiles whose image dimensions aren't in the feed.
+    pub default_tile_aspect_ratio: f64,
+    // Turns off both the selection and pan animations, unless they're
+    // explicitly turned back on below.
+    pub reduce_motion: bool,
+    // What marks the selected tile.
+    pub selection_style: SelectionStyle,
+    // Whether selected tiles grow smoothly, or pop to their selected size.
+    // If None, follows `reduce_motion`.
+    pub selection_animation: Option<bool>,
+    // Whether rows scroll smoothly to the selected tile in PanAlignment::Center,
+    // the grid glides from row to row as the selection moves, and SCROLL_ROW
+    // commands can be animated. If None, follows `reduce_motion`.
+    pub pan_animation: Option<bool>,
+    // How long a tile has to stay selected before it swaps to its preview
+    // artwork, if it has one. If None, tiles always show their regular artwork.
+    pub spotlight_dwell: Option<Duration>,
+    // Whether activating a tile opens its DetailPage. Either way, ACTIVATE_ITEM is
+    // sent for the app embedding the grid.
+    pub detail_pages: bool,
+    // If set, activating a tile plays this in a VideoPlayer instead of opening its
+    // DetailPage. The feed doesn't give stream URLs, so every tile plays the same
+    // one; it can also be a local file. See `--play-url`.
+    pub player_stream_url: Option<String>,
+    // Whether tiles show their title, type and rating under their image while
+    // they're selected.
+    pub tile_captions: bool,
+    // Where the brand chip of branded tiles goes. If None, brands aren't shown.
+    pub brand_chip_corner: Option<Corner>,
+    // Caps how often the selection animation advances, to save CPU on slow hardware.
+    // If None, it advances on every frame the display draws.
+    pub animation_fps: Option<u32>,
+    // Only the first `row_limit` rows of the collection are shown, if set.
+    pub row_limit: Option<usize>,
+    // The set ref id of a row to dock at the top of the window, eg "Continue
+    // watching". It's moved to the front of the feed, and the other rows scroll
+    // beneath it.
+    pub pinned_row: Option<String>,
+    // Where the selected tile ends up when the grid scrolls to it.
+    pub pan_alignment: PanAlignment,
+    // With PanAlignment::MinimalReveal, how much room is left between the selected
+    // tile and the edges of the viewport, where the content allows it. Keeps the
+    // tile clear of overscan on TVs.
+    pub focus_margin: f64,
+    // Whether moving past either end of a row wraps around to its other end.
+    // Rows still loading pages don't wrap. Either way, the selection never goes
+    // past the last tile of a row.
+    pub wrap_selection: bool,
+    pub scroll_snap: ScrollSnap,
+    pub keymap: KeyMap,
+    // How to order the tiles of a row, by set ref id. Rows that aren't listed,
+    // and live rows, keep the order of the feed.
+    pub tile_sort: HashMap<String, TileSort>,
+    // How old a row's first page can get, by set ref id. When the selection enters
+    // a row whose first page is older than that, it's fetched again in the
+    // background, and the tiles that changed are swapped in place.
+    pub row_max_age: HashMap<String, Duration>,
+    // Where favorites are saved between runs. If None, they only last for the session.
+    pub favorites_path: Option<PathBuf>,
+    // Where the tiles of the last visit are saved, to badge the new ones.
+    pub seen_tiles_path: Option<PathBuf>,
+    // The size the window opens at the first time. After that, it opens at the size
+    // it was last resized to, saved to `window_size_path`. If both are None, the
+    // platform picks.
+    pub window_size: Option<Size>,
+    pub window_size_path: Option<PathBuf>,
+    // Where the choices made on the settings page are saved. If None, they only
+    // last for the session.
+    pub settings_path: Option<PathBuf>,
+    // People sharing the device, each with their own feed, favorites, seen tiles
+    // and settings.
+    // The first one is active at startup. If empty, there's a single, unnamed profile.
+    pub profiles: Vec<Profile>,
+    // Where the state of each profile is saved, in a directory named after it.
+    pub profiles_dir: PathBuf,
+    // Where tile images are saved once downloaded. If None, they're fetched on every launch.
+    pub image_cache_dir: Option<PathBuf>,
+    // How many bytes of decoded images are kept in memory for thumbnails that
+    // aren't live, so they show up immediately when scrolled back to.
+    pub decoded_image_budget: usize,
+    pub load_mode: LoadMode,
+    // How long a row waits for its first page before showing a spinner, so rows
+    // that load quickly never flash one. If None, the spinner shows right away.
+    pub spinner_delay: Option<Duration>,
+    // Rows only fetch their first page once they come within this many pixels of
+    // the visible part of the grid, so that a long feed doesn't send a request per
+    // row before anything is shown. If None, every row fetches as soon as it's added.
+    pub lazy_load_margin: Option<f64>,
+    // How often changes to the session (favorites, seen tiles, window size) can be
+    // written to disk. Changes made in between are saved together, and anything
+    // left is saved on exit. If None, every change is written right away.
+    pub session_save_interval: Option<Duration>,
+    // How many collection and set loads can run at once in `LoadMode::Background`.
+    // If None, every row starts loading as soon as it's added.
+    pub background_workers: Option<usize>,
+    // How many images can be fetched and decoded at once, separately from
+    // `background_workers`, so that images and sets don't hold each other up.
+    // If None, every image starts loading as soon as its thumbnail is added.
+    pub image_workers: Option<usize>,
+}
+
+// How long each kind of request can take before it fails.
+#[derive(Clone, Debug)]
+pub struct HttpConfig {
+    // The collection is the first thing we show, so it gets more slack.
+    pub home_timeout: Duration,
+    pub set_timeout: Duration,
+    pub image_timeout: Duration,
+    // If None, requests use the proxy from the environment, if any.
+    pub proxy: Option<ProxyConfig>,
+    pub retry: RetryPolicy,
+}
+
+// How requests that failed in a way that may well not happen again, like a
+// timeout or a 5xx from the CDN, are retried. The wait starts at `base_delay` and
+// doubles after every attempt, up to `max_delay`, and is shortened by a random
+// amount so that the loads that failed together don't all retry together.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    // Including the first one. 1 turns retries off.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+// An HTTP or HTTPS proxy every request goes through, for networks that only
+// reach the CDN that way.
+#[derive(Clone, Debug)]
+pub struct ProxyConfig {
+    // Like "http://proxy.example.com:3128".
+    pub url: String,
+    // As (username, password), if the proxy needs them.
+    pub credentials: Option<(String, String)>,
+    // Hosts reached directly rather than through the proxy, like in NO_PROXY.
+    pub no_proxy: Vec<String>,
+}
+
+// One person's feed on a shared device. See `AppConfig::for_profile`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    // If None, the profile uses `AppConfig::collection_url`.
+    pub collection_url: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextDirection {
+    LeftToRight,
+    // Rows start on the right, titles are right-aligned, and Left/Right arrows are swapped.
+    RightToLeft,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartupFocus {
+    // The tile of `initial_selection`.
+    FirstTile,
+    // The header of the row of `initial_selection`, with the tile still selected,
+    // so that Tab goes down to it.
+    FirstRowHeader,
+    // The hero banner above the rows. The grid doesn't have one yet, so for now
+    // this falls back to FirstTile.
+    Hero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PanAlignment {
+    // Scroll just enough for the selected tile to be visible.
+    MinimalReveal,
+    // Keep the selected tile, and its row, in the middle of the viewport,
+    // except near the ends of the content.
+    Center,
+    // Split each row into screens of as many whole tiles as fit the viewport, and
+    // snap the row to the screen holding the selected tile.
+    Paged,
+}
+
+// Which effects mark the selected tile. Any combination works, and each one
+// follows the selection animation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SelectionStyle {
+    // A white border around the image.
+    pub border: bool,
+    // The image grows to its selected scale.
+    pub scale: bool,
+    // A soft halo behind the image.
+    pub glow: bool,
+}
+
+// Once free scrolling (eg with a mouse wheel) stops, moves the grid to the
+// nearest tile boundary horizontally, and the nearest row boundary vertically,
+// so nothing is left half cut. Keyboard navigation doesn't need it.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScrollSnap {
+    pub horizontal: bool,
+    pub vertical: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileSort {
+    FeedOrder,
+    // Alphabetical. Tiles without a title go last.
+    Title,
+    // Newest first. Tiles without a release year go last.
+    Year,
+}
+
+// How the collection and sets are fetched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadMode {
+    // With `compute_in_background`, so the UI stays responsive.
+    Background,
+    // On the UI thread, while handling an event. This freezes the UI during each
+    // fetch, but doesn't need the framework's background executor, so it's
+    // meant for headless runs and test harnesses where promises never resolve.
+    Synchronous,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        // The URLs are filled in by `set_api`, below.
+        let mut config = Self {
+            collection_url: String::new(),
+            set_url_template: String::new(),
+            search_url_template: String::new(),
+            http: HttpConfig::default(),
+            text_direction: TextDirection::LeftToRight,
+            initial_selection: Some((0, 0)),
+            startup_focus: StartupFocus::FirstTile,
+            volume: 0.5,
+            sounds_muted: false,
+            virtualization_margin: 12,
+            text_only: false,
+            unavailable_artwork_url: None,
+            dedupe_tiles: false,
+            default_tile_aspect_ratio: 1.0,
+            reduce_motion: false,
+            selection_style: SelectionStyle::default(),
+            selection_animation: None,
+            pan_animation: None,
+            spotlight_dwell: None,
+            detail_pages: true,
+            player_stream_url: None,
+            tile_captions: true,
+            brand_chip_corner: Some(Corner::BottomRight),
+            animation_fps: None,
+            row_limit: None,
+            pinned_row: None,
+            pan_alignment: PanAlignment::MinimalReveal,
+            focus_margin: 0.0,
+            wrap_selection: false,
+            scroll_snap: ScrollSnap::default(),
+            keymap: KeyMap::default(),
+            tile_sort: HashMap::new(),
+            row_max_age: HashMap::new(),
+            favorites_path: Some(config_path("favorites.txt")),
+            seen_tiles_path: Some(config_path("seen_tiles.txt")),
+            window_size: Some(Size::new(1280.0, 720.0)),
+            window_size_path: Some(config_path("window_size.txt")),
+            settings_path: Some(config_path("settings.toml")),
+            profiles: Vec::new(),
+            profiles_dir: config_path("profiles"),
+            image_cache_dir: Some(cache_path("images")),
+            decoded_image_budget: 64 * 1024 * 1024,
+            load_mode: LoadMode::Background,
+            spinner_delay: Some(Duration::from_millis(300)),
+            lazy_load_margin: Some(400.0),
+            session_save_interval: Some(Duration::from_secs(2)),
+            background_workers: None,
+            image_workers: None,
+        };
+        config.set_api(DEFAULT_API_BASE_URL, DEFAULT_COLLECTION_ID);
+        config
+    }
+}
+
+impl AppConfig {
+    // Points the collection, set and search URLs at the collection `collection_id`
+    // served from `base_url`, like https://cd-static.bamgrid.com/dp-117731241344/home.json.
+    pub fn set_api(&mut self, base_url: &str, collection_id: &str) {
+        let root = format!("{}/{}", base_url.trim_end_matches('/'), collection_id);
+        self.collection_url = format!("{}/home.json", root);
+        self.set_url_template = format!("{}/sets/{{ref_id}}.json", root);
+        self.search_url_template = format!("{}/search/{{query}}.json", root);
+    }
+
+    pub fn selection_animation_enabled(&self) -> bool {
+        self.selection_animation.unwrap_or(!self.reduce_motion)
+    }
+
+    pub fn pan_animation_enabled(&self) -> bool {
+        self.pan_animation.unwrap_or(!self.reduce_motion)
+    }
+
+    // The config the grid runs with while `profile` is active: this one, with the
+    // profile's feed, and its favorites, seen tiles and settings saved under `profiles_dir`.
+    // The window size and image cache belong to the device, so they're shared.
+    pub fn for_profile(&self, profile: &Profile) -> AppConfig {
+        let profile_dir = self.profiles_dir.join(&profile.name);
+        let profile_path = |path: &Option<PathBuf>| {
+            path.as_ref()
+                .and_then(|path| path.file_name())
+                .map(|file_name| profile_dir.join(file_name))
+        };
+        AppConfig {
+            collection_url: profile
+                .collection_url
+                .clone()
+                .unwrap_or_else(|| self.collection_url.clone()),
+            favorites_path: profile_path(&self.favorites_path),
+            seen_tiles_path: profile_path(&self.seen_tiles_path),
+            settings_path: profile_path(&self.settings_path),
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        Self {
+            border: true,
+            scale: true,
+            glow: false,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            home_timeout: Duration::from_secs(30),
+            set_timeout: Duration::from_secs(10),
+            image_timeout: Duration::from_secs(10),
+            proxy: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl TileSort {
+    // The order the header's sort control cycles through.
+    pub fn next(self) -> Self {
+        match self {
+            TileSort::FeedOrder => TileSort::Title,
+            TileSort::Title => TileSort::Year,
+            TileSort::Year => TileSort::FeedOrder,
+        }
+    }
+}
+
+impl TextDirection {
+    pub fn is_rtl(self) -> bool {
+        self == TextDirection::RightToLeft
+    }
+}
@@ -1,16 +1,265 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
 use smallvec::{smallvec, SmallVec};
 use tracing::{trace_span, Span};
 
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
 use widget_cruncher::promise::PromiseToken;
 use widget_cruncher::widget::prelude::*;
-use widget_cruncher::widget::{AsWidgetPod, ClipBox, Flex, Label, SizedBox, Spinner, WidgetPod};
-use widget_cruncher::Point;
+use widget_cruncher::widget::{AsWidgetPod, Axis, ClipBox, Label, WidgetPod};
+use widget_cruncher::{theme, Command, Point, Rect, Selector, Target, TimerToken};
+
+use crate::api::{self, fetch_set, Lenient};
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, LoadMode, PanAlignment, TileSort};
+use crate::hit_map::SharedHitMap;
+use crate::net::{CancelHandle, FetchError};
+use crate::pan::{
+    center_clipbox_on, clamp_pan_offset, pan_clipbox_to, reveal_in_clipbox, scrolling_clipbox,
+};
+use crate::root_widget::ROW_SPACING;
+use crate::session::SharedSession;
+use crate::settings::animation_speed;
+use crate::thumbnail::{
+    FocusTarget, SharedSelection, CHANGE_FOCUS, CHANGE_SELECTED_ITEM, FOCUS_OUTLINE_COLOR,
+    FOCUS_OUTLINE_WIDTH, THUMBNAIL_OVERFLOW, UPDATE_LIVE_PROGRAMS,
+};
+use crate::tile_row::{
+    cell_x, TileRow, APPEND_TILES, REPLACE_FIRST_PAGE, SET_SORT, SET_WINDOW_CENTER,
+    SHOW_LOAD_ERROR, SHOW_UNAVAILABLE,
+};
+use crate::workers::{LoadPriority, WorkerPool};
+
+// Sent once the first page of a row has been resolved, whether it loaded or failed.
+// The payload is the row index, and its new phase.
+pub const ROW_LOADED: Selector<(usize, LoadPhase)> = Selector::new("row_loaded");
+
+// Sent by a row that only loads once it comes into view, in answer to
+// VISIBLE_AREA, while it's still out of view. The payload is the row index.
+pub const ROW_DEFERRED: Selector<usize> = Selector::new("row_deferred");
+
+// Sent once every page of a row has loaded, so that its tiles are the whole set.
+// The payload is the row index.
+pub const ROW_COMPLETE: Selector<usize> = Selector::new("row_complete");
+
+// Sent by the selected row with PanAlignment::Center, so that the RootWidget can
+// center it vertically. The payload is the y coordinate of the row's center, in
+// window coordinates.
+pub const CENTER_ROW: Selector<f64> = Selector::new("center_row");
+
+// Sent by the selected row with PanAlignment::MinimalReveal, so that the RootWidget
+// can bring it into view with `config.focus_margin`. The payload is the top and bottom of
+// the row, margin included, in window coordinates.
+// With pan animations, also sent by the selected row or header otherwise, without
+// a margin, since the selected Thumbnail doesn't bring itself into view then.
+pub const REVEAL_ROW: Selector<(f64, f64)> = Selector::new("reveal_row");
+
+// Sent by a ContentSet to itself once it's added, since it can't fetch its
+// first page from `lifecycle`.
+const LOAD_FIRST_PAGE: Selector = Selector::new("load_first_page");
+
+// Starts loading the first page of a row again, unless it already loaded.
+// The payload is the row index.
+pub const RELOAD_ROW: Selector<usize> = Selector::new("reload_row");
+
+// Sent by the RootWidget when which rows are in view may have changed. The
+// payload is the grid's rect, in window coordinates. Rows waiting to come into
+// view start loading when they're near it. See `AppConfig::lazy_load_margin`.
+pub const VISIBLE_AREA: Selector<Rect> = Selector::new("visible_area");
+
+// Sets the horizontal scroll offset of a row, regardless of the selection, eg to
+// restore a session or mirror another device. The payload is the row index, the
+// x offset (clamped to the row's content), and whether to animate the scroll
+// instead of jumping.
+pub const SCROLL_ROW: Selector<(usize, f64, bool)> = Selector::new("scroll_row");
+
+// Share of the remaining distance an animated SCROLL_ROW covers every 60th of a second.
+const SCROLL_ANIMATION_RATE: f64 = 0.2;
+
+// Asks a row to switch to the next TileSort. The payload is the row index.
+pub const CYCLE_SORT: Selector<usize> = Selector::new("cycle_sort");
+
+// Sent by the RootWidget when the user activates the selected tile. The payload
+// is its (row, column). The row answers with ACTIVATE_ITEM.
+pub const ACTIVATE_TILE: Selector<(usize, usize)> = Selector::new("activate_tile");
+
+// Sent by the RootWidget to look for the first tile that hasn't been activated yet.
+// Every row that has one answers with UNVISITED_TILE, before the RootWidget's own
+// FIND_UNVISITED_DONE.
+pub const FIND_UNVISITED: Selector = Selector::new("find_unvisited");
+pub const FIND_UNVISITED_DONE: Selector = Selector::new("find_unvisited_done");
+// The (row, column) of the first tile of a row that hasn't been activated yet.
+pub const UNVISITED_TILE: Selector<(usize, usize)> = Selector::new("unvisited_tile");
+
+// Sent by the RootWidget once the selection has settled on a tile, to have it
+// announced. The payload is its (row, column). The row answers with TILE_DESCRIPTION.
+pub const DESCRIBE_TILE: Selector<(usize, usize)> = Selector::new("describe_tile");
+
+// The text to announce for DESCRIBE_TILE.
+pub const TILE_DESCRIPTION: Selector<String> = Selector::new("tile_description");
+
+// Sent by the RootWidget to show the info overlay for a tile. The payload is its
+// (row, column). The row answers with TILE_INFO.
+pub const SHOW_TILE_INFO: Selector<(usize, usize)> = Selector::new("show_tile_info");
+
+// What the info overlay shows for SHOW_TILE_INFO.
+pub const TILE_INFO: Selector<TileInfo> = Selector::new("tile_info");
+
+#[derive(Clone, Debug)]
+pub struct TileInfo {
+    pub title: String,
+    // The full description from the feed.
+    pub description: Option<String>,
+}
+
+// Sent when the user activates a tile. The grid opens the tile's DetailPage,
+// unless `AppConfig::detail_pages` is off; it's also for the app embedding the grid.
+pub const ACTIVATE_ITEM: Selector<ActivatedItem> = Selector::new("activate_item");
+
+// Sent when the user activates a row's "See all" control. The payload is the
+// row index. Like ACTIVATE_ITEM, it's for the app embedding the grid.
+pub const SEE_ALL: Selector<usize> = Selector::new("see_all");
 
-use crate::thumbnail::{Thumbnail, THUMBNAIL_MAX_SIZE};
+// Sent by the RootWidget to collapse every row to its header, or to expand them back.
+pub const COLLAPSE_ROWS: Selector<bool> = Selector::new("collapse_rows");
 
+// Sent by the RootWidget for type-ahead within a row. The payload is the row,
+// and what was typed so far, in lowercase. The row answers with JUMP_TO_TILE
+// if one of its loaded tiles has a title starting with it.
+pub const FIND_TILE: Selector<(usize, String)> = Selector::new("find_tile");
+
+// Asks the RootWidget to select a tile, as (row, column).
+pub const JUMP_TO_TILE: Selector<(usize, usize)> = Selector::new("jump_to_tile");
+
+// Sent by the RootWidget once vertical scrolling has settled, with ScrollSnap::vertical.
+// The payload is the window y of the top of the grid's viewport. The row cut by
+// that edge answers with SNAP_GRID_TO.
+pub const SNAP_ROWS: Selector<f64> = Selector::new("snap_rows");
+
+// Asks the RootWidget to scroll so that this window y is at the top of its viewport.
+pub const SNAP_GRID_TO: Selector<f64> = Selector::new("snap_grid_to");
+
+// How long scrolling has to stop for before it snaps.
+pub const SCROLL_SNAP_DELAY: Duration = Duration::from_millis(150);
+
+// Controls drawn in a row's header, after its title.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeaderControl {
+    SeeAll,
+    Sort,
+}
+
+pub const HEADER_CONTROLS: [HeaderControl; 2] = [HeaderControl::SeeAll, HeaderControl::Sort];
+
+// How close to the end of the loaded tiles the selection can get before we
+// request the next page.
+const NEXT_PAGE_THRESHOLD: usize = 4;
+
+// Sets of this type hold live channels instead of on-demand titles.
+pub const LIVE_SET_TYPE: &str = "LiveChannelSet";
+
+// The row synthesized from the user's favorites. It's built locally instead of
+// being fetched.
+pub const FAVORITES_SET_TYPE: &str = "Favorites";
+
+const DESCRIPTION_TEXT_SIZE: f64 = 12.0;
+
+// How often a live row refreshes what's currently airing.
+const LIVE_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+// Where a row is in loading its first page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadPhase {
+    Pending,
+    Loaded,
+    Failed,
+}
+
+#[derive(Clone)]
 pub struct ContentSetMetadata {
     pub title: String,
+    // A short line shown under the title, for the sets that have one.
+    pub description: Option<String>,
     pub ref_id: String,
+    pub set_type: String,
+    // Promotional rows are only shown between these two times. Rows without
+    // them are always shown.
+    pub available_from: Option<SystemTime>,
+    pub available_until: Option<SystemTime>,
+    // Editorial hint to load this row before others, whatever its position.
+    // Higher goes first; rows without one count as 0.
+    pub load_priority: Option<i64>,
+    // The first page of the set, for CuratedSet containers that come with their
+    // items. SetRef containers only point to their set, which is fetched.
+    pub inline_set: Option<Arc<api::CuratedSet>>,
+}
+
+// The payload of ACTIVATE_ITEM.
+#[derive(Clone, Debug)]
+pub struct ActivatedItem {
+    pub row: usize,
+    pub column: usize,
+    // The set the tile was activated from, since the same title can be in several.
+    pub ref_id: String,
+    pub tile: TileMetadata,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileMetadata {
+    pub image_url: String,
+    // Which piece of content the tile is for. Several tiles can show the same
+    // content with different artwork.
+    pub content_id: Option<String>,
+    // Width / height of the image, from the dimensions of its master in the feed.
+    pub image_aspect_ratio: Option<f64>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub release_year: Option<u32>,
+    // Like "PG-13".
+    pub rating: Option<String>,
+    pub content_type: Option<ContentType>,
+    // Wide artwork shown at the top of the tile's DetailPage.
+    pub hero_image_url: Option<String>,
+    // Alternate artwork shown once the tile has been selected for a while,
+    // see `AppConfig::spotlight_dwell`.
+    pub preview_image_url: Option<String>,
+    // The sub-brand the tile belongs to, if the feed says.
+    pub brand: Option<TileBrand>,
+    // Only set for items of a live set.
+    pub live: Option<LiveAiring>,
+}
+
+// A sub-brand (Marvel, Pixar, ...), drawn as a chip in a corner of the tile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileBrand {
+    pub name: Option<String>,
+    // Drawn instead of the name when there is one.
+    pub logo_url: Option<String>,
+}
+
+// What kind of title a tile is for. Items of other types, like collections,
+// don't get one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentType {
+    Movie,
+    Series,
+}
+
+// What's airing right now on a live channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiveAiring {
+    pub program_title: Option<String>,
+}
+
+// A single page of a curated set.
+pub struct ContentSetPage {
+    pub tiles: Vec<TileMetadata>,
+    // How many items the page had, including the ones without a usable tile.
+    pub item_count: usize,
+    // Offset of the following page, if the set has more items than this page holds.
+    pub next_offset: Option<u64>,
 }
 
 pub struct ContentSet {
@@ -18,54 +267,924 @@ pub struct ContentSet {
 
     // We store which row is to pass to thumbnails
     pub row: usize,
+    pub config: Arc<AppConfig>,
+    pub selection: SharedSelection,
+    pub session: SharedSession,
+    pub workers: Arc<WorkerPool>,
+    // Passed to every load of the set, and cancelled when the row is dropped, so
+    // that a row that went away doesn't keep its requests running.
+    pub cancel: CancelHandle,
 
     // The promise token is mostly a type-system aid to "prove" to the compiler
     // that the result you're getting is the same you asked for earlier.
-    pub children_promise: PromiseToken<Vec<String>>,
+    // Every page of the set, including the first, is loaded through this token.
+    pub children_promise: PromiseToken<Result<ContentSetPage, FetchError>>,
+
+    // Pagination state. `next_offset` is None once every page has been loaded.
+    // `phase` only tracks the first page.
+    pub phase: LoadPhase,
+    pub tile_count: usize,
+    pub next_offset: Option<u64>,
+    pub loading_page: bool,
+    // Set while the first fetch waits for the row to come into view.
+    pub waiting_for_view: bool,
+
+    // Which part of this row's header has keyboard focus, as an index like
+    // in FocusTarget::RowHeader.
+    pub header_focus: Option<usize>,
+    // Changed with the header's sort control. Always FeedOrder for live rows.
+    pub sort: TileSort,
+    // Collapsed rows only show their header. See COLLAPSE_ROWS.
+    pub collapsed: bool,
+    // Which way the tiles scroll: along the row, or down a vertical rail.
+    pub axis: Axis,
+
+    // In right-to-left mode, the row starts scrolled to its right end once
+    // the first page has been laid out.
+    pub scroll_to_start: bool,
+    // Where an animated scroll is headed, from SCROLL_ROW or from centering the
+    // selected tile. Cleared when it gets there, or when the selection moves.
+    pub scroll_target: Option<f64>,
+    // The column in the middle of the viewport, as last sent with SET_WINDOW_CENTER.
+    pub viewport_column: Option<usize>,
+
+    // Live rows periodically reload their first page to update what's airing.
+    pub live_refresh_timer: TimerToken,
+    pub live_refresh_promise: PromiseToken<Result<ContentSetPage, FetchError>>,
+
+    // Rows with a `config.row_max_age` reload their first page when the selection
+    // enters them, if it's older than that. `first_page_len` is how many tiles
+    // that page had, so it can be swapped for the new one.
+    pub first_page_loaded_at: Option<Instant>,
+    pub first_page_len: usize,
+    pub stale_refresh_promise: PromiseToken<Result<ContentSetPage, FetchError>>,
+    pub has_selection: bool,
+    pub clock: SharedClock,
+    // Told where our tiles are every time we paint.
+    pub hit_map: SharedHitMap,
+
+    // Restarted by every wheel event, with ScrollSnap::horizontal.
+    pub snap_timer: TimerToken,
 
     // What's we're actually displaying.
-    pub children: WidgetPod<Flex>,
+    pub title: WidgetPod<Label>,
+    pub description: Option<WidgetPod<Label>>,
+    pub tiles: WidgetPod<ClipBox<TileRow>>,
 }
 
 // --- METHODS ---
 
 impl ContentSet {
-    pub fn new(row: usize, data: ContentSetMetadata) -> Self {
+    pub fn new(
+        row: usize,
+        data: ContentSetMetadata,
+        config: Arc<AppConfig>,
+        selection: SharedSelection,
+        session: SharedSession,
+        workers: Arc<WorkerPool>,
+        clock: SharedClock,
+        hit_map: SharedHitMap,
+    ) -> Self {
         let title_label = Label::new(data.title.clone());
-        let placeholder = SizedBox::new(Spinner::new())
-            .width(THUMBNAIL_MAX_SIZE / 2.0)
-            .height(THUMBNAIL_MAX_SIZE / 2.0);
+        // Smaller and dimmer than the title, so that it reads as secondary.
+        let description_label = data.description.clone().map(|description| {
+            Label::new(description)
+                .with_text_size(DESCRIPTION_TEXT_SIZE)
+                .with_text_color(theme::DISABLED_TEXT_COLOR)
+        });
+        // Live rows are refreshed by column, so their tiles must stay in feed order.
+        let sort = match config.tile_sort.get(&data.ref_id) {
+            Some(sort) if !data.is_live() => *sort,
+            _ => TileSort::FeedOrder,
+        };
+        let tile_row = TileRow::new(
+            row,
+            config.clone(),
+            selection.clone(),
+            session.clone(),
+            clock.clone(),
+        )
+        .with_sort(sort);
+        let tiles = scrolling_clipbox(tile_row, Axis::Horizontal);
         Self {
             row,
+            config,
+            selection,
+            session,
+            workers,
+            cancel: CancelHandle::default(),
             data,
             children_promise: PromiseToken::empty(),
-            children: WidgetPod::new(
-                Flex::column()
-                    .with_child(title_label)
-                    .with_child(placeholder),
-            ),
+            phase: LoadPhase::Pending,
+            tile_count: 0,
+            next_offset: None,
+            loading_page: false,
+            waiting_for_view: false,
+            header_focus: None,
+            sort,
+            collapsed: false,
+            axis: Axis::Horizontal,
+            scroll_to_start: false,
+            scroll_target: None,
+            viewport_column: None,
+            live_refresh_timer: TimerToken::INVALID,
+            live_refresh_promise: PromiseToken::empty(),
+            first_page_loaded_at: None,
+            first_page_len: 0,
+            stale_refresh_promise: PromiseToken::empty(),
+            has_selection: false,
+            hit_map,
+            clock,
+            snap_timer: TimerToken::INVALID,
+            title: WidgetPod::new(title_label),
+            description: description_label.map(WidgetPod::new),
+            tiles: WidgetPod::new(tiles),
+        }
+    }
+
+    // Used for rows built while the grid is collapsed.
+    pub fn with_collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self
+    }
+
+    // Makes the tiles scroll along `axis`. Only for rows that haven't been laid
+    // out yet, since their tiles are built again.
+    pub fn with_axis(mut self, axis: Axis) -> Self {
+        let tile_row = TileRow::new(
+            self.row,
+            self.config.clone(),
+            self.selection.clone(),
+            self.session.clone(),
+            self.clock.clone(),
+        )
+        .with_sort(self.sort);
+        self.tiles = WidgetPod::new(scrolling_clipbox(tile_row, axis));
+        self.axis = axis;
+        self
+    }
+
+    fn request_next_page(&mut self, ctx: &mut EventCtx) {
+        let offset = match self.next_offset {
+            Some(offset) if !self.loading_page => offset,
+            _ => return,
+        };
+        self.fetch_page(ctx, offset);
+    }
+
+    // Drops any page in flight and requests the first page again.
+    fn reload(&mut self, ctx: &mut EventCtx) {
+        self.phase = LoadPhase::Pending;
+        self.waiting_for_view = false;
+        self.fetch_page(ctx, 0);
+    }
+
+    fn load_priority(&self) -> LoadPriority {
+        LoadPriority::new(self.data.load_priority, self.row)
+    }
+
+    fn fetch_page(&mut self, ctx: &mut EventCtx, offset: u64) {
+        let content_set_url = content_set_url(&self.config, &self.data, offset);
+        let set_type = self.data.set_type.clone();
+        let dedupe = self.config.dedupe_tiles;
+
+        // An inline set is only used once: reloads fetch the set like any other.
+        if offset == 0 {
+            if let Some(set) = self.data.inline_set.take() {
+                let page = parse_set_page(&set, &set_type, dedupe, &self.data.ref_id);
+                self.on_page_loaded(ctx, Ok(page));
+                return;
+            }
+        }
+
+        self.loading_page = true;
+        self.load_page(
+            ctx,
+            content_set_url,
+            set_type,
+            |row| &mut row.children_promise,
+            Self::on_page_loaded,
+        );
+    }
+
+    fn refresh_live_programs(&mut self, ctx: &mut EventCtx) {
+        let content_set_url = content_set_url(&self.config, &self.data, 0);
+        self.load_page(
+            ctx,
+            content_set_url,
+            LIVE_SET_TYPE.to_string(),
+            |row| &mut row.live_refresh_promise,
+            Self::on_live_programs_loaded,
+        );
+    }
+
+    // Loads the set page at `content_set_url`. In LoadMode::Background, that's on
+    // the worker pool, and the page comes back through the promise `promise`
+    // returns. In LoadMode::Synchronous, it's passed to `on_loaded` right away.
+    fn load_page(
+        &mut self,
+        ctx: &mut EventCtx,
+        content_set_url: String,
+        set_type: String,
+        promise: fn(&mut Self) -> &mut PromiseToken<Result<ContentSetPage, FetchError>>,
+        on_loaded: fn(&mut Self, &mut EventCtx, Result<ContentSetPage, FetchError>),
+    ) {
+        let timeout = self.config.http.set_timeout;
+        let dedupe = self.config.dedupe_tiles;
+        match self.config.load_mode {
+            LoadMode::Background => {
+                let workers = self.workers.clone();
+                let priority = self.load_priority();
+                let cancel = self.cancel.clone();
+                *promise(self) = ctx.compute_in_background(move |_| {
+                    workers.run_with_priority(priority, || {
+                        load_content_set(&content_set_url, &set_type, dedupe, timeout, &cancel)
+                    })
+                });
+            }
+            LoadMode::Synchronous => {
+                let page =
+                    load_content_set(&content_set_url, &set_type, dedupe, timeout, &self.cancel);
+                on_loaded(self, ctx, page);
+            }
+        }
+    }
+
+    // Reloads the first page if it's older than the row's max age.
+    fn refresh_if_stale(&mut self, ctx: &mut EventCtx) {
+        let max_age = match self.config.row_max_age.get(&self.data.ref_id) {
+            Some(max_age) => *max_age,
+            None => return,
+        };
+        let loaded_at = match self.first_page_loaded_at {
+            Some(loaded_at) if self.phase == LoadPhase::Loaded => loaded_at,
+            _ => return,
+        };
+        if self.clock.now().saturating_duration_since(loaded_at) < max_age {
+            return;
+        }
+        // Counts as fresh while the reload is in flight, so it isn't requested twice.
+        self.first_page_loaded_at = Some(self.clock.now());
+
+        let content_set_url = content_set_url(&self.config, &self.data, 0);
+        let set_type = self.data.set_type.clone();
+        self.load_page(
+            ctx,
+            content_set_url,
+            set_type,
+            |row| &mut row.stale_refresh_promise,
+            Self::on_stale_refresh_loaded,
+        );
+    }
+
+    // Unlike the first load, a failed refresh keeps the tiles we have, and
+    // doesn't show a spinner or an error.
+    fn on_stale_refresh_loaded(
+        &mut self,
+        ctx: &mut EventCtx,
+        page: Result<ContentSetPage, FetchError>,
+    ) {
+        match page {
+            Ok(page) => {
+                self.first_page_loaded_at = Some(self.clock.now());
+                self.tile_count = self.tile_count - self.first_page_len + page.tiles.len();
+                let old_len = self.first_page_len;
+                self.first_page_len = page.tiles.len();
+                self.session
+                    .borrow_mut()
+                    .seen_tiles
+                    .record(page.tiles.iter().map(|tile| tile.image_url.as_str()));
+                ctx.submit_command(REPLACE_FIRST_PAGE.with((self.row, old_len, page.tiles)));
+            }
+            Err(err) => {
+                tracing::warn!("Failed to refresh set '{}': {}", self.data.ref_id, err);
+            }
+        }
+    }
+
+    fn on_page_loaded(&mut self, ctx: &mut EventCtx, page: Result<ContentSetPage, FetchError>) {
+        self.loading_page = false;
+        let first_page = self.phase == LoadPhase::Pending;
+        match page {
+            Ok(page) => {
+                if first_page {
+                    self.phase = LoadPhase::Loaded;
+                    self.first_page_loaded_at = Some(self.clock.now());
+                    self.first_page_len = page.tiles.len();
+                }
+                self.tile_count += page.tiles.len();
+                self.session
+                    .borrow_mut()
+                    .seen_tiles
+                    .record(page.tiles.iter().map(|tile| tile.image_url.as_str()));
+                self.next_offset = page.next_offset;
+                if self.next_offset.is_none() {
+                    ctx.submit_command(ROW_COMPLETE.with(self.row));
+                }
+                // The set has items, but we can't show any of them. That's different
+                // from an empty set, which legitimately shows nothing.
+                let unusable =
+                    page.tiles.is_empty() && page.item_count > 0 && page.next_offset.is_none();
+                // The TileRow builds the thumbnails once it gets this command.
+                ctx.submit_command(APPEND_TILES.with((self.row, page.tiles)));
+                if first_page && unusable {
+                    ctx.submit_command(SHOW_UNAVAILABLE.with(self.row));
+                }
+                if first_page && self.config.text_direction.is_rtl() {
+                    // We need the row to be laid out before we know where its end is.
+                    self.scroll_to_start = true;
+                    ctx.request_anim_frame();
+                }
+            }
+            Err(err) => {
+                tracing::error!("Failed to load set '{}': {}", self.data.ref_id, err);
+                if first_page {
+                    self.phase = LoadPhase::Failed;
+                    ctx.submit_command(SHOW_LOAD_ERROR.with((self.row, err.to_string())));
+                }
+            }
+        }
+        if first_page {
+            ctx.submit_command(ROW_LOADED.with((self.row, self.phase)));
+            if self.data.is_live() {
+                self.live_refresh_timer = ctx.request_timer(LIVE_REFRESH_INTERVAL);
+            }
+        }
+    }
+
+    fn on_live_programs_loaded(
+        &mut self,
+        ctx: &mut EventCtx,
+        page: Result<ContentSetPage, FetchError>,
+    ) {
+        match page {
+            Ok(page) => {
+                let programs = page
+                    .tiles
+                    .into_iter()
+                    .map(|tile| tile.live.and_then(|live| live.program_title))
+                    .collect();
+                ctx.submit_command(UPDATE_LIVE_PROGRAMS.with((self.row, programs)));
+            }
+            Err(err) => {
+                tracing::warn!("Failed to refresh live set '{}': {}", self.data.ref_id, err);
+            }
+        }
+        self.live_refresh_timer = ctx.request_timer(LIVE_REFRESH_INTERVAL);
+    }
+
+    // Draws the HEADER_CONTROLS as chips on the title's line, after the title.
+    // In the Env's text colors, so that they follow the theme like the title.
+    fn paint_header_controls(&self, ctx: &mut PaintCtx, env: &Env) {
+        const SPACING: f64 = 12.0;
+        const PADDING: f64 = 4.0;
+
+        let chip_color = env.get(&theme::TEXT_COLOR).with_alpha(0.125);
+
+        let title_rect = self.title.layout_rect();
+        let rtl = self.config.text_direction.is_rtl();
+        let mut x = if rtl {
+            title_rect.x0 - SPACING
+        } else {
+            title_rect.x1 + SPACING
+        };
+        for (index, control) in HEADER_CONTROLS.iter().enumerate() {
+            let (label, enabled) = match control {
+                HeaderControl::SeeAll => ("See all".to_string(), true),
+                HeaderControl::Sort => (
+                    format!("Sort: {}", sort_label(self.sort)),
+                    !self.data.is_live(),
+                ),
+            };
+            let text_color = if enabled {
+                env.get(&theme::TEXT_COLOR)
+            } else {
+                env.get(&theme::DISABLED_TEXT_COLOR)
+            };
+            let text = ctx
+                .text()
+                .new_text_layout(label)
+                .font(FontFamily::SYSTEM_UI, DESCRIPTION_TEXT_SIZE)
+                .text_color(text_color)
+                .build()
+                .unwrap();
+            let text_size = text.size();
+            let chip_width = text_size.width + 2.0 * PADDING;
+            let chip_x = if rtl { x - chip_width } else { x };
+            let chip_rect = Rect::from_origin_size(
+                Point::new(
+                    chip_x,
+                    title_rect.center().y - text_size.height / 2.0 - PADDING,
+                ),
+                Size::new(chip_width, text_size.height + 2.0 * PADDING),
+            );
+            ctx.fill(chip_rect.to_rounded_rect(4.0), &chip_color);
+            ctx.draw_text(&text, chip_rect.origin() + (PADDING, PADDING));
+            if self.header_focus == Some(index + 1) {
+                ctx.stroke(
+                    chip_rect.to_rounded_rect(4.0),
+                    &FOCUS_OUTLINE_COLOR,
+                    FOCUS_OUTLINE_WIDTH,
+                );
+            }
+            if rtl {
+                x -= chip_width + SPACING;
+            } else {
+                x += chip_width + SPACING;
+            }
+        }
+    }
+
+    // Pans the row so that `x` is at the left edge of its viewport, without
+    // scrolling past either end of the row.
+    fn pan_row_to(&mut self, ctx: &mut EventCtx, x: f64) {
+        let mut moved = false;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                moved = pan_clipbox_to(clipbox, Point::new(x, 0.0));
+            });
+        if moved {
+            ctx.request_paint();
+        }
+    }
+
+    // Pans the row by `delta_x`, for the mouse wheel. This interrupts any
+    // animated scroll.
+    fn scroll_row_by(&mut self, ctx: &mut EventCtx, delta_x: f64) {
+        self.scroll_target = None;
+        let mut moved = false;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                let x = clipbox.viewport_origin().x + delta_x;
+                moved = pan_clipbox_to(clipbox, Point::new(x, 0.0));
+            });
+        if moved {
+            ctx.request_paint();
+        }
+    }
+
+    // The width of the row's cells, as of its last layout.
+    fn cell_size(&self) -> f64 {
+        self.tiles.widget().child.widget().cell_size
+    }
+
+    // Tells the TileRow which column is in the middle of the viewport, if that
+    // changed since the last time. However the row was panned, only the tiles
+    // near what's on screen have widgets.
+    fn track_viewport(&mut self, ctx: &mut EventCtx) {
+        let clipbox = self.tiles.widget();
+        let tile_count = clipbox.child.widget().tile_data.len();
+        if tile_count == 0 {
+            return;
+        }
+        let center_x =
+            clipbox.viewport_origin().x + clipbox.viewport_size().width / 2.0 - THUMBNAIL_OVERFLOW;
+        let cell = ((center_x / self.cell_size()).max(0.0) as usize).min(tile_count - 1);
+        // Same as `cell_x`, the other way around.
+        let column = if self.config.text_direction.is_rtl() {
+            tile_count - cell - 1
+        } else {
+            cell
+        };
+        if self.viewport_column != Some(column) {
+            self.viewport_column = Some(column);
+            ctx.submit_command(SET_WINDOW_CENTER.with((self.row, column)));
+        }
+    }
+
+    // Moves the row one frame closer to `scroll_target`. `interval` is the time since
+    // the last frame, in nanoseconds.
+    fn step_scroll_animation(&mut self, ctx: &mut EventCtx, interval: u64) {
+        let target_x = match self.scroll_target {
+            Some(target_x) => target_x,
+            None => return,
+        };
+        let progress = 1.0 - (1.0 - SCROLL_ANIMATION_RATE).powf(interval as f64 * 60.0 / 1e9);
+        let mut arrived = false;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                let target = clamp_pan_offset(
+                    Point::new(target_x, 0.0),
+                    clipbox.content_size(),
+                    clipbox.viewport_size(),
+                );
+                let current_x = clipbox.viewport_origin().x;
+                let x = if (target.x - current_x).abs() < 0.5 {
+                    arrived = true;
+                    target.x
+                } else {
+                    current_x + (target.x - current_x) * progress
+                };
+                pan_clipbox_to(clipbox, Point::new(x, 0.0));
+            });
+        ctx.request_paint();
+        if arrived {
+            self.scroll_target = None;
+        } else {
+            ctx.request_anim_frame();
+        }
+    }
+
+    // Pans the row so that the tile at `column` is in the middle of the viewport,
+    // and asks the RootWidget to do the same with the row itself.
+    fn center_on_column(&mut self, ctx: &mut EventCtx, column: usize) {
+        let rtl = self.config.text_direction.is_rtl();
+        let cell_size = self.cell_size();
+        let center_x =
+            THUMBNAIL_OVERFLOW + cell_x(column, self.tile_count, cell_size, rtl) + cell_size / 2.0;
+        if self.config.pan_animation_enabled() {
+            let mut viewport_width = 0.0;
+            self.tiles
+                .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                    viewport_width = clipbox.viewport_size().width;
+                });
+            self.scroll_target = Some(center_x - viewport_width / 2.0);
+            ctx.request_anim_frame();
+        } else {
+            let mut moved = false;
+            self.tiles
+                .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                    moved = center_clipbox_on(clipbox, Point::new(center_x, 0.0));
+                });
+            if moved {
+                ctx.request_paint();
+            }
+        }
+
+        let center_y = ctx.window_origin().y + ctx.size().height / 2.0;
+        ctx.submit_command(CENTER_ROW.with(center_y));
+    }
+
+    // Tells the hit map where our tiles were just painted. Parts of tiles scrolled
+    // out of the row's viewport don't count.
+    fn register_tiles(&self, ctx: &mut PaintCtx) {
+        let mut tiles = Vec::new();
+        if !self.collapsed {
+            let viewport = self.tiles.layout_rect();
+            let clipbox = self.tiles.widget();
+            let offset = viewport.origin().to_vec2() - clipbox.viewport_origin().to_vec2()
+                + ctx.window_origin().to_vec2();
+            let window_viewport = viewport + ctx.window_origin().to_vec2();
+            for (column, rect) in clipbox.child.widget().tile_rects() {
+                let rect = (rect + offset).intersect(window_viewport);
+                if rect.area() > 0.0 {
+                    tiles.push((column, rect));
+                }
+            }
+        }
+        self.hit_map.borrow_mut().set_row(self.row, tiles);
+    }
+
+    // Pans the row, and asks the RootWidget to pan the grid, just enough for the
+    // tile at `column` to have `config.focus_margin` of room on every side.
+    // The span includes THUMBNAIL_OVERFLOW, so that the tile is fully visible
+    // once it has grown to its selected size.
+    fn reveal_column(&mut self, ctx: &mut EventCtx, column: usize) {
+        let margin = self.config.focus_margin;
+        let rtl = self.config.text_direction.is_rtl();
+        let cell_size = self.cell_size();
+        let x = THUMBNAIL_OVERFLOW + cell_x(column, self.tile_count, cell_size, rtl);
+        let tile_span = Rect::new(
+            x - THUMBNAIL_OVERFLOW - margin,
+            0.0,
+            x + cell_size + THUMBNAIL_OVERFLOW + margin,
+            0.0,
+        );
+        let mut moved = false;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                moved = reveal_in_clipbox(clipbox, tile_span);
+            });
+        if moved {
+            ctx.request_paint();
+        }
+
+        let top = ctx.window_origin().y;
+        let bottom = top + ctx.size().height;
+        ctx.submit_command(REVEAL_ROW.with((top - margin, bottom + margin)));
+    }
+
+    // Snaps the row to the screen of tiles holding `column`. A screen is as many
+    // whole tiles as fit the viewport, so screens change when the window is resized.
+    fn show_page_of(&mut self, ctx: &mut EventCtx, column: usize) {
+        let mut viewport_width = 0.0;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                viewport_width = clipbox.viewport_size().width;
+            });
+        let x = page_offset(
+            column,
+            self.tile_count,
+            self.cell_size(),
+            viewport_width,
+            self.config.text_direction.is_rtl(),
+        );
+        self.scroll_row_to(ctx, x);
+        if self.config.pan_animation_enabled() {
+            self.reveal_row(ctx);
+        }
+    }
+
+    // Asks the RootWidget to bring the whole row into view.
+    fn reveal_row(&self, ctx: &mut EventCtx) {
+        let top = ctx.window_origin().y;
+        let bottom = top + ctx.size().height;
+        ctx.submit_command(REVEAL_ROW.with((top, bottom)));
+    }
+
+    // Scrolls the row to the nearest tile boundary, counted from the start of the row.
+    fn snap_to_tile(&mut self, ctx: &mut EventCtx) {
+        let mut x = 0.0;
+        let mut viewport_width = 0.0;
+        let mut content_width = 0.0;
+        self.tiles
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                x = clipbox.viewport_origin().x;
+                viewport_width = clipbox.viewport_size().width;
+                content_width = clipbox.content_size().width;
+            });
+        let cell_size = self.cell_size();
+        let target = if self.config.text_direction.is_rtl() {
+            // The row starts on the right, so boundaries are counted from its right end.
+            let from_end = content_width - (x + viewport_width);
+            let snapped = (from_end / cell_size).round() * cell_size;
+            content_width - viewport_width - snapped
+        } else {
+            (x / cell_size).round() * cell_size
+        };
+        if (target - x).abs() >= 0.5 {
+            self.scroll_row_to(ctx, target);
+        }
+    }
+
+    // Like `pan_row_to`, but animated if pan animations are on.
+    fn scroll_row_to(&mut self, ctx: &mut EventCtx, x: f64) {
+        if self.config.pan_animation_enabled() {
+            self.scroll_target = Some(x);
+            ctx.request_anim_frame();
+        } else {
+            self.pan_row_to(ctx, x);
+        }
+    }
+}
+
+// How many whole tiles fit in a row viewport `viewport_width` wide, with cells
+// `cell_size` wide. Always at least one, so that a narrow window still pages
+// tile by tile.
+pub fn tiles_per_page(viewport_width: f64, cell_size: f64) -> usize {
+    let usable_width = viewport_width - THUMBNAIL_OVERFLOW * 2.0;
+    (usable_width / cell_size).floor().max(1.0) as usize
+}
+
+// The scroll offset of the screen of tiles holding `column`, in a row of
+// `cell_count` cells viewed through a viewport `viewport_width` wide.
+fn page_offset(
+    column: usize,
+    cell_count: usize,
+    cell_size: f64,
+    viewport_width: f64,
+    rtl: bool,
+) -> f64 {
+    let page_size = tiles_per_page(viewport_width, cell_size);
+    let first = (column / page_size) * page_size;
+    if rtl {
+        // The page grows leftwards from its first tile, so line up its right edge.
+        let right_edge =
+            THUMBNAIL_OVERFLOW * 2.0 + cell_x(first, cell_count, cell_size, true) + cell_size;
+        right_edge - viewport_width
+    } else {
+        cell_x(first, cell_count, cell_size, false)
+    }
+}
+
+fn sort_label(sort: TileSort) -> &'static str {
+    match sort {
+        TileSort::FeedOrder => "Featured",
+        TileSort::Title => "A-Z",
+        TileSort::Year => "Newest",
+    }
+}
+
+// Where a set is fetched from, according to `config.set_url_template`.
+pub fn set_url(config: &AppConfig, ref_id: &str, set_type: &str) -> String {
+    config
+        .set_url_template
+        .replace("{ref_id}", ref_id)
+        .replace("{set_type}", set_type)
+}
+
+// The page of a set starting at its `offset`th item.
+pub fn content_set_url(config: &AppConfig, data: &ContentSetMetadata, offset: u64) -> String {
+    let url = set_url(config, &data.ref_id, &data.set_type);
+    if offset == 0 {
+        url
+    } else {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!("{}{}offset={}", url, separator, offset)
+    }
+}
+
+impl ContentSetMetadata {
+    pub fn is_live(&self) -> bool {
+        self.set_type == LIVE_SET_TYPE
+    }
+
+    pub fn is_favorites(&self) -> bool {
+        self.set_type == FAVORITES_SET_TYPE
+    }
+
+    pub fn has_availability_window(&self) -> bool {
+        self.available_from.is_some() || self.available_until.is_some()
+    }
+
+    // The window includes its start, but not its end.
+    pub fn is_available_at(&self, now: SystemTime) -> bool {
+        self.available_from.map_or(true, |from| from <= now)
+            && self.available_until.map_or(true, |until| now < until)
+    }
+}
+
+// Returns the tile of an item of a set, or None if the item has no usable tile.
+pub fn parse_tile(item: &api::Tile) -> Option<TileMetadata> {
+    // Just take the first suggested tile.
+    let tile = &item.image.tile.values().next()?.get("program")?.default;
+    let tile_url = tile.url.clone()?;
+    let image_aspect_ratio = match (tile.master_width, tile.master_height) {
+        (Some(width), Some(height)) if width > 0.0 && height > 0.0 => Some(width / height),
+        _ => None,
+    };
+    let first_text = |field: &Option<api::TextField>| {
+        field
+            .as_ref()
+            .and_then(|field| field.first())
+            .map(|text| text.to_string())
+    };
+    let title = first_text(&item.text.title);
+    let description = first_text(&item.text.description);
+    let content_id = item
+        .content_id
+        .clone()
+        .or_else(|| item.encoded_series_id.clone());
+    let release_year = item
+        .releases
+        .first()
+        .and_then(|release| release.release_year);
+    let rating = item.ratings.first().and_then(|rating| rating.value.clone());
+    let content_type = item
+        .item_type
+        .as_deref()
+        .and_then(ContentType::from_item_type);
+    let preview_image_url =
+        api::first_image(&item.image.background).and_then(|background| background.url.clone());
+    let hero_image_url = api::first_image(&item.image.hero_collection)
+        .and_then(|hero| hero.url.clone())
+        .or_else(|| preview_image_url.clone());
+    let brand = parse_brand(item);
+
+    Some(TileMetadata {
+        image_url: tile_url,
+        content_id,
+        image_aspect_ratio,
+        title,
+        description,
+        release_year,
+        rating,
+        content_type,
+        hero_image_url,
+        preview_image_url,
+        brand,
+        live: None,
+    })
+}
+
+fn parse_brand(item: &api::Tile) -> Option<TileBrand> {
+    let name = item
+        .text
+        .brand
+        .as_ref()
+        .and_then(|brand| brand.first())
+        .map(|name| name.to_string());
+    let logo_url = api::first_image(&item.image.logo).and_then(|logo| logo.url.clone());
+    if name.is_none() && logo_url.is_none() {
+        return None;
+    }
+    Some(TileBrand { name, logo_url })
+}
+
+impl TileMetadata {
+    // What identifies the tile's content. Tiles without a content id are only
+    // the same if they have the same image.
+    pub fn content_key(&self) -> &str {
+        self.content_id.as_deref().unwrap_or(&self.image_url)
+    }
+
+    // Like "Series · PG-13", or None if we know neither.
+    pub fn caption_details(&self) -> Option<String> {
+        let content_type = self.content_type.map(|content_type| content_type.label());
+        let details: Vec<&str> = content_type
+            .into_iter()
+            .chain(self.rating.as_deref())
+            .collect();
+        if details.is_empty() {
+            None
+        } else {
+            Some(details.join(" \u{b7} "))
+        }
+    }
+}
+
+impl ContentType {
+    fn from_item_type(item_type: &str) -> Option<Self> {
+        match item_type {
+            "DmcVideo" => Some(ContentType::Movie),
+            "DmcSeries" => Some(ContentType::Series),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ContentType::Movie => "Movie",
+            ContentType::Series => "Series",
         }
     }
 }
 
-// Loads and parses "https://cd-static.bamgrid.com/dp-117731241344/sets/<refId>.json"
-fn load_content_set(url: &str) -> Result<Vec<String>, reqwest::Error> {
-    let json: serde_json::Value = reqwest::blocking::get(url)?.json()?;
-    let items = json["data"]["CuratedSet"]["items"].clone();
-    let items_tiles = items
-        .as_array()
-        .unwrap_or(&vec![])
+// Items of live sets describe the program currently airing on the channel.
+pub fn parse_live_airing(item: &api::Tile) -> LiveAiring {
+    let program_title = item
+        .current_airing
+        .as_ref()
+        .and_then(|airing| airing.text.title.as_ref())
+        .and_then(|title| title.get("program"))
+        .map(|title| title.to_string());
+    LiveAiring { program_title }
+}
+
+// Loads and parses a page of a set, from `content_set_url`. Each type of set
+// (CuratedSet, TrendingSet, PersonalizedCuratedSet, ...) is under its own key
+// in the document, but they all list their items the same way.
+// With `dedupe`, items whose content already appeared earlier in the page are
+// dropped, see `AppConfig::dedupe_tiles`.
+pub fn load_content_set(
+    url: &str,
+    set_type: &str,
+    dedupe: bool,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<ContentSetPage, FetchError> {
+    // A set without items is most likely a different type of set than we
+    // expected, so it's reported as an error rather than shown as empty.
+    // An empty `items` array is fine.
+    let set = fetch_set(url, set_type, timeout, cancel)?;
+    Ok(parse_set_page(&set, set_type, dedupe, url))
+}
+
+// Same as `load_content_set`, for a set that's already been fetched. `source`
+// is only used in logs.
+pub fn parse_set_page(
+    set: &api::CuratedSet,
+    set_type: &str,
+    dedupe: bool,
+    source: &str,
+) -> ContentSetPage {
+    let live = set_type == LIVE_SET_TYPE;
+    let mut seen_content = HashSet::new();
+    let items_tiles = set
+        .items
         .iter()
         .filter_map(|item| {
-            let tileset = item["image"]["tile"].clone();
-            // Just take the first suggested tile.
-            let tile = tileset.as_object().unwrap().values().next()?;
-            let tile_url = tile["program"]["default"]["url"].as_str()?.to_string();
-
-            Some(tile_url)
+            let item = match item {
+                Lenient::Parsed(item) => item,
+                Lenient::Malformed(_) => {
+                    tracing::warn!("Skipping malformed item of {}", source);
+                    return None;
+                }
+            };
+            let mut tile = parse_tile(item)?;
+            if live {
+                tile.live = Some(parse_live_airing(item));
+            }
+            Some(tile)
         })
+        .filter(|tile| !dedupe || seen_content.insert(tile.content_key().to_string()))
         .collect::<Vec<_>>();
-    Ok(items_tiles)
+
+    // The set metadata tells us how many items exist in total, and where
+    // this page starts.
+    let meta = &set.meta;
+    let next_offset = match (meta.hits, meta.offset, meta.page_size) {
+        (Some(hits), Some(offset), Some(page_size)) if offset + page_size < hits => {
+            Some(offset + page_size)
+        }
+        _ => None,
+    };
+
+    ContentSetPage {
+        tiles: items_tiles,
+        item_count: set.items.len(),
+        next_offset,
+    }
 }
 
 // --- TRAIT IMPL ---
@@ -76,75 +1195,395 @@ impl Widget for ContentSet {
         match event {
             // This happens after the callback passed to `ctx.compute_in_background` returns
             Event::PromiseResult(result) => {
-                if let Some(children) = result.try_get(self.children_promise) {
-                    let row = self.row;
-                    let title = self.data.title.clone();
-                    self.children.recurse_pass(
-                        "custom_pass",
-                        &mut ctx.widget_state,
-                        // flex is an alias of self.children in this closure
-                        |flex, flex_state| {
-                            flex.clear(flex_state);
-                            flex.add_child(flex_state, Label::new(title));
-                            let mut titles = Flex::row();
-                            for (column, child) in children.into_iter().enumerate() {
-                                titles = titles.with_child(Thumbnail::new(row, column, child));
+                if let Some(page) = result.try_get(self.children_promise) {
+                    self.on_page_loaded(ctx, page);
+                    ctx.skip_child(&mut self.title);
+                    if let Some(description) = &mut self.description {
+                        ctx.skip_child(description);
+                    }
+                    ctx.skip_child(&mut self.tiles);
+                    return;
+                }
+                if let Some(page) = result.try_get(self.stale_refresh_promise) {
+                    self.on_stale_refresh_loaded(ctx, page);
+                    ctx.skip_child(&mut self.title);
+                    if let Some(description) = &mut self.description {
+                        ctx.skip_child(description);
+                    }
+                    ctx.skip_child(&mut self.tiles);
+                    return;
+                }
+                if let Some(page) = result.try_get(self.live_refresh_promise) {
+                    self.on_live_programs_loaded(ctx, page);
+                    ctx.skip_child(&mut self.title);
+                    if let Some(description) = &mut self.description {
+                        ctx.skip_child(description);
+                    }
+                    ctx.skip_child(&mut self.tiles);
+                    return;
+                }
+            }
+            Event::Command(command) if command.is(LOAD_FIRST_PAGE) => {
+                self.fetch_page(ctx, 0);
+            }
+            Event::Timer(token) if *token == self.live_refresh_timer => {
+                self.refresh_live_programs(ctx);
+            }
+            Event::Timer(token) if *token == self.snap_timer => {
+                self.snap_timer = TimerToken::INVALID;
+                self.snap_to_tile(ctx);
+            }
+            // Wheels only scroll vertically, so Shift turns them sideways.
+            // Trackpads can scroll sideways on their own.
+            Event::Wheel(mouse) => {
+                let delta_x = if mouse.mods.shift() {
+                    mouse.wheel_delta.x + mouse.wheel_delta.y
+                } else {
+                    mouse.wheel_delta.x
+                };
+                if delta_x != 0.0 {
+                    self.scroll_row_by(ctx, delta_x);
+                    if self.config.scroll_snap.horizontal {
+                        self.snap_timer = ctx.request_timer(SCROLL_SNAP_DELAY);
+                    }
+                }
+            }
+            Event::AnimFrame(_) if self.scroll_to_start => {
+                self.scroll_to_start = false;
+                self.pan_row_to(ctx, f64::INFINITY);
+            }
+            Event::AnimFrame(interval) if self.scroll_target.is_some() => {
+                let interval = *interval as f64 * animation_speed(env);
+                self.step_scroll_animation(ctx, interval as u64);
+            }
+            Event::Command(command) => {
+                if let Some(area) = command.try_get(VISIBLE_AREA) {
+                    let margin = self.config.lazy_load_margin.unwrap_or(0.0);
+                    let top = ctx.window_origin().y - margin;
+                    let bottom = ctx.window_origin().y + ctx.size().height + margin;
+                    if self.waiting_for_view {
+                        if top < area.y1 && bottom > area.y0 {
+                            self.waiting_for_view = false;
+                            self.fetch_page(ctx, 0);
+                        } else {
+                            ctx.submit_command(ROW_DEFERRED.with(self.row));
+                        }
+                    }
+                }
+                if let Some(row) = command.try_get(RELOAD_ROW) {
+                    if *row == self.row && self.phase != LoadPhase::Loaded {
+                        self.reload(ctx);
+                    }
+                }
+                if let Some((row, x, animate)) = command.try_get(SCROLL_ROW) {
+                    if *row == self.row {
+                        self.scroll_to_start = false;
+                        if *animate && self.config.pan_animation_enabled() {
+                            self.scroll_target = Some(*x);
+                            ctx.request_anim_frame();
+                        } else {
+                            self.scroll_target = None;
+                            self.pan_row_to(ctx, *x);
+                        }
+                    }
+                }
+                if command.is(CHANGE_SELECTED_ITEM) {
+                    let had_selection = self.has_selection;
+                    self.has_selection =
+                        matches!(self.selection.get(), Some((row, _)) if row == self.row);
+                    if self.has_selection && !had_selection {
+                        self.refresh_if_stale(ctx);
+                    }
+                    match self.selection.get() {
+                        Some((row, column)) if row == self.row => {
+                            self.scroll_target = None;
+                            if column + NEXT_PAGE_THRESHOLD >= self.tile_count {
+                                self.request_next_page(ctx);
                             }
-                            flex.add_child(
-                                flex_state,
-                                ClipBox::new(titles).constrain_vertical(true),
+                            if column < self.tile_count {
+                                // The row pans itself rather than relying on the
+                                // Thumbnail's `request_pan_to_this`, since the
+                                // thumbnail may not exist yet: the TileRow only
+                                // creates it on this same command.
+                                match self.config.pan_alignment {
+                                    PanAlignment::MinimalReveal => self.reveal_column(ctx, column),
+                                    PanAlignment::Center => self.center_on_column(ctx, column),
+                                    PanAlignment::Paged => self.show_page_of(ctx, column),
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(focus) = command.try_get(CHANGE_FOCUS) {
+                    let header_focus = match *focus {
+                        FocusTarget::RowHeader(row, index) if row == self.row => Some(index),
+                        _ => None,
+                    };
+                    if header_focus != self.header_focus {
+                        self.header_focus = header_focus;
+                        ctx.request_paint();
+                        // With the tiles hidden, there's no selected tile to scroll
+                        // the grid to, so the header brings itself into view.
+                        if self.collapsed && header_focus.is_some() {
+                            if self.config.pan_animation_enabled() {
+                                self.reveal_row(ctx);
+                            } else {
+                                ctx.request_pan_to_this();
+                            }
+                        }
+                    }
+                }
+                if let Some(viewport_top) = command.try_get(SNAP_ROWS) {
+                    let top = ctx.window_origin().y;
+                    let bottom = top + ctx.size().height;
+                    if top < *viewport_top && *viewport_top < bottom {
+                        // Whichever of this row and the next shows more of itself.
+                        let target = if *viewport_top - top < (bottom - top) / 2.0 {
+                            top
+                        } else {
+                            bottom + ROW_SPACING
+                        };
+                        ctx.submit_command(SNAP_GRID_TO.with(target));
+                    }
+                }
+                if let Some(collapsed) = command.try_get(COLLAPSE_ROWS) {
+                    if *collapsed != self.collapsed {
+                        self.collapsed = *collapsed;
+                        ctx.request_layout();
+                    }
+                }
+                if let Some((row, prefix)) = command.try_get(FIND_TILE) {
+                    if *row == self.row {
+                        let tile_row = self.tiles.widget().child.widget();
+                        let column = tile_row.tile_data.iter().position(|tile| {
+                            tile.title
+                                .as_ref()
+                                .map_or(false, |title| title.to_lowercase().starts_with(prefix))
+                        });
+                        if let Some(column) = column {
+                            ctx.submit_command(JUMP_TO_TILE.with((*row, column)));
+                        }
+                    }
+                }
+                if let Some((row, column)) = command.try_get(DESCRIBE_TILE) {
+                    if *row == self.row {
+                        let tile_row = self.tiles.widget().child.widget();
+                        if let Some(tile) = tile_row.tile_data.get(*column) {
+                            let position = format!(
+                                "{} of {} in {}",
+                                column + 1,
+                                self.tile_count,
+                                self.data.title
                             );
-                            // when this closure returns, the framework automatically merges
-                            // invalidated state
-                        },
-                    );
-
-                    ctx.skip_child(&mut self.children);
-                    return;
+                            let description = match &tile.title {
+                                Some(title) => format!("{}, {}", title, position),
+                                None => format!("Thumbnail {}", position),
+                            };
+                            ctx.submit_command(TILE_DESCRIPTION.with(description));
+                        }
+                    }
+                }
+                if let Some((row, column)) = command.try_get(SHOW_TILE_INFO) {
+                    if *row == self.row {
+                        let tile_row = self.tiles.widget().child.widget();
+                        if let Some(tile) = tile_row.tile_data.get(*column) {
+                            ctx.submit_command(
+                                TILE_INFO.with(TileInfo {
+                                    title: tile
+                                        .title
+                                        .clone()
+                                        .unwrap_or_else(|| self.data.title.clone()),
+                                    description: tile.description.clone(),
+                                }),
+                            );
+                        }
+                    }
+                }
+                if let Some((row, column)) = command.try_get(ACTIVATE_TILE) {
+                    if *row == self.row {
+                        // The TileRow has the tiles in display order, which is what
+                        // the column refers to.
+                        let tile_row = self.tiles.widget().child.widget();
+                        if let Some(tile) = tile_row.tile_data.get(*column) {
+                            self.session
+                                .borrow_mut()
+                                .visited_tiles
+                                .insert(tile.image_url.clone());
+                            ctx.submit_command(ACTIVATE_ITEM.with(ActivatedItem {
+                                row: *row,
+                                column: *column,
+                                ref_id: self.data.ref_id.clone(),
+                                tile: tile.clone(),
+                            }));
+                        }
+                    }
+                }
+                if command.is(FIND_UNVISITED) {
+                    // Like ACTIVATE_TILE, in display order.
+                    let tile_row = self.tiles.widget().child.widget();
+                    let session = self.session.borrow();
+                    let column = tile_row
+                        .tile_data
+                        .iter()
+                        .position(|tile| !session.visited_tiles.contains(&tile.image_url));
+                    if let Some(column) = column {
+                        ctx.submit_command(UNVISITED_TILE.with((self.row, column)));
+                    }
+                }
+                if let Some(row) = command.try_get(CYCLE_SORT) {
+                    // Live rows are refreshed by column, so they can't be sorted.
+                    if *row == self.row && !self.data.is_live() {
+                        self.sort = self.sort.next();
+                        ctx.submit_command(SET_SORT.with((self.row, self.sort)));
+                        ctx.request_paint();
+                    }
                 }
             }
             _ => {}
         }
-        self.children.on_event(ctx, event, env)
+        self.title.on_event(ctx, event, env);
+        if let Some(description) = &mut self.description {
+            description.on_event(ctx, event, env);
+        }
+        self.tiles.on_event(ctx, event, env);
+        self.track_viewport(ctx);
     }
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
-        let content_set_url = format!(
-            "https://cd-static.bamgrid.com/dp-117731241344/sets/{}.json",
-            self.data.ref_id
-        );
-
         ctx.init();
         match event {
             // This is essentially a second constructor.
             // Bit of an anti-pattern, IMO, but I haven't yet found a workaround.
+            LifeCycle::WidgetAdded if self.data.is_favorites() => {
+                // Saved favorites are already on disk, so the row loads right away.
+                // It doesn't change until the next load.
+                let tiles = self.session.borrow().favorites.tiles.clone();
+                self.tile_count = tiles.len();
+                self.phase = LoadPhase::Loaded;
+                ctx.submit_command(APPEND_TILES.with((self.row, tiles)));
+                ctx.submit_command(ROW_LOADED.with((self.row, self.phase)));
+                ctx.submit_command(ROW_COMPLETE.with(self.row));
+            }
+            LifeCycle::WidgetAdded
+                if self.data.inline_set.is_none() && self.config.lazy_load_margin.is_some() =>
+            {
+                self.waiting_for_view = true;
+            }
+            // Pages are fetched from `on_event`, where LoadMode::Synchronous can
+            // handle them right away. Inline sets are already loaded, but go
+            // through the same path.
             LifeCycle::WidgetAdded => {
-                self.children_promise =
-                    ctx.compute_in_background(move |_| load_content_set(&content_set_url).unwrap());
+                ctx.submit_command(
+                    Command::from(LOAD_FIRST_PAGE).to(Target::Widget(ctx.widget_id())),
+                );
             }
             _ => {}
         }
-        self.children.lifecycle(ctx, event, env)
+        self.title.lifecycle(ctx, event, env);
+        if let Some(description) = &mut self.description {
+            description.lifecycle(ctx, event, env);
+        }
+        self.tiles.lifecycle(ctx, event, env);
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
-        let layout = self.children.layout(ctx, bc, env);
-        self.children.set_origin(ctx, env, Point::ORIGIN);
-        layout
+        let title_size = self.title.layout(ctx, &bc.loosen(), env);
+        let description_size = match &mut self.description {
+            Some(description) => description.layout(ctx, &bc.loosen(), env),
+            None => Size::ZERO,
+        };
+        let header_height = title_size.height + description_size.height;
+
+        // The row is as tall as it needs to be; the ClipBox takes care of the width.
+        // A rail gets the height left under the header, and scrolls in it instead.
+        // Collapsed rows still lay out their tiles, but with no room for them.
+        let tiles_constraints = match self.axis {
+            _ if self.collapsed => BoxConstraints::tight(Size::ZERO),
+            Axis::Horizontal => {
+                BoxConstraints::new(Size::ZERO, Size::new(bc.max().width, f64::INFINITY))
+            }
+            Axis::Vertical => BoxConstraints::new(
+                Size::ZERO,
+                Size::new(bc.max().width, (bc.max().height - header_height).max(0.0)),
+            ),
+        };
+        let tiles_size = self.tiles.layout(ctx, &tiles_constraints, env);
+
+        let content_width = title_size
+            .width
+            .max(description_size.width)
+            .max(tiles_size.width);
+        if self.config.text_direction.is_rtl() {
+            // Take the whole width, so that the title and tiles line up on the right.
+            let width = if bc.max().width.is_finite() {
+                bc.max().width
+            } else {
+                content_width
+            };
+            self.title
+                .set_origin(ctx, env, Point::new(width - title_size.width, 0.0));
+            if let Some(description) = &mut self.description {
+                description.set_origin(
+                    ctx,
+                    env,
+                    Point::new(width - description_size.width, title_size.height),
+                );
+            }
+            self.tiles.set_origin(
+                ctx,
+                env,
+                Point::new(width - tiles_size.width, header_height),
+            );
+            bc.constrain(Size::new(width, header_height + tiles_size.height))
+        } else {
+            self.title.set_origin(ctx, env, Point::ORIGIN);
+            if let Some(description) = &mut self.description {
+                description.set_origin(ctx, env, Point::new(0.0, title_size.height));
+            }
+            self.tiles
+                .set_origin(ctx, env, Point::new(0.0, header_height));
+            bc.constrain(Size::new(content_width, header_height + tiles_size.height))
+        }
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        self.children.paint(ctx, env)
+        self.title.paint(ctx, env);
+        if let Some(description) = &mut self.description {
+            description.paint(ctx, env);
+        }
+        if !self.collapsed {
+            self.tiles.paint(ctx, env);
+        }
+        self.register_tiles(ctx);
+
+        self.paint_header_controls(ctx, env);
+        if self.header_focus == Some(0) {
+            let outline_rect = self.title.layout_rect().inset(FOCUS_OUTLINE_WIDTH);
+            ctx.stroke(outline_rect, &FOCUS_OUTLINE_COLOR, FOCUS_OUTLINE_WIDTH);
+        }
     }
 
     fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
-        smallvec![&self.children as &dyn AsWidgetPod]
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> =
+            smallvec![&self.title as &dyn AsWidgetPod];
+        if let Some(description) = &self.description {
+            children.push(description as &dyn AsWidgetPod);
+        }
+        children.push(&self.tiles as &dyn AsWidgetPod);
+        children
     }
 
     fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
-        smallvec![&mut self.children as &mut dyn AsWidgetPod]
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> =
+            smallvec![&mut self.title as &mut dyn AsWidgetPod];
+        if let Some(description) = &mut self.description {
+            children.push(description as &mut dyn AsWidgetPod);
+        }
+        children.push(&mut self.tiles as &mut dyn AsWidgetPod);
+        children
     }
 
     // This isn't useful for the application itself, but it makes traces more readable
@@ -153,3 +1592,578 @@ impl Widget for ContentSet {
         trace_span!("ContentSet")
     }
 }
+
+impl Drop for ContentSet {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use serde_json::{json, Value};
+    use widget_cruncher::shell::keyboard_types::Key;
+    use widget_cruncher::testing::Harness;
+    use widget_cruncher::{Modifiers, MouseButton, MouseButtons, MouseEvent, Vec2};
+
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::root_widget::{parse_container, RootWidget};
+    use crate::session::SessionState;
+    use crate::settings::{Settings, ThumbnailSize};
+    use crate::testing::{
+        harness, home_json, home_path, paints_color, press, set_path, test_config, tile_json,
+        with_root, MockServer, WINDOW_SIZE,
+    };
+    use crate::thumbnail::{LIVE_BADGE_COLOR, THUMBNAIL_MAX_SIZE};
+    use crate::tile_row::UNAVAILABLE_TEXT;
+    use crate::title_card::title_color;
+
+    // The middle of tile (row, column), along its row, as it was last painted.
+    fn painted_x(harness: &Harness, row: usize, column: usize) -> Option<f64> {
+        with_root(harness, |root| {
+            let hit_map = root.hit_map.borrow();
+            hit_map
+                .tiles
+                .iter()
+                .find(|tile| (tile.row, tile.column) == (row, column))
+                .map(|tile| tile.rect.center().x)
+        })
+    }
+
+    #[test]
+    fn scroll_row_moves_the_row_and_not_the_selection() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending"), ("new", "New")], 20);
+        let mut harness = harness(RootWidget::new(Arc::new(test_config(&server))));
+        let second = painted_x(&harness, 0, 1).unwrap();
+        let third = painted_x(&harness, 0, 2).unwrap();
+        let row_below = painted_x(&harness, 1, 2);
+
+        harness.submit_command(SCROLL_ROW.with((0, third - second, false)));
+        let _ = harness.render();
+        let scrolled = painted_x(&harness, 0, 2).unwrap();
+        assert!(
+            (scrolled - second).abs() < 0.5,
+            "{} != {}",
+            scrolled,
+            second
+        );
+        assert_eq!(painted_x(&harness, 1, 2), row_below);
+        let selected_item = with_root(&harness, |root| root.snapshot().selected_item);
+        assert_eq!(selected_item, Some((0, 0)));
+    }
+
+    #[test]
+    fn cells_follow_the_thumbnail_size() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 5);
+        let root = RootWidget::new(Arc::new(test_config(&server)));
+        root.session.borrow_mut().settings.set(Settings {
+            thumbnail_size: ThumbnailSize::Small,
+            ..Settings::default()
+        });
+        let harness = harness(root);
+
+        // Neither tile is selected, so they're both their cell's size.
+        let spacing = painted_x(&harness, 0, 2).unwrap() - painted_x(&harness, 0, 1).unwrap();
+        let small_cell = THUMBNAIL_MAX_SIZE * ThumbnailSize::Small.scale();
+        assert!((spacing - small_cell).abs() < 0.5, "{}", spacing);
+    }
+
+    #[test]
+    fn smaller_cells_fit_more_tiles_per_page() {
+        let viewport_width = 5.0 * THUMBNAIL_MAX_SIZE + 2.0 * THUMBNAIL_OVERFLOW;
+        assert_eq!(tiles_per_page(viewport_width, THUMBNAIL_MAX_SIZE), 5);
+        assert_eq!(tiles_per_page(viewport_width, THUMBNAIL_MAX_SIZE * 0.8), 6);
+        // Never less than one, however narrow the window.
+        assert_eq!(tiles_per_page(0.0, THUMBNAIL_MAX_SIZE), 1);
+    }
+
+    // Room for five whole tiles, and a bit.
+    const PAGE_VIEWPORT_WIDTH: f64 = 5.5 * THUMBNAIL_MAX_SIZE + 2.0 * THUMBNAIL_OVERFLOW;
+
+    #[test]
+    fn pages_snap_to_their_first_tile() {
+        let cell = THUMBNAIL_MAX_SIZE;
+        let viewport_width = PAGE_VIEWPORT_WIDTH;
+        assert_eq!(page_offset(0, 20, cell, viewport_width, false), 0.0);
+        assert_eq!(page_offset(4, 20, cell, viewport_width, false), 0.0);
+        // Moving past the edge of the first page shows the whole second one.
+        assert_eq!(page_offset(5, 20, cell, viewport_width, false), 5.0 * cell);
+        assert_eq!(page_offset(7, 20, cell, viewport_width, false), 5.0 * cell);
+    }
+
+    #[test]
+    fn right_to_left_pages_line_up_their_right_edge() {
+        let cell = THUMBNAIL_MAX_SIZE;
+        let viewport_width = PAGE_VIEWPORT_WIDTH;
+        // Tiles 0 to 4 are in the last five cells, and 5 to 9 in the five before.
+        // The leftover half cell is on the left of the page.
+        let first_page = page_offset(2, 20, cell, viewport_width, true);
+        assert!((first_page - 14.5 * cell).abs() < 0.01, "{}", first_page);
+        let second_page = page_offset(7, 20, cell, viewport_width, true);
+        assert!((second_page - 9.5 * cell).abs() < 0.01, "{}", second_page);
+    }
+
+    #[test]
+    fn right_at_the_edge_of_a_page_moves_a_page() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 20);
+        let mut config = test_config(&server);
+        config.pan_alignment = PanAlignment::Paged;
+        let root = RootWidget::new(Arc::new(config)).with_grab_focus();
+        let mut harness = harness(root);
+        let page = tiles_per_page(WINDOW_SIZE.width, THUMBNAIL_MAX_SIZE);
+
+        // Within a page, the selection moves tile by tile.
+        for _ in 0..page - 1 {
+            press(&mut harness, Key::ArrowRight);
+        }
+        let selected_item = with_root(&harness, |root| root.snapshot().selected_item);
+        assert_eq!(selected_item, Some((0, page - 1)));
+        let edge_x = painted_x(&harness, 0, page - 1).unwrap();
+
+        // The last tile of the next page takes the place of the last tile of this one.
+        press(&mut harness, Key::ArrowRight);
+        let selected_item = with_root(&harness, |root| root.snapshot().selected_item);
+        assert_eq!(selected_item, Some((0, 2 * page - 1)));
+        let paged_x = painted_x(&harness, 0, 2 * page - 1).unwrap();
+        assert!((paged_x - edge_x).abs() < 0.5, "{} != {}", paged_x, edge_x);
+    }
+
+    #[test]
+    fn free_scrolling_snaps_to_the_nearest_tile() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 20);
+        let mut config = test_config(&server);
+        config.scroll_snap.horizontal = true;
+        config.pan_animation = Some(true);
+        let mut harness = harness(RootWidget::new(Arc::new(config)));
+        let first_x = painted_x(&harness, 0, 0).unwrap();
+        let second_x = painted_x(&harness, 0, 1).unwrap();
+        let cell = second_x - first_x;
+
+        // A sideways scroll that stops most of the way to the second tile.
+        let pos = Point::new(
+            second_x,
+            with_root(&harness, |root| {
+                root.hit_map.borrow().tiles[0].rect.center().y
+            }),
+        );
+        let wheel = MouseEvent {
+            pos,
+            window_pos: pos,
+            buttons: MouseButtons::new(),
+            mods: Modifiers::SHIFT,
+            count: 0,
+            focus: false,
+            button: MouseButton::None,
+            wheel_delta: Vec2::new(0.0, 0.7 * cell),
+        };
+        harness.process_event(Event::Wheel(wheel));
+        let _ = harness.render();
+        let scrolled = painted_x(&harness, 0, 1).unwrap();
+        assert!(
+            (scrolled - (second_x - 0.7 * cell)).abs() < 0.5,
+            "{}",
+            scrolled
+        );
+
+        // Once scrolling settles, the row glides on to the second tile.
+        harness.move_timers_forward(SCROLL_SNAP_DELAY);
+        let frame = Duration::from_millis(16).as_nanos() as u64;
+        harness.process_event(Event::AnimFrame(frame));
+        let _ = harness.render();
+        let gliding = painted_x(&harness, 0, 1).unwrap();
+        assert!(gliding < scrolled && gliding > first_x, "{}", gliding);
+        for _ in 0..100 {
+            harness.process_event(Event::AnimFrame(frame));
+        }
+        let _ = harness.render();
+        let snapped = painted_x(&harness, 0, 1).unwrap();
+        assert!(
+            (snapped - first_x).abs() < 0.5,
+            "{} != {}",
+            snapped,
+            first_x
+        );
+    }
+
+    fn set_metadata(ref_id: &str, set_type: &str) -> ContentSetMetadata {
+        ContentSetMetadata {
+            title: "Trending".to_string(),
+            description: None,
+            ref_id: ref_id.to_string(),
+            set_type: set_type.to_string(),
+            available_from: None,
+            available_until: None,
+            load_priority: None,
+            inline_set: None,
+        }
+    }
+
+    #[test]
+    fn set_url_fills_in_the_template() {
+        let mut config = AppConfig::default();
+        config.set_url_template = "https://cdn.invalid/{set_type}/{ref_id}.json".to_string();
+        assert_eq!(
+            set_url(&config, "abc-123", "CuratedSet"),
+            "https://cdn.invalid/CuratedSet/abc-123.json"
+        );
+
+        // Templates don't have to use every placeholder.
+        config.set_url_template = "https://cdn.invalid/sets/{ref_id}".to_string();
+        assert_eq!(
+            set_url(&config, "abc-123", "CuratedSet"),
+            "https://cdn.invalid/sets/abc-123"
+        );
+    }
+
+    #[test]
+    fn content_set_url_adds_the_offset_past_the_first_page() {
+        let mut config = AppConfig::default();
+        config.set_url_template = "https://cdn.invalid/sets/{ref_id}.json".to_string();
+        let data = set_metadata("abc-123", "CuratedSet");
+        assert_eq!(
+            content_set_url(&config, &data, 0),
+            "https://cdn.invalid/sets/abc-123.json"
+        );
+        assert_eq!(
+            content_set_url(&config, &data, 15),
+            "https://cdn.invalid/sets/abc-123.json?offset=15"
+        );
+
+        // Appended to the query, if the template already has one.
+        config.set_url_template = "https://cdn.invalid/sets?id={ref_id}".to_string();
+        assert_eq!(
+            content_set_url(&config, &data, 15),
+            "https://cdn.invalid/sets?id=abc-123&offset=15"
+        );
+    }
+
+    // A page of a set of `hits` tiles, starting at `offset`, with a tile per title.
+    fn page_json(titles: &[&str], hits: usize, offset: usize) -> Value {
+        let items: Vec<Value> = titles.iter().map(|title| tile_json(title)).collect();
+        json!({
+            "data": {
+                "CuratedSet": {
+                    "items": items,
+                    "meta": { "hits": hits, "offset": offset, "page_size": titles.len() }
+                }
+            }
+        })
+    }
+
+    fn parse_page(document: Value, dedupe: bool) -> ContentSetPage {
+        let set: api::CuratedSet =
+            serde_json::from_value(document["data"]["CuratedSet"].clone()).unwrap();
+        parse_set_page(&set, "CuratedSet", dedupe, "test")
+    }
+
+    #[test]
+    fn pages_know_where_the_next_one_starts() {
+        let first = parse_page(page_json(&["A", "B", "C"], 5, 0), false);
+        assert_eq!(first.tiles.len(), 3);
+        assert_eq!(first.next_offset, Some(3));
+
+        let last = parse_page(page_json(&["D", "E"], 5, 3), false);
+        assert_eq!(last.next_offset, None);
+
+        // Without the metadata, the page is all there is.
+        let unpaged = parse_page(json!({ "data": { "CuratedSet": { "items": [] } } }), false);
+        assert_eq!(unpaged.next_offset, None);
+    }
+
+    #[test]
+    fn nearing_the_end_of_the_row_loads_the_next_page() {
+        let server = MockServer::start();
+        server.add(
+            &home_path(),
+            home_json(&[("trending", "Trending")]).to_string(),
+        );
+        let first_page = ["T0", "T1", "T2", "T3", "T4", "T5"];
+        let second_page = ["T6", "T7", "T8", "T9"];
+        server.add(
+            &set_path("trending"),
+            page_json(&first_page, 10, 0).to_string(),
+        );
+        let second_page_path = format!("{}?offset=6", set_path("trending"));
+        server.add(
+            &second_page_path,
+            page_json(&second_page, 10, 6).to_string(),
+        );
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+
+        let row = with_root(&harness, |root| root.snapshot().rows[0].clone());
+        assert_eq!(row.tiles.len(), 6);
+        assert!(!row.complete);
+        assert_eq!(server.request_count(&second_page_path), 0);
+
+        // The next page is requested NEXT_PAGE_THRESHOLD tiles before the end.
+        for _ in 0..6 - NEXT_PAGE_THRESHOLD {
+            press(&mut harness, Key::ArrowRight);
+        }
+        let row = with_root(&harness, |root| root.snapshot().rows[0].clone());
+        let titles: Vec<&str> = row
+            .tiles
+            .iter()
+            .filter_map(|tile| tile.title.as_deref())
+            .collect();
+        assert_eq!(titles, [&first_page[..], &second_page[..]].concat());
+        assert!(row.complete);
+        assert_eq!(server.request_count(&second_page_path), 1);
+    }
+
+    #[test]
+    fn dedupe_drops_repeated_content() {
+        // The same content twice, with different artwork the second time.
+        let mut document = page_json(&["Moana", "Frozen", "Moana"], 3, 0);
+        document["data"]["CuratedSet"]["items"][2]["image"]["tile"]["1.78"]["program"]["default"]
+            ["url"] = json!("https://images.invalid/Moana-alt.jpg");
+
+        let kept = parse_page(document.clone(), false);
+        assert_eq!(kept.tiles.len(), 3);
+
+        let deduped = parse_page(document, true);
+        let urls: Vec<&str> = deduped
+            .tiles
+            .iter()
+            .map(|tile| tile.image_url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://images.invalid/Moana.jpg",
+                "https://images.invalid/Frozen.jpg"
+            ]
+        );
+        // The dropped item still counts as one of the page's items.
+        assert_eq!(deduped.item_count, 3);
+    }
+
+    #[test]
+    fn live_rows_show_the_program_airing_now() {
+        let server = MockServer::start();
+        let mut home = home_json(&[("channels", "Channels")]);
+        home["data"]["StandardCollection"]["containers"][0]["set"]["refType"] =
+            json!(LIVE_SET_TYPE);
+        server.add(&home_path(), home.to_string());
+        let mut channel = tile_json("Channel 1");
+        channel["currentAiring"] = json!({
+            "text": {
+                "title": { "full": { "program": { "default": { "content": "Evening News" } } } }
+            }
+        });
+        let set = json!({ "data": { LIVE_SET_TYPE: { "items": [channel] } } });
+        server.add(&set_path("channels"), set.to_string());
+        let mut harness = harness(RootWidget::new(Arc::new(test_config(&server))));
+
+        let tile = with_root(&harness, |root| root.snapshot().rows[0].tiles[0].clone());
+        assert_eq!(
+            tile.live,
+            Some(LiveAiring {
+                program_title: Some("Evening News".to_string())
+            })
+        );
+        // The badge is in the top left corner of the image.
+        let image_rect = with_root(&harness, |root| root.hit_map.borrow().tiles[0].rect);
+        let badge_area = Rect::from_origin_size(image_rect.origin(), Size::new(40.0, 24.0));
+        let image = harness.render();
+        assert!(paints_color(&image, badge_area, LIVE_BADGE_COLOR));
+    }
+
+    #[test]
+    fn set_descriptions_render_under_the_title() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 5);
+        let mut home = home_json(&[("trending", "Trending")]);
+        let container = &mut home["data"]["StandardCollection"]["containers"][0];
+        container["set"]["text"]["description"] =
+            json!({ "full": { "set": { "default": { "content": "What everyone's watching" } } } });
+        let container: Lenient<api::Container> = serde_json::from_value(container.clone()).unwrap();
+        let data = parse_container(&container).unwrap();
+        assert_eq!(
+            data.description.as_deref(),
+            Some("What everyone's watching")
+        );
+
+        let config = Arc::new(test_config(&server));
+        let content_set = ContentSet::new(
+            0,
+            data,
+            config.clone(),
+            Rc::new(Cell::new(None)),
+            Rc::new(RefCell::new(SessionState::load(&config))),
+            Arc::new(WorkerPool::new(None)),
+            Rc::new(SystemClock),
+            SharedHitMap::default(),
+        );
+        let mut harness = Harness::create_with_size(content_set, WINDOW_SIZE);
+        let _ = harness.render();
+
+        let root = harness.root_widget();
+        let content_set = root.downcast::<ContentSet>().unwrap();
+        let title = content_set.title.layout_rect();
+        let description = content_set
+            .description
+            .as_ref()
+            .expect("the description label wasn't built")
+            .layout_rect();
+        let tiles = content_set.tiles.layout_rect();
+        // A line of its own, between the title and the tiles.
+        assert!(description.width() > 0.0 && description.height() > 0.0);
+        assert_eq!(description.y0, title.y1);
+        assert_eq!(tiles.y0, description.y1);
+    }
+
+    #[test]
+    fn rows_without_items_fail_and_empty_rows_load() {
+        let server = MockServer::start();
+        server.add(
+            &home_path(),
+            home_json(&[("missing", "Missing"), ("empty", "Empty")]).to_string(),
+        );
+        let missing = json!({ "data": { "CuratedSet": { "meta": { "hits": 0 } } } });
+        server.add(&set_path("missing"), missing.to_string());
+        let empty = json!({ "data": { "CuratedSet": { "items": [] } } });
+        server.add(&set_path("empty"), empty.to_string());
+        let harness = harness(RootWidget::new(Arc::new(test_config(&server))));
+
+        let rows = with_root(&harness, |root| root.snapshot().rows);
+        assert_eq!(rows[0].phase, LoadPhase::Failed);
+        assert_eq!(rows[1].phase, LoadPhase::Loaded);
+        assert!(rows[1].tiles.is_empty());
+    }
+
+    #[test]
+    fn rows_of_only_malformed_items_show_the_unavailable_card() {
+        let shows_card = |items: Value| {
+            let server = MockServer::start();
+            server.add(&home_path(), home_json(&[("set", "Set")]).to_string());
+            let set = json!({ "data": { "CuratedSet": { "items": items } } });
+            server.add(&set_path("set"), set.to_string());
+            let mut harness = harness(RootWidget::new(Arc::new(test_config(&server))));
+            let row = with_root(&harness, |root| root.snapshot().rows[0].clone());
+            assert_eq!(row.phase, LoadPhase::Loaded);
+            assert!(row.tiles.is_empty());
+            let whole_window = Rect::from_origin_size(Point::ORIGIN, WINDOW_SIZE);
+            paints_color(
+                &harness.render(),
+                whole_window,
+                title_color(UNAVAILABLE_TEXT),
+            )
+        };
+
+        // Neither item has a tile image.
+        assert!(shows_card(json!([
+            { "type": "DmcVideo", "contentId": "no-image" },
+            { "type": "DmcVideo", "contentId": "no-url", "image": { "tile": {} } },
+        ])));
+        // An empty set has nothing to apologize for.
+        assert!(!shows_card(json!([])));
+    }
+
+    #[test]
+    fn revealed_tiles_keep_the_focus_margin() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 20);
+        let mut config = test_config(&server);
+        config.focus_margin = 100.0;
+        let root = RootWidget::new(Arc::new(config)).with_grab_focus();
+        let mut harness = harness(root);
+        let assert_margin = |harness: &Harness, column: usize| {
+            let center = painted_x(harness, 0, column).unwrap();
+            let left = center - THUMBNAIL_MAX_SIZE / 2.0;
+            let right = center + THUMBNAIL_MAX_SIZE / 2.0;
+            assert!(left >= 100.0 - 0.5, "column {}: {}", column, left);
+            assert!(
+                right <= WINDOW_SIZE.width - 100.0 + 0.5,
+                "column {}: {}",
+                column,
+                right
+            );
+        };
+
+        // The first tile can't have the margin without showing past the start of
+        // the row, so start checking from the second one.
+        for column in 1..=10 {
+            press(&mut harness, Key::ArrowRight);
+            assert_margin(&harness, column);
+        }
+        // Going back pans the row the other way, with the same margin.
+        for column in (1..10).rev() {
+            press(&mut harness, Key::ArrowLeft);
+            assert_margin(&harness, column);
+        }
+    }
+
+    #[test]
+    fn rails_scroll_vertically_and_rows_horizontally() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 5);
+        let home = home_json(&[("trending", "Trending")]);
+        let container = &home["data"]["StandardCollection"]["containers"][0];
+        let container: Lenient<api::Container> = serde_json::from_value(container.clone()).unwrap();
+        let data = parse_container(&container).unwrap();
+        let config = Arc::new(test_config(&server));
+        // Narrower than the five tiles, and not as tall as one.
+        let window_size = Size::new(3.0 * THUMBNAIL_MAX_SIZE, 0.9 * THUMBNAIL_MAX_SIZE);
+        let clipbox_sizes = |axis: Axis| {
+            let content_set = ContentSet::new(
+                0,
+                data.clone(),
+                config.clone(),
+                Rc::new(Cell::new(None)),
+                Rc::new(RefCell::new(SessionState::load(&config))),
+                Arc::new(WorkerPool::new(None)),
+                Rc::new(SystemClock),
+                SharedHitMap::default(),
+            )
+            .with_axis(axis);
+            let mut harness = Harness::create_with_size(content_set, window_size);
+            let _ = harness.render();
+
+            let root = harness.root_widget();
+            let content_set = root.downcast::<ContentSet>().unwrap();
+            assert_eq!(content_set.tile_count, 5);
+            let clipbox = content_set.tiles.widget();
+            (clipbox.content_size(), clipbox.viewport_size())
+        };
+
+        // A rail is held to the width of the window, and its tiles overflow below.
+        let (content, viewport) = clipbox_sizes(Axis::Vertical);
+        assert!(
+            content.width <= viewport.width,
+            "{:?} {:?}",
+            content,
+            viewport
+        );
+        assert!(
+            content.height > viewport.height,
+            "{:?} {:?}",
+            content,
+            viewport
+        );
+        assert!(viewport.height < window_size.height);
+
+        // A row is held to its tiles' height, and they overflow to the right.
+        let (content, viewport) = clipbox_sizes(Axis::Horizontal);
+        assert!(
+            content.width > viewport.width,
+            "{:?} {:?}",
+            content,
+            viewport
+        );
+        assert!(
+            content.height <= viewport.height,
+            "{:?} {:?}",
+            content,
+            viewport
+        );
+    }
+}
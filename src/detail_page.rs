@@ -0,0 +1,156 @@
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{AsWidgetPod, WidgetPod};
+use widget_cruncher::{Color, Point};
+
+use crate::config::AppConfig;
+use crate::content_set::TileMetadata;
+use crate::tile_image::TileImage;
+use crate::title_card::TitleCard;
+
+// Width / height of the hero image, at the top of the page.
+const HERO_ASPECT_RATIO: f64 = 16.0 / 9.0;
+// The hero image takes at most this share of the page's height.
+const HERO_MAX_HEIGHT: f64 = 0.6;
+
+const PADDING: f64 = 32.0;
+const LINE_SPACING: f64 = 12.0;
+const BACKGROUND_COLOR: Color = Color::rgb8(0x0e, 0x10, 0x18);
+const DETAILS_COLOR: Color = Color::rgb8(0xa0, 0xa4, 0xb0);
+
+// Everything we know about a title: its hero image, synopsis, rating and release
+// year. The RootWidget pushes one when a tile is activated, and draws it over
+// the grid until it's dismissed.
+pub struct DetailPage {
+    pub tile: TileMetadata,
+    // The tile's hero artwork, or its regular artwork if it has none. In text-only
+    // mode, a TitleCard instead.
+    pub hero: WidgetPod<Box<dyn Widget>>,
+}
+
+// --- METHODS ---
+
+impl DetailPage {
+    pub fn new(tile: TileMetadata, config: &AppConfig) -> Self {
+        let title = tile.title.clone().unwrap_or_default();
+        let hero: Box<dyn Widget> = if config.text_only {
+            Box::new(TitleCard::new(title))
+        } else {
+            let url = tile
+                .hero_image_url
+                .clone()
+                .unwrap_or_else(|| tile.image_url.clone());
+            Box::new(TileImage::new(url, title, config))
+        };
+        Self {
+            tile,
+            hero: WidgetPod::new(hero),
+        }
+    }
+
+    // Like "2019 · PG-13", or None if we know neither.
+    fn details_line(&self) -> Option<String> {
+        let year = self.tile.release_year.map(|year| year.to_string());
+        let details: Vec<String> = year.into_iter().chain(self.tile.rating.clone()).collect();
+        if details.is_empty() {
+            None
+        } else {
+            Some(details.join(" \u{b7} "))
+        }
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for DetailPage {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        self.hero.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.hero.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = bc.max();
+        let hero_width = size
+            .width
+            .min(size.height * HERO_MAX_HEIGHT * HERO_ASPECT_RATIO);
+        let hero_size = Size::new(hero_width, hero_width / HERO_ASPECT_RATIO);
+        self.hero
+            .layout(ctx, &BoxConstraints::tight(hero_size), env);
+        self.hero.set_origin(
+            ctx,
+            env,
+            Point::new((size.width - hero_size.width) / 2.0, 0.0),
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &BACKGROUND_COLOR);
+        self.hero.paint(ctx, env);
+
+        let text_width = rect.width() - 2.0 * PADDING;
+        let mut y = self.hero.layout_rect().y1 + PADDING;
+
+        let title = self.tile.title.clone().unwrap_or_default();
+        let title_text = ctx
+            .text()
+            .new_text_layout(title)
+            .font(FontFamily::SYSTEM_UI, 28.0)
+            .text_color(Color::WHITE)
+            .max_width(text_width)
+            .build()
+            .unwrap();
+        ctx.draw_text(&title_text, Point::new(PADDING, y));
+        y += title_text.size().height + LINE_SPACING;
+
+        if let Some(details) = self.details_line() {
+            let details_text = ctx
+                .text()
+                .new_text_layout(details)
+                .font(FontFamily::SYSTEM_UI, 14.0)
+                .text_color(DETAILS_COLOR)
+                .build()
+                .unwrap();
+            ctx.draw_text(&details_text, Point::new(PADDING, y));
+            y += details_text.size().height + LINE_SPACING;
+        }
+
+        if let Some(synopsis) = &self.tile.description {
+            // Synopses that don't fit run off the bottom of the page.
+            ctx.clip(rect);
+            let synopsis_text = ctx
+                .text()
+                .new_text_layout(synopsis.clone())
+                .font(FontFamily::SYSTEM_UI, 16.0)
+                .text_color(Color::WHITE)
+                .max_width(text_width)
+                .build()
+                .unwrap();
+            ctx.draw_text(&synopsis_text, Point::new(PADDING, y));
+        }
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        smallvec![&self.hero as &dyn AsWidgetPod]
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        smallvec![&mut self.hero as &mut dyn AsWidgetPod]
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("DetailPage")
+    }
+}
@@ -0,0 +1,101 @@
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::AsWidgetPod;
+use widget_cruncher::{Color, Point};
+
+use crate::config::AppConfig;
+use crate::keymap::Action;
+
+const BACKGROUND_COLOR: Color = Color::rgb8(0x3a, 0x16, 0x1a);
+const DETAIL_COLOR: Color = Color::rgb8(0xd0, 0xb0, 0xb0);
+
+// Stands in for the tiles of a row whose first page failed to load: what went
+// wrong, and how to retry. The key hint is built once, from the keymap the row
+// was created with.
+pub struct ErrorCard {
+    pub error: String,
+    pub retry_hint: String,
+}
+
+// --- METHODS ---
+
+impl ErrorCard {
+    pub fn new(error: String, config: &AppConfig) -> Self {
+        Self {
+            error,
+            retry_hint: retry_hint(config),
+        }
+    }
+}
+
+// Like "Press Enter to retry.", or an empty string if Activate isn't bound.
+pub fn retry_hint(config: &AppConfig) -> String {
+    match config.keymap.key(Action::Activate) {
+        Some(key) => format!("Press {} to retry.", key),
+        None => String::new(),
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for ErrorCard {
+    fn on_event(&mut self, _ctx: &mut EventCtx, _event: &Event, _env: &Env) {}
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+        const PADDING: f64 = 12.0;
+        const LINE_SPACING: f64 = 6.0;
+
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect.to_rounded_rect(4.0), &BACKGROUND_COLOR);
+        // Errors can be long, eg with the URL of the request in them.
+        ctx.clip(rect);
+
+        let text_width = rect.width() - 2.0 * PADDING;
+        let mut y = PADDING;
+        let lines = [
+            ("Couldn't load this row".to_string(), 16.0, Color::WHITE),
+            (self.retry_hint.clone(), 14.0, Color::WHITE),
+            (self.error.clone(), 12.0, DETAIL_COLOR),
+        ];
+        for (line, font_size, color) in lines {
+            if line.is_empty() {
+                continue;
+            }
+            let text = ctx
+                .text()
+                .new_text_layout(line)
+                .font(FontFamily::SYSTEM_UI, font_size)
+                .text_color(color)
+                .max_width(text_width)
+                .build()
+                .unwrap();
+            ctx.draw_text(&text, Point::new(PADDING, y));
+            y += text.size().height + LINE_SPACING;
+        }
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ErrorCard")
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use crate::content_set::TileMetadata;
+use crate::paths::write_file;
+
+// Tiles the user marked with the 'f' key. They're shown with a heart badge, and
+// gathered in a synthesized row at the top of the grid on the next load.
+//
+// Tiles don't have a stable id in the feed, so favorites are keyed by image URL.
+// They're saved to `path` as one "<image url>\t<title>" line per tile.
+pub struct Favorites {
+    pub path: Option<PathBuf>,
+    pub tiles: Vec<TileMetadata>,
+    // Whether `tiles` changed since they were last saved. See `SessionState::flush`.
+    pub dirty: bool,
+}
+
+impl Favorites {
+    // A missing or unreadable file just means there are no favorites yet.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let contents = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let tiles = contents
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut fields = line.splitn(2, '\t');
+                let image_url = fields.next().unwrap_or_default().to_string();
+                let title = fields
+                    .next()
+                    .filter(|title| !title.is_empty())
+                    .map(|title| title.to_string());
+                TileMetadata {
+                    image_url,
+                    content_id: None,
+                    image_aspect_ratio: None,
+                    title,
+                    description: None,
+                    release_year: None,
+                    rating: None,
+                    content_type: None,
+                    hero_image_url: None,
+                    preview_image_url: None,
+                    brand: None,
+                    live: None,
+                }
+            })
+            .collect();
+        Self {
+            path,
+            tiles,
+            dirty: false,
+        }
+    }
+
+    pub fn contains(&self, image_url: &str) -> bool {
+        self.tiles.iter().any(|tile| tile.image_url == image_url)
+    }
+
+    // Setting a tile to the state it's already in does nothing.
+    pub fn set(&mut self, tile: &TileMetadata, favorite: bool) {
+        if self.contains(&tile.image_url) == favorite {
+            return;
+        }
+        if favorite {
+            self.tiles.push(TileMetadata {
+                live: None,
+                ..tile.clone()
+            });
+        } else {
+            self.tiles
+                .retain(|favorite| favorite.image_url != tile.image_url);
+        }
+        self.dirty = true;
+    }
+
+    // Returns whether the tile is now a favorite.
+    pub fn toggle(&mut self, tile: &TileMetadata) -> bool {
+        let favorite = !self.contains(&tile.image_url);
+        self.set(tile, favorite);
+        favorite
+    }
+
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let contents = self
+            .tiles
+            .iter()
+            .map(|tile| {
+                format!(
+                    "{}\t{}\n",
+                    tile.image_url,
+                    tile.title.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<String>();
+        if let Err(err) = write_file(path, contents) {
+            tracing::warn!("Failed to save favorites to {}: {}", path.display(), err);
+        }
+    }
+}
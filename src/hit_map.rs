@@ -0,0 +1,129 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use widget_cruncher::{Point, Rect, Region};
+
+// Where each tile was last painted, in window coordinates, so that a point can be
+// mapped back to the tile under it. ContentSets fill it in as they paint, so it
+// accounts for scrolling and selection scaling exactly like the screen does.
+#[derive(Default)]
+pub struct HitMap {
+    // In paint order: a tile drawn over its neighbors comes after them.
+    pub tiles: Vec<TileHit>,
+    // A row docked over the others, with its rect. Rows scrolled beneath it still
+    // know where they were painted, but only its own tiles can be hit there.
+    pub docked: Option<(usize, Rect)>,
+}
+
+pub struct TileHit {
+    pub row: usize,
+    pub column: usize,
+    // The visible part of the tile's image. Gaps between images aren't included.
+    pub rect: Rect,
+}
+
+// Like SharedSelection, owned by the RootWidget and cloned into the widgets that need it.
+pub type SharedHitMap = Rc<RefCell<HitMap>>;
+
+impl HitMap {
+    // Replaces everything known about `row` with the tiles it just painted.
+    pub fn set_row(&mut self, row: usize, tiles: Vec<(usize, Rect)>) {
+        self.tiles.retain(|tile| tile.row != row);
+        self.tiles.extend(
+            tiles
+                .into_iter()
+                .map(|(column, rect)| TileHit { row, column, rect }),
+        );
+    }
+
+    // Forgets the tiles in `region`, which is about to be repainted. Whatever is
+    // still there registers itself again; tiles outside it haven't moved.
+    pub fn invalidate(&mut self, region: &Region) {
+        self.tiles.retain(|tile| !region.intersects(tile.rect));
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+    }
+
+    // The (row, column) of the tile drawn at `point`, if any.
+    pub fn tile_at(&self, point: Point) -> Option<(usize, usize)> {
+        let docked_row = self
+            .docked
+            .filter(|(_, rect)| rect.contains(point))
+            .map(|(row, _)| row);
+        self.tiles
+            .iter()
+            .rev()
+            .filter(|tile| docked_row.map_or(true, |row| tile.row == row))
+            .find(|tile| tile.rect.contains(point))
+            .map(|tile| (tile.row, tile.column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two rows of two tiles 100 wide, with a gap of 10 between them.
+    fn hit_map() -> HitMap {
+        let mut hit_map = HitMap::default();
+        for row in 0..2 {
+            let y = row as f64 * 110.0;
+            hit_map.set_row(
+                row,
+                vec![
+                    (0, Rect::new(0.0, y, 100.0, y + 100.0)),
+                    (1, Rect::new(110.0, y, 210.0, y + 100.0)),
+                ],
+            );
+        }
+        hit_map
+    }
+
+    #[test]
+    fn points_map_to_the_tile_under_them() {
+        let hit_map = hit_map();
+        assert_eq!(hit_map.tile_at(Point::new(50.0, 50.0)), Some((0, 0)));
+        assert_eq!(hit_map.tile_at(Point::new(150.0, 160.0)), Some((1, 1)));
+        // Gaps between tiles and the space past them hit nothing.
+        assert_eq!(hit_map.tile_at(Point::new(105.0, 50.0)), None);
+        assert_eq!(hit_map.tile_at(Point::new(500.0, 50.0)), None);
+    }
+
+    #[test]
+    fn tiles_painted_last_are_on_top() {
+        let mut hit_map = hit_map();
+        // A selected tile scaled up over its neighbor, and painted after it.
+        hit_map.set_row(
+            0,
+            vec![
+                (1, Rect::new(110.0, 0.0, 210.0, 100.0)),
+                (0, Rect::new(-5.0, -5.0, 115.0, 105.0)),
+            ],
+        );
+        assert_eq!(hit_map.tile_at(Point::new(112.0, 50.0)), Some((0, 0)));
+    }
+
+    #[test]
+    fn set_row_replaces_the_row() {
+        let mut hit_map = hit_map();
+        hit_map.set_row(0, vec![(3, Rect::new(0.0, 0.0, 100.0, 100.0))]);
+        assert_eq!(hit_map.tile_at(Point::new(50.0, 50.0)), Some((0, 3)));
+        assert_eq!(hit_map.tile_at(Point::new(150.0, 50.0)), None);
+        assert_eq!(hit_map.tile_at(Point::new(150.0, 160.0)), Some((1, 1)));
+    }
+
+    #[test]
+    fn invalidated_tiles_are_forgotten() {
+        let mut hit_map = hit_map();
+        let mut region = Region::EMPTY;
+        region.add_rect(Rect::new(0.0, 0.0, 50.0, 50.0));
+        hit_map.invalidate(&region);
+        assert_eq!(hit_map.tile_at(Point::new(50.0, 50.0)), None);
+        assert_eq!(hit_map.tile_at(Point::new(150.0, 50.0)), Some((0, 1)));
+
+        hit_map.clear();
+        assert_eq!(hit_map.tile_at(Point::new(150.0, 50.0)), None);
+    }
+}
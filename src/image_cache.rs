@@ -0,0 +1,177 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use widget_cruncher::ImageBuf;
+
+// Tile image files saved to disk, so that the grid doesn't download them all again
+// on every launch. Entries are never evicted.
+#[derive(Clone, Debug)]
+pub struct ImageCache {
+    pub dir: PathBuf,
+}
+
+impl ImageCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}", url_hash(url)))
+    }
+
+    pub fn contains(&self, url: &str) -> bool {
+        self.path(url).exists()
+    }
+
+    pub fn get(&self, url: &str) -> Option<Vec<u8>> {
+        std::fs::read(self.path(url)).ok()
+    }
+
+    // Writes to a temporary file first, so that a crash or a concurrent reader
+    // never sees half an image.
+    pub fn put(&self, url: &str, bytes: &[u8]) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(url);
+        let temp_path = path.with_extension(format!("{:?}.tmp", std::thread::current().id()));
+        std::fs::write(&temp_path, bytes)?;
+        std::fs::rename(&temp_path, &path)
+    }
+}
+
+// FNV-1a. Unlike DefaultHasher, it's guaranteed to give the same file names
+// across builds.
+fn url_hash(url: &str) -> u64 {
+    url.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Decoded images, kept in memory so that a thumbnail recreated by its row's
+// virtualization doesn't have to read and decode its image again.
+// The RootWidget sets the budget from its config.
+// Images of live TileImages are pinned. Unpinned images are evicted, least
+// recently used first, once the total goes over `budget` bytes.
+pub struct DecodedImages {
+    pub budget: usize,
+    entries: HashMap<String, DecodedEntry>,
+    bytes: usize,
+    use_counter: u64,
+}
+
+struct DecodedEntry {
+    image: ImageBuf,
+    pins: usize,
+    last_use: u64,
+}
+
+thread_local! {
+    // Only touched from the UI thread, like the widgets.
+    pub static DECODED_IMAGES: RefCell<DecodedImages> =
+        RefCell::new(DecodedImages::new(0));
+}
+
+impl DecodedImages {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            entries: HashMap::new(),
+            bytes: 0,
+            use_counter: 0,
+        }
+    }
+
+    // ImageBufs share their pixels, so this is cheap.
+    pub fn get(&mut self, url: &str) -> Option<ImageBuf> {
+        self.use_counter += 1;
+        let entry = self.entries.get_mut(url)?;
+        entry.last_use = self.use_counter;
+        Some(entry.image.clone())
+    }
+
+    pub fn insert(&mut self, url: &str, image: ImageBuf) {
+        self.use_counter += 1;
+        let size = image.raw_pixels().len();
+        let entry = DecodedEntry {
+            image,
+            pins: 0,
+            last_use: self.use_counter,
+        };
+        if let Some(old_entry) = self.entries.insert(url.to_string(), entry) {
+            self.bytes -= old_entry.image.raw_pixels().len();
+            self.entries.get_mut(url).unwrap().pins = old_entry.pins;
+        }
+        self.bytes += size;
+        self.evict(self.budget);
+    }
+
+    pub fn pin(&mut self, url: &str) {
+        if let Some(entry) = self.entries.get_mut(url) {
+            entry.pins += 1;
+        }
+    }
+
+    pub fn unpin(&mut self, url: &str) {
+        if let Some(entry) = self.entries.get_mut(url) {
+            entry.pins = entry.pins.saturating_sub(1);
+        }
+        self.evict(self.budget);
+    }
+
+    // To be called by the app when the OS reports it's low on memory.
+    // Drops every image that isn't on a live thumbnail.
+    pub fn on_memory_pressure(&mut self) {
+        self.evict(0);
+    }
+
+    // Evicts unpinned images until we're under `budget`, or only pinned ones are left.
+    fn evict(&mut self, budget: usize) {
+        while self.bytes > budget {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|(_, entry)| entry.pins == 0)
+                .min_by_key(|(_, entry)| entry.last_use)
+                .map(|(url, _)| url.clone());
+            let oldest = match oldest {
+                Some(oldest) => oldest,
+                None => return,
+            };
+            let entry = self.entries.remove(&oldest).unwrap();
+            self.bytes -= entry.image.raw_pixels().len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use widget_cruncher::piet::ImageFormat;
+
+    use super::*;
+
+    // A 2x2 gray image, 16 bytes of pixels.
+    fn image() -> ImageBuf {
+        ImageBuf::from_raw(vec![0x80; 16], ImageFormat::RgbaSeparate, 2, 2)
+    }
+
+    #[test]
+    fn memory_pressure_keeps_only_pinned_images() {
+        let mut images = DecodedImages::new(1024);
+        images.insert("visible.jpg", image());
+        images.insert("scrolled-away.jpg", image());
+        images.insert("never-shown.jpg", image());
+        images.pin("visible.jpg");
+
+        images.on_memory_pressure();
+        assert!(images.get("visible.jpg").is_some());
+        assert!(images.get("scrolled-away.jpg").is_none());
+        assert!(images.get("never-shown.jpg").is_none());
+        assert_eq!(images.bytes, 16);
+
+        // Once unpinned, the image goes back to being evictable.
+        images.unpin("visible.jpg");
+        images.on_memory_pressure();
+        assert!(images.get("visible.jpg").is_none());
+        assert_eq!(images.bytes, 0);
+    }
+}
@@ -0,0 +1,114 @@
+use widget_cruncher::shell::keyboard_types::Key;
+
+// Everything the grid can do from the keyboard.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Activate,
+    ToggleHeaderFocus,
+    ToggleCollapse,
+    ToggleFavorite,
+    ShowInfo,
+    JumpToUnvisited,
+    Dismiss,
+    ShowVolume,
+    LoadNextUnloadedRow,
+    Refresh,
+    RetryFailedRows,
+    SwitchProfile,
+    Screenshot,
+    ShowHelp,
+    Search,
+    ShowSettings,
+}
+
+impl Action {
+    // Shown next to the key in the help overlay.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Up => "Previous row",
+            Action::Down => "Next row",
+            Action::Left => "Move left",
+            Action::Right => "Move right",
+            Action::Activate => "Open the selected title",
+            Action::ToggleHeaderFocus => "Switch between tiles and row header",
+            Action::ToggleCollapse => "Collapse or expand all rows",
+            Action::ToggleFavorite => "Add or remove from favorites",
+            Action::ShowInfo => "Show the description of the selected title",
+            Action::JumpToUnvisited => "Go to the first title not opened yet",
+            Action::Dismiss => "Close overlay",
+            Action::ShowVolume => "Volume",
+            Action::LoadNextUnloadedRow => "Load next unloaded row (diagnostic)",
+            Action::Refresh => "Reload the catalog",
+            Action::RetryFailedRows => "Reload the rows that failed to load",
+            Action::SwitchProfile => "Switch to the next profile",
+            Action::Screenshot => "Save a screenshot",
+            Action::ShowHelp => "Show this help",
+            Action::Search => "Search titles",
+            Action::ShowSettings => "Settings",
+        }
+    }
+}
+
+// Which key triggers which action. Several keys can trigger the same action,
+// but each key only triggers one.
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    pub bindings: Vec<(Key, Action)>,
+}
+
+impl KeyMap {
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound_key, _)| bound_key == key)
+            .map(|(_, action)| *action)
+    }
+
+    // The first key bound to `action`, if any.
+    pub fn key(&self, action: Action) -> Option<&Key> {
+        self.bindings
+            .iter()
+            .find(|(_, bound_action)| *bound_action == action)
+            .map(|(key, _)| key)
+    }
+
+    // Binds `key` to `action`, replacing whatever the key was bound to.
+    pub fn bind(&mut self, key: Key, action: Action) {
+        self.bindings.retain(|(bound_key, _)| *bound_key != key);
+        self.bindings.push((key, action));
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (Key::ArrowUp, Action::Up),
+                (Key::ArrowDown, Action::Down),
+                (Key::ArrowLeft, Action::Left),
+                (Key::ArrowRight, Action::Right),
+                (Key::Enter, Action::Activate),
+                (Key::Character(" ".to_string()), Action::Activate),
+                (Key::Tab, Action::ToggleHeaderFocus),
+                (Key::Character("c".to_string()), Action::ToggleCollapse),
+                (Key::Character("f".to_string()), Action::ToggleFavorite),
+                (Key::Character("i".to_string()), Action::ShowInfo),
+                (Key::Character("u".to_string()), Action::JumpToUnvisited),
+                (Key::Escape, Action::Dismiss),
+                (Key::Character("v".to_string()), Action::ShowVolume),
+                (Key::Character("l".to_string()), Action::LoadNextUnloadedRow),
+                (Key::Character("r".to_string()), Action::Refresh),
+                (Key::Character("R".to_string()), Action::RetryFailedRows),
+                (Key::Character("p".to_string()), Action::SwitchProfile),
+                (Key::F12, Action::Screenshot),
+                (Key::Character("?".to_string()), Action::ShowHelp),
+                (Key::Character("/".to_string()), Action::Search),
+                (Key::Character("s".to_string()), Action::ShowSettings),
+            ],
+        }
+    }
+}
@@ -0,0 +1,43 @@
+// The grid and everything it's built from. `main.rs` is the standalone app; a
+// larger app can embed a `root_widget::RootWidget` instead, and hook into it
+// with its `with_*` methods.
+
+pub mod api;
+pub mod api_options;
+pub mod breadcrumbs;
+pub mod bundle;
+pub mod catalog;
+pub mod clock;
+pub mod config;
+pub mod content_set;
+pub mod detail_page;
+pub mod error_card;
+pub mod favorites;
+pub mod hit_map;
+pub mod image_cache;
+pub mod keymap;
+pub mod logging;
+pub mod net;
+pub mod overlay;
+pub mod pan;
+pub mod paths;
+pub mod player;
+pub mod root_widget;
+pub mod search;
+pub mod seen_tiles;
+pub mod session;
+pub mod settings;
+pub mod settings_page;
+pub mod snapshot;
+pub mod sound;
+pub mod speech;
+#[cfg(test)]
+mod testing;
+pub mod thumbnail;
+pub mod tile_image;
+pub mod tile_row;
+pub mod title_card;
+pub mod validate;
+pub mod warm;
+pub mod window_size;
+pub mod workers;
@@ -0,0 +1,121 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tracing::Level;
+
+// Environment variables read when the matching flag isn't passed.
+const LEVEL_VAR: &str = "DSC_LOG_LEVEL";
+const FILE_VAR: &str = "DSC_LOG_FILE";
+
+// Where logs go, from `--log-level=<level>` and `--log-file=<path>`, or the
+// DSC_LOG_LEVEL and DSC_LOG_FILE environment variables. Flags win over the environment.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LogOptions {
+    // If None, the framework's default level is used.
+    pub level: Option<Level>,
+    // If None, logs go to the console.
+    pub file: Option<PathBuf>,
+}
+
+impl LogOptions {
+    pub fn from_env() -> Result<Self, String> {
+        let args = std::env::args().skip(1).collect::<Vec<_>>();
+        Self::parse(
+            &args,
+            std::env::var(LEVEL_VAR).ok(),
+            std::env::var(FILE_VAR).ok(),
+        )
+    }
+
+    pub fn parse(
+        args: &[String],
+        env_level: Option<String>,
+        env_file: Option<String>,
+    ) -> Result<Self, String> {
+        let flag = |name: &str| {
+            let prefix = format!("--{}=", name);
+            args.iter()
+                .find_map(|arg| arg.strip_prefix(&prefix).map(|value| value.to_string()))
+        };
+        let level = match flag("log-level").or(env_level) {
+            Some(level) => Some(
+                level
+                    .parse::<Level>()
+                    .map_err(|_| format!("unknown log level '{}'", level))?,
+            ),
+            None => None,
+        };
+        let file = flag("log-file").or(env_file).map(PathBuf::from);
+        Ok(Self { level, file })
+    }
+
+    // With the defaults, the framework sets up console logging itself.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // Installs the global subscriber. Only call this if `is_default` is false,
+    // and before anything is logged.
+    pub fn install(&self) -> Result<(), String> {
+        let builder = tracing_subscriber::fmt().with_max_level(self.level.unwrap_or(Level::INFO));
+        match &self.file {
+            Some(path) => {
+                let file =
+                    File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+                builder
+                    .with_ansi(false)
+                    .with_writer(Mutex::new(file))
+                    .try_init()
+            }
+            None => builder.try_init(),
+        }
+        .map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn nothing_set_is_the_default() {
+        let options = LogOptions::parse(&args(&["--fullscreen"]), None, None).unwrap();
+        assert!(options.is_default());
+    }
+
+    #[test]
+    fn flags_are_read() {
+        let options = LogOptions::parse(
+            &args(&["--log-level=debug", "--log-file=app.log"]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(options.level, Some(Level::DEBUG));
+        assert_eq!(options.file, Some(PathBuf::from("app.log")));
+        assert!(!options.is_default());
+    }
+
+    #[test]
+    fn flags_win_over_the_environment() {
+        let options = LogOptions::parse(
+            &args(&["--log-level=warn"]),
+            Some("trace".to_string()),
+            Some("env.log".to_string()),
+        )
+        .unwrap();
+        assert_eq!(options.level, Some(Level::WARN));
+        assert_eq!(options.file, Some(PathBuf::from("env.log")));
+    }
+
+    #[test]
+    fn unknown_levels_are_rejected() {
+        let err = LogOptions::parse(&args(&["--log-level=loud"]), None, None).unwrap_err();
+        assert_eq!(err, "unknown log level 'loud'");
+    }
+}
@@ -3,16 +3,117 @@
 // On Windows platform, don't show a console when opening the app.
 #![windows_subsystem = "windows"]
 
-mod content_set;
-mod root_widget;
-mod thumbnail;
+use std::sync::Arc;
 
 use widget_cruncher::{AppLauncher, WindowDesc};
 
+use disney_streaming_clone::api_options::ApiOptions;
+use disney_streaming_clone::catalog::RowOrder;
+use disney_streaming_clone::config::AppConfig;
+use disney_streaming_clone::logging::LogOptions;
+use disney_streaming_clone::root_widget::RootWidget;
+use disney_streaming_clone::window_size::WindowSize;
+use disney_streaming_clone::workers::WorkerPool;
+use disney_streaming_clone::{bundle, catalog, net, validate, warm};
+
 fn main() {
-    let main_window = WindowDesc::new(root_widget::RootWidget::new()).title("Title list");
-    AppLauncher::with_window(main_window)
-        .log_to_console()
-        .launch()
-        .expect("launch failed");
+    // Without logging flags, the launcher logs to the console at its own level.
+    let log_options = LogOptions::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    if !log_options.is_default() {
+        if let Err(err) = log_options.install() {
+            eprintln!("Failed to set up logging: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    let api_options = ApiOptions::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let mut config = AppConfig::default();
+    api_options.apply(&mut config);
+    // With `--play-url=<url>`, activated tiles play that stream or file.
+    config.player_stream_url =
+        std::env::args().find_map(|arg| arg.strip_prefix("--play-url=").map(|url| url.to_string()));
+    let config = Arc::new(config);
+    net::configure_client(&config.http);
+
+    // With `--feed-stdin`, the catalog is read from a bundle on stdin instead of
+    // fetched. See `bundle`. This works with every mode.
+    if std::env::args().any(|arg| arg == "--feed-stdin") {
+        match bundle::read_bundle(std::io::stdin().lock(), &config) {
+            Ok(documents) => bundle::install_bundle(documents),
+            Err(err) => {
+                eprintln!("stdin: {}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // `validate` checks the live feed and exits, without opening a window.
+    if std::env::args().nth(1).as_deref() == Some("validate") {
+        let report = validate::validate_feed(&config);
+        print!("{}", report);
+        std::process::exit(if report.is_ok() { 0 } else { 1 });
+    }
+
+    // `warm` downloads every tile image into the image cache and exits.
+    if std::env::args().nth(1).as_deref() == Some("warm") {
+        let summary = warm::warm_caches(&config);
+        print!("{}", summary);
+        std::process::exit(if summary.failures.is_empty() { 0 } else { 1 });
+    }
+
+    // `catalog` prints every row of the feed as it loads, and exits.
+    // With `--feed-order`, rows are printed in the order of the feed instead.
+    if std::env::args().nth(1).as_deref() == Some("catalog") {
+        let order = if std::env::args().any(|arg| arg == "--feed-order") {
+            RowOrder::Feed
+        } else {
+            RowOrder::Completion
+        };
+        let workers = Arc::new(WorkerPool::new(config.background_workers));
+        let rows = match catalog::stream_catalog(&config, workers, order) {
+            Ok(rows) => rows,
+            Err(err) => {
+                eprintln!("{}: {}", config.collection_url, err);
+                std::process::exit(1);
+            }
+        };
+        let mut failed = false;
+        for row in rows {
+            match row.page {
+                Ok(page) => println!(
+                    "{} {} ({} tiles)",
+                    row.index,
+                    row.metadata.title,
+                    page.tiles.len()
+                ),
+                Err(err) => {
+                    failed = true;
+                    println!("{} {} failed: {}", row.index, row.metadata.title, err);
+                }
+            }
+        }
+        std::process::exit(if failed { 1 } else { 0 });
+    }
+
+    // The RootWidget shrinks the window once it's open if it doesn't fit the display.
+    let window_size = WindowSize::load(config.window_size_path.clone())
+        .saved
+        .or(config.window_size);
+    let mut main_window =
+        WindowDesc::new(RootWidget::new(config).with_grab_focus()).title("Title list");
+    if let Some(window_size) = window_size {
+        main_window = main_window.window_size(window_size);
+    }
+    let mut launcher = AppLauncher::with_window(main_window);
+    if log_options.is_default() {
+        launcher = launcher.log_to_console();
+    }
+    launcher.launch().expect("launch failed");
 }
@@ -0,0 +1,527 @@
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, RETRY_AFTER};
+use reqwest::{Client, Proxy, StatusCode};
+use tokio::runtime::Runtime;
+use tokio::sync::Notify;
+
+use crate::bundle::bundled_json;
+use crate::config::{HttpConfig, ProxyConfig, RetryPolicy};
+
+// Every request of the app goes through this client, so that connections
+// to the CDN are reused across sets. See `configure_client`.
+static CLIENT: OnceCell<Client> = OnceCell::new();
+
+// How every request of the app retries transient failures. Set up along with
+// the client, see `configure_client`.
+static RETRY_POLICY: OnceCell<RetryPolicy> = OnceCell::new();
+
+// Drives every request of the app. Loads still run on the framework's background
+// threads, which wait on the runtime for their request, but a cancelled request
+// is dropped right away instead of running to its timeout.
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(NETWORK_THREADS)
+        .thread_name("network")
+        .enable_all()
+        .build()
+        .expect("failed to start the network runtime")
+});
+
+// Requests are mostly waiting on the CDN, so a couple of threads are plenty.
+const NETWORK_THREADS: usize = 2;
+
+// The feeds are big and compress very well, so we always ask for a compressed response.
+const ACCEPTED_ENCODINGS: &str = "gzip, br";
+
+// How a request answered with 429 Too Many Requests is retried. Without a
+// Retry-After header, the wait starts at DEFAULT_RETRY_AFTER and doubles on every attempt.
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+// If the CDN asks us to wait longer than this, the request fails instead of
+// holding up its load.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+// How long the WorkerPool keeps running one load at a time after the CDN
+// said we can retry, so that the queued loads don't all hit it again at once.
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(10);
+
+// When the CDN last told us we can send requests again. Requests wait until then
+// before they're sent.
+static RETRY_AT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug)]
+pub enum FetchError {
+    Http(reqwest::Error),
+    // The body didn't match its Content-Encoding.
+    Decode(std::io::Error),
+    Json(serde_json::Error),
+    // The JSON parsed, but isn't shaped like we expect. Describes what's missing.
+    Schema(String),
+    // The CDN answered 429 Too Many Requests, with how long it asked us to wait,
+    // if it said. Only returned once retries have run out.
+    RateLimited(Option<Duration>),
+    // A feed bundle is installed, and this URL isn't in it. See `bundle`.
+    NotBundled(String),
+    // The load was cancelled with its CancelHandle before it finished.
+    Cancelled,
+}
+
+// Cancels the requests it was passed to, like the loads of a row that went
+// away. Clones share the same state, and a cancelled handle stays cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+// Sets up the shared client with `http.proxy`, or with the proxy from the standard
+// HTTP_PROXY / HTTPS_PROXY / NO_PROXY environment variables if it's None, and
+// with `http.retry`.
+// Only the first call counts, and it has to happen before the first request,
+// which otherwise sets up the client without a configured proxy.
+pub fn configure_client(http: &HttpConfig) {
+    RETRY_POLICY.get_or_init(|| http.retry.clone());
+    let proxy = http.proxy.as_ref();
+    CLIENT.get_or_init(|| {
+        let mut builder = Client::builder();
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(build_proxy(proxy));
+        }
+        builder.build().unwrap_or_else(|err| {
+            tracing::error!(
+                "Failed to set up the HTTP client, ignoring the proxy: {}",
+                err
+            );
+            Client::new()
+        })
+    });
+}
+
+fn client() -> &'static Client {
+    CLIENT.get_or_init(Client::new)
+}
+
+fn retry_policy() -> &'static RetryPolicy {
+    RETRY_POLICY.get_or_init(RetryPolicy::default)
+}
+
+fn build_proxy(config: &ProxyConfig) -> Proxy {
+    let proxy_url = config.url.clone();
+    let no_proxy = config.no_proxy.clone();
+    let proxy = Proxy::custom(move |url| {
+        let host = url.host_str().unwrap_or_default();
+        if bypasses_proxy(host, &no_proxy) {
+            None
+        } else {
+            Some(proxy_url.clone())
+        }
+    });
+    match &config.credentials {
+        Some((username, password)) => proxy.basic_auth(username, password),
+        None => proxy,
+    }
+}
+
+// Entries match their host exactly, and its subdomains. A leading dot, as in
+// ".example.com", is allowed like in NO_PROXY.
+fn bypasses_proxy(host: &str, no_proxy: &[String]) -> bool {
+    no_proxy.iter().any(|entry| {
+        let domain = entry.trim_start_matches('.');
+        host == domain
+            || (host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.'))
+    })
+}
+
+// Fetches and parses a JSON document. Servers that ignore Accept-Encoding and
+// send the body uncompressed are fine too.
+// `timeout` covers the whole request, from connecting to reading the body.
+// With a feed bundle installed, documents come from the bundle instead.
+pub fn fetch_json(
+    url: &str,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<serde_json::Value, FetchError> {
+    if let Some(document) = bundled_json(url) {
+        return document;
+    }
+    let body = fetch_bytes_cancellable(url, timeout, cancel)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+// Same as `fetch_bytes_cancellable`, for requests nothing cancels.
+pub fn fetch_bytes(url: &str, timeout: Duration) -> Result<Vec<u8>, FetchError> {
+    fetch_bytes_cancellable(url, timeout, &CancelHandle::default())
+}
+
+// Fetches a document, undoing its Content-Encoding. Blocks until the request
+// is done, so it can't be called from the runtime itself.
+// Returns FetchError::Cancelled as soon as `cancel` is, including while waiting
+// out a rate limit.
+pub fn fetch_bytes_cancellable(
+    url: &str,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<Vec<u8>, FetchError> {
+    if cancel.is_cancelled() {
+        return Err(FetchError::Cancelled);
+    }
+    RUNTIME.block_on(async {
+        tokio::select! {
+            result = fetch_bytes_async(url, timeout) => result,
+            _ = cancel.cancelled() => Err(FetchError::Cancelled),
+        }
+    })
+}
+
+// If the CDN says we're sending too many requests, waits as long as it asks
+// and tries again, up to MAX_RATE_LIMIT_RETRIES times. Other failures that
+// may be transient are retried with the RetryPolicy. See `FetchError::is_transient`.
+async fn fetch_bytes_async(url: &str, timeout: Duration) -> Result<Vec<u8>, FetchError> {
+    let policy = retry_policy();
+    let mut attempt = 0;
+    let mut backoff = DEFAULT_RETRY_AFTER;
+    let mut failed_attempts = 0;
+    loop {
+        wait_for_rate_limit().await;
+        match fetch_bytes_once(url, timeout).await {
+            Err(FetchError::RateLimited(retry_after)) if attempt < MAX_RATE_LIMIT_RETRIES => {
+                let delay = retry_after.unwrap_or(backoff);
+                if delay > MAX_RETRY_AFTER {
+                    return Err(FetchError::RateLimited(retry_after));
+                }
+                tracing::warn!("Rate limited on {}, retrying in {:?}", url, delay);
+                set_retry_at(Instant::now() + delay);
+                attempt += 1;
+                backoff *= 2;
+            }
+            Err(err) if err.is_transient() && failed_attempts + 1 < policy.max_attempts => {
+                let delay = retry_delay(policy, failed_attempts);
+                tracing::warn!("Fetching {} failed ({}), retrying in {:?}", url, err, delay);
+                failed_attempts += 1;
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+// How long to wait before the retry that follows `failed_attempts` failures.
+// Somewhere between half and all of the exponential backoff, at random.
+fn retry_delay(policy: &RetryPolicy, failed_attempts: u32) -> Duration {
+    let backoff = policy
+        .base_delay
+        .saturating_mul(1 << failed_attempts.min(16))
+        .min(policy.max_delay);
+    // Every RandomState is seeded differently, which is all the randomness we need.
+    let random = RandomState::new().build_hasher().finish();
+    let jitter = 0.5 + 0.5 * (random as f64 / u64::MAX as f64);
+    backoff.mul_f64(jitter)
+}
+
+// Until when loads should be throttled because of a recent 429, if they should be.
+pub fn rate_limited_until() -> Option<Instant> {
+    let retry_at = (*RETRY_AT.lock().unwrap())?;
+    Some(retry_at + RATE_LIMIT_COOLDOWN).filter(|until| *until > Instant::now())
+}
+
+fn set_retry_at(retry_at: Instant) {
+    let mut current = RETRY_AT.lock().unwrap();
+    // Another request may have been told to wait even longer.
+    if current.map_or(true, |current| current < retry_at) {
+        *current = Some(retry_at);
+    }
+}
+
+async fn wait_for_rate_limit() {
+    let retry_at = *RETRY_AT.lock().unwrap();
+    if let Some(retry_at) = retry_at {
+        let now = Instant::now();
+        if retry_at > now {
+            tokio::time::sleep(retry_at - now).await;
+        }
+    }
+}
+
+async fn fetch_bytes_once(url: &str, timeout: Duration) -> Result<Vec<u8>, FetchError> {
+    let response = client()
+        .get(url)
+        .header(ACCEPT_ENCODING, ACCEPTED_ENCODINGS)
+        .timeout(timeout)
+        .send()
+        .await?;
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        // Retry-After can also be an HTTP date, which the CDN doesn't send. We
+        // fall back to our own backoff for those.
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|retry_after| retry_after.to_str().ok())
+            .and_then(|retry_after| retry_after.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        return Err(FetchError::RateLimited(retry_after));
+    }
+    // So that they can be retried. Other errors, like a 404, make it to the
+    // parser like any body.
+    if response.status().is_server_error() {
+        return Err(response.error_for_status().unwrap_err().into());
+    }
+    let encoding = response
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|encoding| encoding.to_str().ok())
+        .unwrap_or("identity")
+        .to_string();
+    let body = response.bytes().await?;
+    let decoded = decode_body(&encoding, &body)?;
+    tracing::debug!(
+        url,
+        encoding = encoding.as_str(),
+        compressed_size = body.len(),
+        decompressed_size = decoded.len(),
+        "fetched document"
+    );
+    Ok(decoded.into_owned())
+}
+
+// Undoes `encoding`. Unknown encodings are passed through as-is.
+pub fn decode_body<'a>(encoding: &str, body: &'a [u8]) -> Result<Cow<'a, [u8]>, std::io::Error> {
+    let mut decoded = Vec::new();
+    match encoding {
+        "gzip" => {
+            flate2::read::GzDecoder::new(body).read_to_end(&mut decoded)?;
+        }
+        "br" => {
+            brotli::Decompressor::new(body, 4096).read_to_end(&mut decoded)?;
+        }
+        _ => return Ok(Cow::Borrowed(body)),
+    }
+    Ok(Cow::Owned(decoded))
+}
+
+// --- METHODS ---
+
+impl FetchError {
+    // Whether the same request may well work if it's sent again: it timed out,
+    // the connection failed or dropped, or the CDN answered with a 5xx.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            FetchError::Http(err) => {
+                err.is_timeout()
+                    || err.is_connect()
+                    || err.is_body()
+                    || err
+                        .status()
+                        .map_or(false, |status| status.is_server_error())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    // Resolves once the handle is cancelled.
+    async fn cancelled(&self) {
+        // Created before the check, so that a `cancel` in between still wakes it.
+        let notified = self.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::Http(err) => write!(f, "{}", err),
+            FetchError::Decode(err) => write!(f, "couldn't decode body: {}", err),
+            FetchError::Json(err) => write!(f, "invalid JSON: {}", err),
+            FetchError::Schema(mismatch) => write!(f, "unexpected JSON: {}", mismatch),
+            FetchError::RateLimited(Some(retry_after)) => {
+                write!(f, "rate limited, retry after {:?}", retry_after)
+            }
+            FetchError::RateLimited(None) => write!(f, "rate limited"),
+            FetchError::NotBundled(url) => write!(f, "{} isn't in the feed bundle", url),
+            FetchError::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        FetchError::Http(err)
+    }
+}
+
+impl From<std::io::Error> for FetchError {
+    fn from(err: std::io::Error) -> Self {
+        FetchError::Decode(err)
+    }
+}
+
+impl From<serde_json::Error> for FetchError {
+    fn from(err: serde_json::Error) -> Self {
+        FetchError::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::testing::MockServer;
+
+    const DOCUMENT: &[u8] = br#"{"data": {"StandardCollection": {"containers": []}}}"#;
+
+    #[test]
+    fn decode_body_inflates_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(DOCUMENT).unwrap();
+        let body = encoder.finish().unwrap();
+        assert_eq!(decode_body("gzip", &body).unwrap(), DOCUMENT);
+    }
+
+    #[test]
+    fn decode_body_inflates_brotli() {
+        let mut body = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut body, 4096, 5, 22);
+            encoder.write_all(DOCUMENT).unwrap();
+        }
+        assert_eq!(decode_body("br", &body).unwrap(), DOCUMENT);
+    }
+
+    #[test]
+    fn decode_body_passes_identity_through() {
+        assert!(matches!(
+            decode_body("identity", DOCUMENT).unwrap(),
+            Cow::Borrowed(DOCUMENT)
+        ));
+        // Same for encodings we don't know about.
+        assert_eq!(decode_body("zstd", DOCUMENT).unwrap(), DOCUMENT);
+    }
+
+    #[test]
+    fn decode_body_fails_on_a_corrupt_body() {
+        assert!(decode_body("gzip", DOCUMENT).is_err());
+    }
+
+    fn no_proxy(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn hosts_and_their_subdomains_bypass_the_proxy() {
+        let entries = no_proxy(&["bamgrid.com", "localhost"]);
+        assert!(bypasses_proxy("bamgrid.com", &entries));
+        assert!(bypasses_proxy("cd-static.bamgrid.com", &entries));
+        assert!(bypasses_proxy("localhost", &entries));
+        assert!(!bypasses_proxy("example.com", &entries));
+    }
+
+    #[test]
+    fn only_whole_labels_match() {
+        let entries = no_proxy(&["bamgrid.com"]);
+        assert!(!bypasses_proxy("notbamgrid.com", &entries));
+        assert!(!bypasses_proxy("bamgrid.com.evil.example", &entries));
+    }
+
+    #[test]
+    fn a_leading_dot_is_allowed() {
+        let entries = no_proxy(&[".bamgrid.com"]);
+        assert!(bypasses_proxy("bamgrid.com", &entries));
+        assert!(bypasses_proxy("cd-static.bamgrid.com", &entries));
+    }
+
+    #[test]
+    fn nothing_bypasses_an_empty_list() {
+        assert!(!bypasses_proxy("bamgrid.com", &[]));
+    }
+
+    #[test]
+    fn retry_delays_back_off_within_the_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+        };
+        for failed_attempts in 0..40 {
+            let backoff =
+                (policy.base_delay * 2u32.pow(failed_attempts.min(5))).min(policy.max_delay);
+            let delay = retry_delay(&policy, failed_attempts);
+            assert!(
+                delay >= backoff / 2 && delay <= backoff,
+                "{:?} isn't between half of {:?} and all of it",
+                delay,
+                backoff
+            );
+        }
+    }
+
+    #[test]
+    fn failed_connections_and_server_errors_are_transient() {
+        let server = MockServer::start();
+        server.add_with_status("/busy.json", 503, "{}");
+        let timeout = Duration::from_secs(5);
+
+        let busy = RUNTIME.block_on(fetch_bytes_once(
+            &format!("{}/busy.json", server.base_url),
+            timeout,
+        ));
+        assert!(busy.unwrap_err().is_transient());
+
+        // Nothing listens on port 1.
+        let refused = RUNTIME.block_on(fetch_bytes_once("http://127.0.0.1:1/", timeout));
+        assert!(refused.unwrap_err().is_transient());
+    }
+
+    #[test]
+    fn other_failures_arent_transient() {
+        let json_error = serde_json::from_slice::<serde_json::Value>(b"{").unwrap_err();
+        assert!(!FetchError::Json(json_error).is_transient());
+        assert!(!FetchError::Schema("data is missing".to_string()).is_transient());
+        assert!(!FetchError::RateLimited(None).is_transient());
+        assert!(!FetchError::Cancelled.is_transient());
+    }
+
+    #[test]
+    fn rate_limited_requests_wait_for_retry_after() {
+        let server = MockServer::start();
+        server.add("/home.json", DOCUMENT);
+        server.add_once("/home.json", 429, &[("Retry-After", "1")], "{}");
+        let url = format!("{}/home.json", server.base_url);
+
+        let start = Instant::now();
+        let body = fetch_bytes(&url, Duration::from_secs(5)).unwrap();
+        assert_eq!(body, DOCUMENT);
+        // Retried once, no sooner than the CDN asked.
+        assert_eq!(server.request_count("/home.json"), 2);
+        assert!(
+            start.elapsed() >= Duration::from_secs(1),
+            "{:?}",
+            start.elapsed()
+        );
+    }
+}
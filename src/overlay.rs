@@ -0,0 +1,353 @@
+use std::time::Duration;
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::{Color, Point, Rect, TimerToken};
+
+use crate::keymap::KeyMap;
+
+// How long the overlay stays up after the last key press.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(3);
+
+const PANEL_SIZE: Size = Size::new(320.0, 72.0);
+const PANEL_COLOR: Color = Color::rgba8(0x10, 0x14, 0x1c, 0xe0);
+const TRACK_COLOR: Color = Color::rgba8(0xff, 0xff, 0xff, 0x40);
+const FILL_COLOR: Color = Color::WHITE;
+
+// A transient slider with a label, drawn over the grid.
+// It's shown with a key, adjusted with Left/Right, and hides itself after
+// being idle for a while. This isn't a widget: the owner forwards keys and
+// timers to it, and paints it last so it's on top of everything else.
+pub struct SliderOverlay {
+    pub label: String,
+    // Always between 0.0 and 1.0.
+    pub value: f64,
+    pub step: f64,
+
+    pub visible: bool,
+    pub hide_timer: TimerToken,
+}
+
+impl SliderOverlay {
+    pub fn new(label: impl Into<String>, value: f64, step: f64) -> Self {
+        Self {
+            label: label.into(),
+            value: value.max(0.0).min(1.0),
+            step,
+            visible: false,
+            hide_timer: TimerToken::INVALID,
+        }
+    }
+
+    pub fn show(&mut self, ctx: &mut EventCtx) {
+        self.visible = true;
+        self.hide_timer = ctx.request_timer(IDLE_TIMEOUT);
+        ctx.request_paint();
+    }
+
+    pub fn hide(&mut self, ctx: &mut EventCtx) {
+        self.visible = false;
+        self.hide_timer = TimerToken::INVALID;
+        ctx.request_paint();
+    }
+
+    // Moves the value by `steps` steps, and restarts the idle timeout.
+    pub fn adjust(&mut self, ctx: &mut EventCtx, steps: f64) {
+        self.value = (self.value + steps * self.step).max(0.0).min(1.0);
+        self.show(ctx);
+    }
+
+    // Returns true if the timer was ours.
+    pub fn on_timer(&mut self, ctx: &mut EventCtx, token: TimerToken) -> bool {
+        if token != self.hide_timer {
+            return false;
+        }
+        self.hide(ctx);
+        true
+    }
+
+    // Draws the overlay centered near the bottom of an area of the given size.
+    pub fn paint(&self, ctx: &mut PaintCtx, area: Size) {
+        if !self.visible {
+            return;
+        }
+        const PADDING: f64 = 12.0;
+        const TRACK_HEIGHT: f64 = 6.0;
+
+        let panel_origin = Point::new(
+            (area.width - PANEL_SIZE.width) / 2.0,
+            area.height - PANEL_SIZE.height - 2.0 * PADDING,
+        );
+        let panel_rect = Rect::from_origin_size(panel_origin, PANEL_SIZE);
+        ctx.fill(panel_rect.to_rounded_rect(8.0), &PANEL_COLOR);
+
+        let label_text = ctx
+            .text()
+            .new_text_layout(format!("{}  {:.0}%", self.label, self.value * 100.0))
+            .font(FontFamily::SYSTEM_UI, 14.0)
+            .text_color(Color::WHITE)
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &label_text,
+            Point::new(panel_rect.x0 + PADDING, panel_rect.y0 + PADDING),
+        );
+
+        let track_y = panel_rect.y0 + PADDING + label_text.size().height + PADDING;
+        let track_rect = Rect::new(
+            panel_rect.x0 + PADDING,
+            track_y,
+            panel_rect.x1 - PADDING,
+            track_y + TRACK_HEIGHT,
+        );
+        let fill_rect = track_rect.with_size(Size::new(
+            track_rect.width() * self.value,
+            track_rect.height(),
+        ));
+        ctx.fill(track_rect.to_rounded_rect(TRACK_HEIGHT / 2.0), &TRACK_COLOR);
+        ctx.fill(fill_rect.to_rounded_rect(TRACK_HEIGHT / 2.0), &FILL_COLOR);
+    }
+}
+
+// Lists every key binding of a KeyMap, in the middle of the grid.
+// Like SliderOverlay, the owner forwards keys to it and paints it last.
+#[derive(Default)]
+pub struct HelpOverlay {
+    pub visible: bool,
+}
+
+impl HelpOverlay {
+    pub fn new() -> Self {
+        Self { visible: false }
+    }
+
+    pub fn show(&mut self, ctx: &mut EventCtx) {
+        self.visible = true;
+        ctx.request_paint();
+    }
+
+    pub fn hide(&mut self, ctx: &mut EventCtx) {
+        self.visible = false;
+        ctx.request_paint();
+    }
+
+    // The bindings are read at paint time, so that changes to the keymap show up.
+    pub fn paint(&self, ctx: &mut PaintCtx, area: Size, keymap: &KeyMap) {
+        if !self.visible {
+            return;
+        }
+        const PADDING: f64 = 16.0;
+        const KEY_COLUMN_WIDTH: f64 = 120.0;
+        const LINE_SPACING: f64 = 6.0;
+
+        let lines = help_lines(keymap)
+            .into_iter()
+            .map(|(key, description)| {
+                let key_text = ctx
+                    .text()
+                    .new_text_layout(key)
+                    .font(FontFamily::MONOSPACE, 14.0)
+                    .text_color(Color::WHITE)
+                    .build()
+                    .unwrap();
+                let description_text = ctx
+                    .text()
+                    .new_text_layout(description)
+                    .font(FontFamily::SYSTEM_UI, 14.0)
+                    .text_color(Color::WHITE)
+                    .build()
+                    .unwrap();
+                (key_text, description_text)
+            })
+            .collect::<Vec<_>>();
+
+        let content_width = lines
+            .iter()
+            .map(|(_, description)| KEY_COLUMN_WIDTH + description.size().width)
+            .fold(0.0, f64::max);
+        let content_height = lines
+            .iter()
+            .map(|(key, description)| key.size().height.max(description.size().height))
+            .sum::<f64>()
+            + LINE_SPACING * lines.len().saturating_sub(1) as f64;
+
+        let panel_size = Size::new(
+            content_width + 2.0 * PADDING,
+            content_height + 2.0 * PADDING,
+        );
+        let panel_origin = Point::new(
+            (area.width - panel_size.width) / 2.0,
+            (area.height - panel_size.height) / 2.0,
+        );
+        let panel_rect = Rect::from_origin_size(panel_origin, panel_size);
+        ctx.fill(panel_rect.to_rounded_rect(8.0), &PANEL_COLOR);
+
+        let mut y = panel_rect.y0 + PADDING;
+        for (key_text, description_text) in &lines {
+            ctx.draw_text(key_text, Point::new(panel_rect.x0 + PADDING, y));
+            ctx.draw_text(
+                description_text,
+                Point::new(panel_rect.x0 + PADDING + KEY_COLUMN_WIDTH, y),
+            );
+            y += key_text.size().height.max(description_text.size().height) + LINE_SPACING;
+        }
+    }
+}
+
+// What the HelpOverlay lists for `keymap`: each bound key, and what it does.
+pub fn help_lines(keymap: &KeyMap) -> Vec<(String, &'static str)> {
+    keymap
+        .bindings
+        .iter()
+        .map(|(key, action)| (key.to_string(), action.description()))
+        .collect()
+}
+
+const INFO_PANEL_SIZE: Size = Size::new(480.0, 240.0);
+// How far Up/Down scroll the description.
+const INFO_SCROLL_STEP: f64 = 40.0;
+// Share of the remaining distance the description scrolls every 60th of a second.
+const INFO_SCROLL_RATE: f64 = 0.25;
+
+// The title and full description of a tile, in the middle of the grid.
+// Descriptions too long for the panel scroll with Up/Down. Like HelpOverlay,
+// the owner forwards keys and animation frames to it and paints it last.
+#[derive(Default)]
+pub struct InfoOverlay {
+    pub visible: bool,
+    pub title: String,
+    pub description: String,
+
+    // How far the description is scrolled, and how far it's animating to.
+    pub scroll_offset: f64,
+    pub scroll_target: f64,
+    // How far the description can scroll. It's measured when painting, since
+    // that's when the text is laid out.
+    pub max_scroll: f64,
+}
+
+impl InfoOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn show(&mut self, ctx: &mut EventCtx, title: String, description: String) {
+        self.visible = true;
+        self.title = title;
+        self.description = description;
+        self.scroll_offset = 0.0;
+        self.scroll_target = 0.0;
+        self.max_scroll = 0.0;
+        ctx.request_paint();
+    }
+
+    pub fn hide(&mut self, ctx: &mut EventCtx) {
+        self.visible = false;
+        ctx.request_paint();
+    }
+
+    // Scrolls the description by `steps` steps, downwards if positive. It stops
+    // at either end.
+    pub fn scroll(&mut self, ctx: &mut EventCtx, steps: f64) {
+        let target = (self.scroll_target + steps * INFO_SCROLL_STEP)
+            .max(0.0)
+            .min(self.max_scroll);
+        if target != self.scroll_target {
+            self.scroll_target = target;
+            ctx.request_anim_frame();
+        }
+    }
+
+    // Moves the description one frame closer to `scroll_target`. `interval` is
+    // the time since the last frame, in nanoseconds.
+    pub fn on_anim_frame(&mut self, ctx: &mut EventCtx, interval: u64) {
+        if self.scroll_offset == self.scroll_target {
+            return;
+        }
+        let progress = 1.0 - (1.0 - INFO_SCROLL_RATE).powf(interval as f64 * 60.0 / 1e9);
+        let remaining = self.scroll_target - self.scroll_offset;
+        if remaining.abs() < 0.5 {
+            self.scroll_offset = self.scroll_target;
+        } else {
+            self.scroll_offset += remaining * progress;
+            ctx.request_anim_frame();
+        }
+        ctx.request_paint();
+    }
+
+    pub fn paint(&mut self, ctx: &mut PaintCtx, area: Size) {
+        if !self.visible {
+            return;
+        }
+        const PADDING: f64 = 16.0;
+        const TITLE_SPACING: f64 = 8.0;
+
+        let panel_origin = Point::new(
+            (area.width - INFO_PANEL_SIZE.width) / 2.0,
+            (area.height - INFO_PANEL_SIZE.height) / 2.0,
+        );
+        let panel_rect = Rect::from_origin_size(panel_origin, INFO_PANEL_SIZE);
+        ctx.fill(panel_rect.to_rounded_rect(8.0), &PANEL_COLOR);
+
+        let text_width = INFO_PANEL_SIZE.width - 2.0 * PADDING;
+        let title_text = ctx
+            .text()
+            .new_text_layout(self.title.clone())
+            .font(FontFamily::SYSTEM_UI, 18.0)
+            .text_color(Color::WHITE)
+            .max_width(text_width)
+            .build()
+            .unwrap();
+        let description_text = ctx
+            .text()
+            .new_text_layout(self.description.clone())
+            .font(FontFamily::SYSTEM_UI, 14.0)
+            .text_color(Color::WHITE)
+            .max_width(text_width)
+            .build()
+            .unwrap();
+        ctx.draw_text(
+            &title_text,
+            Point::new(panel_rect.x0 + PADDING, panel_rect.y0 + PADDING),
+        );
+
+        let description_rect = Rect::new(
+            panel_rect.x0 + PADDING,
+            panel_rect.y0 + PADDING + title_text.size().height + TITLE_SPACING,
+            panel_rect.x1 - PADDING,
+            panel_rect.y1 - PADDING,
+        );
+        // The window may have been resized since the last scroll.
+        self.max_scroll = (description_text.size().height - description_rect.height()).max(0.0);
+        self.scroll_target = self.scroll_target.min(self.max_scroll);
+        self.scroll_offset = self.scroll_offset.min(self.max_scroll);
+
+        let origin = Point::new(
+            description_rect.x0,
+            description_rect.y0 - self.scroll_offset,
+        );
+        ctx.with_save(|ctx| {
+            ctx.clip(description_rect);
+            ctx.draw_text(&description_text, origin);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use widget_cruncher::shell::keyboard_types::Key;
+
+    use super::*;
+    use crate::keymap::Action;
+
+    #[test]
+    fn help_lists_custom_bindings() {
+        let mut keymap = KeyMap::default();
+        keymap.bind(Key::Character("h".to_string()), Action::ShowHelp);
+        let lines = help_lines(&keymap);
+        assert!(lines.contains(&("h".to_string(), "Show this help")));
+        // The default binding is still there, since it's another key.
+        assert!(lines.contains(&("?".to_string(), "Show this help")));
+        assert_eq!(lines.len(), keymap.bindings.len());
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{Axis, ClipBox};
+use widget_cruncher::{Point, Rect};
+
+// A clipbox that scrolls `child` along `axis`. The other axis is constrained to the
+// viewport: constraining the scrolling axis instead would squash the child to fit,
+// and leaving both free would let it grow past the viewport on the other one.
+pub fn scrolling_clipbox<W: Widget>(child: W, axis: Axis) -> ClipBox<W> {
+    let clipbox = ClipBox::new(child);
+    match axis {
+        Axis::Horizontal => clipbox.constrain_vertical(true),
+        Axis::Vertical => clipbox.constrain_horizontal(true),
+    }
+}
+
+// Clamps a scroll offset to `[0, content_extent - viewport]` on both axes, so that
+// panning never shows the empty area past either end of the content.
+// If the content is smaller than the viewport, the offset is always zero.
+pub fn clamp_pan_offset(offset: Point, content_size: Size, viewport_size: Size) -> Point {
+    let max_x = (content_size.width - viewport_size.width).max(0.0);
+    let max_y = (content_size.height - viewport_size.height).max(0.0);
+    Point::new(offset.x.max(0.0).min(max_x), offset.y.max(0.0).min(max_y))
+}
+
+// Pans the clipbox to `offset`, clamped to its content. Returns true if the viewport moved.
+pub fn pan_clipbox_to<W: Widget>(clipbox: &mut ClipBox<W>, offset: Point) -> bool {
+    let offset = clamp_pan_offset(offset, clipbox.content_size(), clipbox.viewport_size());
+    clipbox.pan_to(offset)
+}
+
+// Pans the clipbox so that `center` is in the middle of its viewport, as far as the
+// content allows. Returns true if the viewport moved.
+pub fn center_clipbox_on<W: Widget>(clipbox: &mut ClipBox<W>, center: Point) -> bool {
+    let offset = centered_offset(clipbox, center);
+    pan_clipbox_to(clipbox, offset)
+}
+
+// The offset `center_clipbox_on` pans to.
+pub fn centered_offset<W: Widget>(clipbox: &ClipBox<W>, center: Point) -> Point {
+    center_offset(center, clipbox.content_size(), clipbox.viewport_size())
+}
+
+// The offset that puts `center` in the middle of the viewport, clamped to the content.
+fn center_offset(center: Point, content_size: Size, viewport_size: Size) -> Point {
+    let offset = Point::new(
+        center.x - viewport_size.width / 2.0,
+        center.y - viewport_size.height / 2.0,
+    );
+    clamp_pan_offset(offset, content_size, viewport_size)
+}
+
+// Pans the clipbox as little as possible for `rect`, in content coordinates, to be
+// inside its viewport, as far as the content allows. If `rect` doesn't fit, its
+// top-left corner is shown. Returns true if the viewport moved.
+pub fn reveal_in_clipbox<W: Widget>(clipbox: &mut ClipBox<W>, rect: Rect) -> bool {
+    let offset = revealing_offset(clipbox, rect);
+    pan_clipbox_to(clipbox, offset)
+}
+
+// The offset `reveal_in_clipbox` pans to.
+pub fn revealing_offset<W: Widget>(clipbox: &ClipBox<W>, rect: Rect) -> Point {
+    let origin = clipbox.viewport_origin();
+    let viewport_size = clipbox.viewport_size();
+    let offset = Point::new(
+        reveal_offset(origin.x, viewport_size.width, rect.x0, rect.x1),
+        reveal_offset(origin.y, viewport_size.height, rect.y0, rect.y1),
+    );
+    clamp_pan_offset(offset, clipbox.content_size(), viewport_size)
+}
+
+// The offset closest to `offset` that shows all of `start..end` along one axis.
+fn reveal_offset(offset: f64, viewport: f64, start: f64, end: f64) -> f64 {
+    if start < offset || end - start > viewport {
+        start
+    } else if end > offset + viewport {
+        end - viewport
+    } else {
+        offset
+    }
+}
+
+// A pan along one axis from `from` to `to`, that starts fast and eases into its
+// target over `duration`.
+pub struct PanAnimation {
+    pub from: f64,
+    pub to: f64,
+    pub duration: Duration,
+    // Time since the animation started, in nanoseconds.
+    pub elapsed: u64,
+}
+
+impl PanAnimation {
+    pub fn new(from: f64, to: f64, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0,
+        }
+    }
+
+    // Advances the animation by `interval` nanoseconds, and returns the offset to
+    // pan to.
+    pub fn step(&mut self, interval: u64) -> f64 {
+        self.elapsed += interval;
+        let t = (self.elapsed as f64 / self.duration.as_nanos() as f64).min(1.0);
+        let eased = 1.0 - (1.0 - t).powi(3);
+        self.from + (self.to - self.from) * eased
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.elapsed as u128 >= self.duration.as_nanos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: Size = Size::new(1000.0, 600.0);
+    const VIEWPORT: Size = Size::new(400.0, 300.0);
+
+    #[test]
+    fn offsets_within_the_content_are_kept() {
+        let offset = Point::new(250.0, 100.0);
+        assert_eq!(clamp_pan_offset(offset, CONTENT, VIEWPORT), offset);
+    }
+
+    #[test]
+    fn offsets_past_either_end_are_clamped() {
+        assert_eq!(
+            clamp_pan_offset(Point::new(-50.0, -10.0), CONTENT, VIEWPORT),
+            Point::ZERO
+        );
+        assert_eq!(
+            clamp_pan_offset(Point::new(5000.0, 5000.0), CONTENT, VIEWPORT),
+            Point::new(600.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn content_smaller_than_the_viewport_never_scrolls() {
+        let content = Size::new(200.0, 100.0);
+        assert_eq!(
+            clamp_pan_offset(Point::new(50.0, 50.0), content, VIEWPORT),
+            Point::ZERO
+        );
+    }
+
+    #[test]
+    fn centering_puts_the_point_mid_viewport() {
+        assert_eq!(
+            center_offset(Point::new(500.0, 300.0), CONTENT, VIEWPORT),
+            Point::new(300.0, 150.0)
+        );
+    }
+
+    #[test]
+    fn centering_near_the_edges_is_clamped() {
+        // The first and last items can't be centered without showing blank space.
+        assert_eq!(
+            center_offset(Point::new(50.0, 20.0), CONTENT, VIEWPORT),
+            Point::ZERO
+        );
+        assert_eq!(
+            center_offset(Point::new(980.0, 590.0), CONTENT, VIEWPORT),
+            Point::new(600.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn revealing_pans_as_little_as_possible() {
+        // Already in view: nothing moves.
+        assert_eq!(reveal_offset(100.0, 400.0, 150.0, 300.0), 100.0);
+        // Past either edge, the span ends up right against that edge.
+        assert_eq!(reveal_offset(100.0, 400.0, 50.0, 200.0), 50.0);
+        assert_eq!(reveal_offset(100.0, 400.0, 450.0, 600.0), 200.0);
+        // A span bigger than the viewport shows its start.
+        assert_eq!(reveal_offset(100.0, 400.0, 300.0, 900.0), 300.0);
+    }
+}
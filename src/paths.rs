@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+// The directory of our own that the default paths are in, under the platform's.
+const APP_DIR_NAME: &str = "disney-streaming-clone";
+
+// `name`, in our directory under the platform's config directory, like
+// ~/.config/disney-streaming-clone/favorites.txt on Linux. On platforms without
+// one, `name` is relative to the working directory instead.
+pub fn config_path(name: &str) -> PathBuf {
+    app_path(dirs::config_dir(), name)
+}
+
+// Same as `config_path`, under the platform's cache directory, like
+// ~/.cache/disney-streaming-clone on Linux. For what can be fetched again.
+pub fn cache_path(name: &str) -> PathBuf {
+    app_path(dirs::cache_dir(), name)
+}
+
+fn app_path(platform_dir: Option<PathBuf>, name: &str) -> PathBuf {
+    match platform_dir {
+        Some(dir) => dir.join(APP_DIR_NAME).join(name),
+        None => PathBuf::from(name),
+    }
+}
+
+// Writes `contents` to `path`, creating its directory first, since our own
+// directory doesn't exist until the first time something is saved.
+pub fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), std::io::Error> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_paths_are_in_our_own_directory() {
+        let path = app_path(Some(PathBuf::from("/home/alice/.config")), "favorites.txt");
+        assert_eq!(
+            path,
+            Path::new("/home/alice/.config/disney-streaming-clone/favorites.txt")
+        );
+    }
+
+    #[test]
+    fn app_paths_fall_back_to_the_working_directory() {
+        assert_eq!(app_path(None, "favorites.txt"), Path::new("favorites.txt"));
+    }
+
+    #[test]
+    fn write_file_creates_the_directory() {
+        let dir = std::env::temp_dir().join(format!("{}-{}", APP_DIR_NAME, std::process::id()));
+        let path = dir.join("nested").join("favorites.txt");
+        write_file(&path, "contents").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,522 @@
+use std::io::Read;
+use std::process::{Child, ChildStderr, ChildStdout, Command as Process, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{
+    FontFamily, ImageFormat, InterpolationMode, Text, TextLayout, TextLayoutBuilder,
+};
+use widget_cruncher::promise::PromiseToken;
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::AsWidgetPod;
+use widget_cruncher::{Color, Command, Point, Rect, Selector, Target};
+
+use crate::config::AppConfig;
+use crate::keymap::Action;
+
+// Sent by the RootWidget, which gets the keys while the player is open.
+pub const TOGGLE_PLAYBACK: Selector = Selector::new("toggle_playback");
+// The payload is in seconds, negative to seek back.
+pub const SEEK_BY: Selector<f64> = Selector::new("seek_by");
+// Sent by the VideoPlayer to itself once it's added, since it can't start
+// playback from `lifecycle`.
+const START_PLAYBACK: Selector = Selector::new("start_playback");
+
+// How far Left and Right seek.
+pub const SEEK_STEP: f64 = 10.0;
+
+// Frames are scaled and letterboxed to this size by ffmpeg, so that we know how
+// many bytes each one takes before the stream is even opened.
+const FRAME_WIDTH: usize = 1280;
+const FRAME_HEIGHT: usize = 720;
+const FRAME_BYTES: usize = FRAME_WIDTH * FRAME_HEIGHT * 4;
+
+const CONTROLS_HEIGHT: f64 = 88.0;
+const PADDING: f64 = 24.0;
+const CONTROLS_COLOR: Color = Color::rgba8(0x00, 0x00, 0x00, 0xb0);
+const TRACK_COLOR: Color = Color::rgba8(0xff, 0xff, 0xff, 0x40);
+const FILL_COLOR: Color = Color::WHITE;
+const DETAIL_COLOR: Color = Color::rgb8(0xa0, 0xa4, 0xb0);
+
+// Plays a stream URL, or a local file, over the grid. Decoding is left to the
+// ffmpeg command line tools: ffmpeg sends us raw frames, ffplay plays the sound
+// without a window, and ffprobe gives the length of the seek bar. Pausing and
+// seeking stop both processes and start them again at the new position.
+pub struct VideoPlayer {
+    pub url: String,
+    pub title: String,
+    // Like "Press Escape to go back.", built from the keymap the player was opened with.
+    pub back_hint: String,
+    // Between 0.0 and 1.0. Fixed for as long as the player is open.
+    pub volume: f64,
+
+    // None while paused.
+    decoder: Option<Decoder>,
+    // The last frame we got. It stays up while paused.
+    pub frame: Option<Vec<u8>>,
+
+    // Where playback was when it was last started or paused, in seconds. While
+    // playing, the current position is this plus the time since `resumed_at`.
+    pub start_position: f64,
+    pub resumed_at: Option<Instant>,
+    // In seconds. None until ffprobe answers, and for live streams.
+    pub duration: Option<f64>,
+    pub duration_promise: PromiseToken<Option<f64>>,
+
+    pub ended: bool,
+    pub error: Option<String>,
+}
+
+// The ffmpeg and ffplay processes playing from a given position, and what the
+// thread reading ffmpeg's output got so far. Dropping it stops both processes.
+struct Decoder {
+    video: Child,
+    audio: Option<Child>,
+    output: Arc<Mutex<DecoderOutput>>,
+}
+
+#[derive(Default)]
+struct DecoderOutput {
+    // The latest frame, until the player takes it.
+    frame: Option<Vec<u8>>,
+    // Set once ffmpeg's output ends.
+    finished: bool,
+    // What ffmpeg printed, if it ended without sending a single frame.
+    error: Option<String>,
+}
+
+// --- METHODS ---
+
+impl VideoPlayer {
+    pub fn new(url: String, title: String, volume: f64, config: &AppConfig) -> Self {
+        let back_hint = match config.keymap.key(Action::Dismiss) {
+            Some(key) => format!("Press {} to go back.", key),
+            None => String::new(),
+        };
+        Self {
+            url,
+            title,
+            back_hint,
+            volume,
+            decoder: None,
+            frame: None,
+            start_position: 0.0,
+            resumed_at: None,
+            duration: None,
+            duration_promise: PromiseToken::empty(),
+            ended: false,
+            error: None,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.decoder.is_some()
+    }
+
+    // In seconds.
+    pub fn position(&self) -> f64 {
+        let elapsed = self
+            .resumed_at
+            .map_or(0.0, |resumed_at| resumed_at.elapsed().as_secs_f64());
+        let position = self.start_position + elapsed;
+        match self.duration {
+            Some(duration) => position.min(duration),
+            None => position,
+        }
+    }
+
+    fn play(&mut self, ctx: &mut EventCtx) {
+        if self.ended {
+            self.ended = false;
+            self.start_position = 0.0;
+        }
+        match Decoder::start(&self.url, self.start_position, self.volume) {
+            Ok(decoder) => {
+                self.decoder = Some(decoder);
+                self.resumed_at = Some(Instant::now());
+                ctx.request_anim_frame();
+            }
+            Err(err) => {
+                tracing::error!("Couldn't play {}: {}", self.url, err);
+                self.error = Some(err);
+            }
+        }
+        ctx.request_paint();
+    }
+
+    fn pause(&mut self, ctx: &mut EventCtx) {
+        self.start_position = self.position();
+        self.resumed_at = None;
+        self.decoder = None;
+        ctx.request_paint();
+    }
+
+    // While paused, the frame only catches up with the new position once
+    // playback resumes.
+    fn seek_by(&mut self, ctx: &mut EventCtx, delta: f64) {
+        let playing = self.is_playing();
+        self.pause(ctx);
+        let mut position = (self.start_position + delta).max(0.0);
+        if let Some(duration) = self.duration {
+            position = position.min(duration);
+        }
+        self.start_position = position;
+        self.ended = false;
+        if playing {
+            self.play(ctx);
+        }
+    }
+
+    // Shows the latest frame from the decoder, and stops once the stream ends.
+    fn on_anim_frame(&mut self, ctx: &mut EventCtx) {
+        let decoder = match &self.decoder {
+            Some(decoder) => decoder,
+            None => return,
+        };
+        let (frame, finished, error) = {
+            let mut output = decoder.output.lock().unwrap();
+            (output.frame.take(), output.finished, output.error.take())
+        };
+        if let Some(frame) = frame {
+            self.frame = Some(frame);
+            ctx.request_paint();
+        }
+        if finished {
+            if let Some(error) = error {
+                tracing::error!("Couldn't play {}: {}", self.url, error);
+                self.error = Some(error);
+            }
+            self.pause(ctx);
+            self.ended = true;
+        } else {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn paint_frame(&self, ctx: &mut PaintCtx, frame: &[u8]) {
+        let image =
+            match ctx.make_image(FRAME_WIDTH, FRAME_HEIGHT, frame, ImageFormat::RgbaSeparate) {
+                Ok(image) => image,
+                Err(err) => {
+                    tracing::error!("Couldn't draw a video frame: {}", err);
+                    return;
+                }
+            };
+        // Frames are letterboxed by ffmpeg, so only their own aspect ratio matters.
+        let size = ctx.size();
+        let frame_ratio = FRAME_WIDTH as f64 / FRAME_HEIGHT as f64;
+        let width = size.width.min(size.height * frame_ratio);
+        let frame_size = Size::new(width, width / frame_ratio);
+        let origin = Point::new(
+            (size.width - frame_size.width) / 2.0,
+            (size.height - frame_size.height) / 2.0,
+        );
+        let rect = Rect::from_origin_size(origin, frame_size);
+        ctx.draw_image(&image, rect, InterpolationMode::Bilinear);
+    }
+
+    fn paint_error(&self, ctx: &mut PaintCtx, error: &str) {
+        let size = ctx.size();
+        let text = ctx
+            .text()
+            .new_text_layout(format!("Couldn't play {}\n{}", self.title, error))
+            .font(FontFamily::SYSTEM_UI, 16.0)
+            .text_color(Color::WHITE)
+            .max_width(size.width - 2.0 * PADDING)
+            .build()
+            .unwrap();
+        let origin = Point::new(PADDING, (size.height - text.size().height) / 2.0);
+        ctx.draw_text(&text, origin);
+    }
+
+    // The title, play state, seek bar and elapsed time, along the bottom edge.
+    fn paint_controls(&self, ctx: &mut PaintCtx) {
+        let size = ctx.size();
+        let panel = Rect::new(0.0, size.height - CONTROLS_HEIGHT, size.width, size.height);
+        ctx.fill(panel, &CONTROLS_COLOR);
+
+        let state = if self.is_playing() {
+            "\u{275a}\u{275a}"
+        } else {
+            "\u{25b6}"
+        };
+        let title_text = ctx
+            .text()
+            .new_text_layout(format!("{}  {}", state, self.title))
+            .font(FontFamily::SYSTEM_UI, 16.0)
+            .text_color(Color::WHITE)
+            .max_width(size.width / 2.0 - PADDING)
+            .build()
+            .unwrap();
+        ctx.draw_text(&title_text, Point::new(PADDING, panel.y0 + 12.0));
+
+        let position = self.position();
+        let time = match self.duration {
+            Some(duration) => format!("{} / {}", format_time(position), format_time(duration)),
+            None => format_time(position),
+        };
+        let time_text = ctx
+            .text()
+            .new_text_layout(time)
+            .font(FontFamily::SYSTEM_UI, 13.0)
+            .text_color(DETAIL_COLOR)
+            .build()
+            .unwrap();
+        let time_size = time_text.size();
+        let bar_y = panel.y1 - PADDING;
+        ctx.draw_text(
+            &time_text,
+            Point::new(
+                size.width - PADDING - time_size.width,
+                bar_y - time_size.height / 2.0,
+            ),
+        );
+
+        // Without a duration, there's nothing to fill the track with.
+        let track = Rect::new(
+            PADDING,
+            bar_y - 2.0,
+            size.width - 2.0 * PADDING - time_size.width,
+            bar_y + 2.0,
+        );
+        ctx.fill(track, &TRACK_COLOR);
+        if let Some(duration) = self.duration.filter(|duration| *duration > 0.0) {
+            let progress = (position / duration).min(1.0);
+            let fill = Rect::new(
+                track.x0,
+                track.y0,
+                track.x0 + track.width() * progress,
+                track.y1,
+            );
+            ctx.fill(fill, &FILL_COLOR);
+        }
+
+        if !self.back_hint.is_empty() {
+            let hint_text = ctx
+                .text()
+                .new_text_layout(self.back_hint.clone())
+                .font(FontFamily::SYSTEM_UI, 13.0)
+                .text_color(DETAIL_COLOR)
+                .build()
+                .unwrap();
+            let hint_origin = Point::new(
+                size.width - PADDING - hint_text.size().width,
+                panel.y0 + 12.0,
+            );
+            ctx.draw_text(&hint_text, hint_origin);
+        }
+    }
+}
+
+impl Decoder {
+    // Starts playing `url` from `position`, in seconds. Only fails if ffmpeg can't
+    // be run at all: a stream it can't open shows up as `DecoderOutput::error`.
+    fn start(url: &str, position: f64, volume: f64) -> Result<Self, String> {
+        let position = format!("{:.3}", position);
+        // ffplay takes the volume as a percentage.
+        let volume = format!("{:.0}", volume.clamp(0.0, 1.0) * 100.0);
+        let filter = format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2",
+            w = FRAME_WIDTH,
+            h = FRAME_HEIGHT,
+        );
+        // `-re` makes ffmpeg decode in real time, so frames arrive when they're due.
+        let mut video = Process::new("ffmpeg")
+            .args([
+                "-hide_banner",
+                "-loglevel",
+                "error",
+                "-ss",
+                position.as_str(),
+                "-re",
+            ])
+            .args(["-i", url, "-an", "-vf", filter.as_str()])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba", "pipe:1"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("couldn't run ffmpeg: {}", err))?;
+        let stdout = video.stdout.take().unwrap();
+        let stderr = video.stderr.take().unwrap();
+        let output = Arc::new(Mutex::new(DecoderOutput::default()));
+        let thread_output = output.clone();
+        thread::spawn(move || read_frames(stdout, stderr, thread_output));
+
+        let audio = Process::new("ffplay")
+            .args(["-hide_banner", "-loglevel", "error", "-nodisp", "-autoexit"])
+            .args(["-volume", volume.as_str()])
+            .args(["-ss", position.as_str(), "-i", url])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        // Without ffplay, the video plays without sound.
+        let audio = match audio {
+            Ok(audio) => Some(audio),
+            Err(err) => {
+                tracing::warn!("Playing without sound, couldn't run ffplay: {}", err);
+                None
+            }
+        };
+
+        Ok(Self {
+            video,
+            audio,
+            output,
+        })
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // These only fail if the process already exited.
+        let _ = self.video.kill();
+        let _ = self.video.wait();
+        if let Some(audio) = &mut self.audio {
+            let _ = audio.kill();
+            let _ = audio.wait();
+        }
+    }
+}
+
+// Runs on its own thread until ffmpeg's output ends, either with the stream or
+// because the Decoder was dropped.
+fn read_frames(
+    mut stdout: ChildStdout,
+    mut stderr: ChildStderr,
+    output: Arc<Mutex<DecoderOutput>>,
+) {
+    // ffmpeg blocks once the stderr pipe is full, so it has to be read while
+    // frames are, not after.
+    let errors = thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+    let mut got_frame = false;
+    loop {
+        let mut frame = vec![0; FRAME_BYTES];
+        if stdout.read_exact(&mut frame).is_err() {
+            break;
+        }
+        got_frame = true;
+        output.lock().unwrap().frame = Some(frame);
+    }
+    let errors = errors.join().unwrap_or_default();
+    let mut output = output.lock().unwrap();
+    output.finished = true;
+    if !got_frame {
+        let errors = errors.trim();
+        output.error = Some(if errors.is_empty() {
+            "the stream has no video".to_string()
+        } else {
+            errors.to_string()
+        });
+    }
+}
+
+// The length of the stream in seconds, or None if ffprobe doesn't know it (say,
+// for a live stream) or isn't installed.
+fn probe_duration(url: &str) -> Option<f64> {
+    let output = Process::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1", url])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+// Like "1:05:09", or "5:09" under an hour.
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for VideoPlayer {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        ctx.init();
+        match event {
+            // This happens after the callback passed to `ctx.compute_in_background` returns
+            Event::PromiseResult(result) => {
+                if let Some(duration) = result.try_get(self.duration_promise) {
+                    self.duration = duration;
+                    ctx.request_paint();
+                }
+            }
+            Event::AnimFrame(_) => {
+                self.on_anim_frame(ctx);
+            }
+            Event::Command(command) => {
+                if command.is(START_PLAYBACK) {
+                    self.play(ctx);
+                }
+                if command.is(TOGGLE_PLAYBACK) && self.error.is_none() {
+                    if self.is_playing() {
+                        self.pause(ctx);
+                    } else {
+                        self.play(ctx);
+                    }
+                }
+                if let Some(delta) = command.try_get(SEEK_BY) {
+                    if self.error.is_none() {
+                        self.seek_by(ctx, *delta);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
+        ctx.init();
+        if let LifeCycle::WidgetAdded = event {
+            let url = self.url.clone();
+            self.duration_promise = ctx.compute_in_background(move |_| probe_duration(&url));
+            ctx.submit_command(Command::from(START_PLAYBACK).to(Target::Widget(ctx.widget_id())));
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &Color::BLACK);
+        match (&self.error, &self.frame) {
+            (Some(error), _) => self.paint_error(ctx, error),
+            (None, Some(frame)) => self.paint_frame(ctx, frame),
+            (None, None) => {}
+        }
+        self.paint_controls(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("VideoPlayer")
+    }
+}
@@ -1,180 +1,3471 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
 use smallvec::{smallvec, SmallVec};
 use tracing::{trace_span, Span};
 
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
 use widget_cruncher::promise::PromiseToken;
 use widget_cruncher::shell::keyboard_types::Key;
 use widget_cruncher::widget::prelude::*;
-use widget_cruncher::widget::{AsWidgetPod, ClipBox, Flex, Spinner, WidgetPod};
-use widget_cruncher::{Color, Command, Point, Selector, Target};
+use widget_cruncher::widget::{AsWidgetPod, Axis, ClipBox, Flex, Label, Spinner, WidgetPod};
+use widget_cruncher::{theme, Color, Command, Point, Rect, Screen, Selector, Target, TimerToken};
 
-use crate::content_set::{ContentSet, ContentSetMetadata};
-use crate::thumbnail::CHANGE_SELECTED_ITEM;
+use crate::api::{self, fetch_home, Lenient};
+use crate::breadcrumbs::{Breadcrumbs, BREADCRUMB_HEIGHT};
+use crate::clock::{SharedClock, SystemClock};
+use crate::config::{AppConfig, LoadMode, PanAlignment, StartupFocus};
+use crate::content_set::{
+    tiles_per_page, ContentSet, ContentSetMetadata, HeaderControl, LoadPhase, TileMetadata,
+    ACTIVATE_ITEM, ACTIVATE_TILE, CENTER_ROW, COLLAPSE_ROWS, CYCLE_SORT, DESCRIBE_TILE,
+    FAVORITES_SET_TYPE, FIND_TILE, FIND_UNVISITED, FIND_UNVISITED_DONE, HEADER_CONTROLS,
+    JUMP_TO_TILE, RELOAD_ROW, REVEAL_ROW, ROW_COMPLETE, ROW_DEFERRED, ROW_LOADED,
+    SCROLL_SNAP_DELAY, SEE_ALL, SHOW_TILE_INFO, SNAP_GRID_TO, SNAP_ROWS, TILE_DESCRIPTION,
+    TILE_INFO, UNVISITED_TILE, VISIBLE_AREA,
+};
+use crate::detail_page::DetailPage;
+use crate::error_card::retry_hint;
+use crate::hit_map::SharedHitMap;
+use crate::image_cache::DECODED_IMAGES;
+use crate::keymap::Action;
+use crate::net::{configure_client, CancelHandle, FetchError};
+use crate::overlay::{HelpOverlay, InfoOverlay, SliderOverlay};
+use crate::pan::{
+    centered_offset, pan_clipbox_to, revealing_offset, scrolling_clipbox, PanAnimation,
+};
+use crate::player::{VideoPlayer, SEEK_BY, SEEK_STEP, TOGGLE_PLAYBACK};
+use crate::search::{SearchPage, SEARCH_KEY};
+use crate::session::{SessionState, SharedSession};
+use crate::settings::BACKGROUND_COLOR;
+use crate::settings_page::{SettingsPage, SETTINGS_ACTION, SETTINGS_CHANGED};
+use crate::snapshot::{CatalogSnapshot, RowSnapshot};
+use crate::sound::{Silent, SoundHandler};
+use crate::speech::SpeechHandler;
+use crate::thumbnail::{
+    cell_size, FocusTarget, SharedSelection, CHANGE_FOCUS, CHANGE_SELECTED_ITEM, CLICK_TILE,
+};
+use crate::tile_image::configure_image_workers;
+use crate::tile_row::{APPEND_TILES, REPLACE_FIRST_PAGE, TOGGLE_FAVORITE};
+use crate::window_size::clamp_to_display;
+use crate::workers::WorkerPool;
 
 const REQUEST_FOCUS: Selector = Selector::new("request_focus");
+// Sent when the grid gains (true) or loses (false) keyboard focus, so that an
+// app embedding it can tell which shortcuts are its own. See `RootWidget::has_focus`.
+pub const GRID_FOCUS_CHANGED: Selector<bool> = Selector::new("grid_focus_changed");
+// Switches to the profile with the given name, from `AppConfig::profiles`.
+pub const SWITCH_PROFILE: Selector<String> = Selector::new("switch_profile");
+// Sent by the RootWidget to itself after a layout that moved the rows, since it
+// can't tell them from `layout`. See `check_visible_rows`.
+const CHECK_VISIBLE_ROWS: Selector = Selector::new("check_visible_rows");
+// Sent by the RootWidget to itself once it's added, since it can't fetch the
+// collection from `lifecycle`.
+const LOAD_COLLECTION: Selector = Selector::new("load_collection");
+
+// How often we check whether a row entered or left its availability window.
+const AVAILABILITY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+// How long after the last letter typed with Alt held the next one starts a new search.
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Vertical space between rows.
+pub const ROW_SPACING: f64 = 30.0;
+
+// How long the grid takes to glide to the selected row, with pan animations.
+const ROW_SCROLL_DURATION: Duration = Duration::from_millis(300);
+
+// How long the selection has to stay on a tile before it's announced.
+const SPEECH_DELAY: Duration = Duration::from_millis(400);
+
+// Why a container of the home feed couldn't be turned into a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContainerError {
+    // The container doesn't have the shape of `api::Container`.
+    Malformed,
+    MissingTitle,
+    MissingRefId,
+}
+
+pub fn parse_container(
+    container: &Lenient<api::Container>,
+) -> Result<ContentSetMetadata, ContainerError> {
+    let set = &container.parsed().ok_or(ContainerError::Malformed)?.set;
+    let set_text = |field: &Option<api::TextField>| {
+        field
+            .as_ref()
+            .and_then(|field| field.get("set"))
+            .map(|text| text.to_string())
+    };
+    let title = set_text(&set.text.title).ok_or(ContainerError::MissingTitle)?;
+    let description = set_text(&set.text.description);
+    let ref_id = set.ref_id.clone().ok_or(ContainerError::MissingRefId)?;
+    // SetRef containers give the type of the referenced set in `refType`.
+    let set_type = set
+        .ref_type
+        .clone()
+        .or_else(|| set.set_type.clone())
+        .unwrap_or_else(|| "CuratedSet".to_string());
+    let parse_time =
+        |seconds: Option<u64>| seconds.map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds));
+    let availability = set.availability.as_ref();
+    let available_from = parse_time(availability.and_then(|availability| availability.start));
+    let available_until = parse_time(availability.and_then(|availability| availability.end));
+    let load_priority = set.load_priority;
+    let inline_set = set.items.as_ref().map(|items| {
+        Arc::new(api::CuratedSet {
+            items: items.clone(),
+            meta: set.meta.clone().unwrap_or_default(),
+        })
+    });
+    Ok(ContentSetMetadata {
+        title,
+        description,
+        ref_id,
+        set_type,
+        available_from,
+        available_until,
+        load_priority,
+        inline_set,
+    })
+}
+
+// Builds a row per entry of `children`, in order. A row whose metadata trips up
+// `build` comes back as its title, so that the caller can put an error message
+// in its place instead of leaving the whole grid on the spinner.
+fn build_guarded<T>(
+    children: Vec<ContentSetMetadata>,
+    mut build: impl FnMut(usize, ContentSetMetadata) -> T,
+) -> Vec<(usize, Result<T, String>)> {
+    children
+        .into_iter()
+        .enumerate()
+        .map(|(row, child)| {
+            let title = child.title.clone();
+            let built = catch_unwind(AssertUnwindSafe(|| build(row, child)));
+            (row, built.map_err(|_| title))
+        })
+        .collect()
+}
+
+// Shown in place of a row that `build_guarded` couldn't build.
+fn unbuildable_row_message(title: &str) -> String {
+    format!("'{}' couldn't be displayed", title)
+}
 
-// Loads and parses https://cd-static.bamgrid.com/dp-117731241344/home.json
-fn load_collection(url: &str) -> Result<Vec<ContentSetMetadata>, reqwest::Error> {
-    let json: serde_json::Value = reqwest::blocking::get(url)?.json()?;
-    let containers = json["data"]["StandardCollection"]["containers"].clone();
-    let container_items = containers
-        .as_array()
-        .unwrap()
+// Loads and parses the home collection, `config.collection_url`.
+pub fn load_collection(
+    url: &str,
+    timeout: Duration,
+    cancel: &CancelHandle,
+) -> Result<Vec<ContentSetMetadata>, FetchError> {
+    let home = fetch_home(url, timeout, cancel)?;
+    let container_items = home
+        .data
+        .standard_collection
+        .containers
         .iter()
-        .filter_map(|container| {
-            let title = container["set"]["text"]["title"]["full"]["set"]["default"]["content"]
-                .as_str()?
-                .to_string();
-            let ref_id = container["set"]["refId"].as_str()?.to_string();
-            Some(ContentSetMetadata { title, ref_id })
+        .enumerate()
+        .filter_map(|(index, container)| match parse_container(container) {
+            Ok(metadata) => Some(metadata),
+            Err(reason) => {
+                tracing::warn!("Skipping container #{} of {}: {}", index, url, reason);
+                None
+            }
         })
         .collect::<Vec<_>>();
     Ok(container_items)
 }
 
+// Where the collection is in loading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollectionState {
+    Loading,
+    Loaded,
+    // The feed loaded fine, but has no rows to show. Unlike a failed load, this
+    // gets a message instead of an empty grid.
+    Empty,
+    Failed,
+}
+
+// Applied to the rows of the collection before their widgets are built, eg to
+// reorder, filter, rename, or add rows. Runs before `AppConfig::row_limit`.
+pub type CatalogTransform = Box<dyn FnMut(Vec<ContentSetMetadata>) -> Vec<ContentSetMetadata>>;
+
 pub struct RootWidget {
+    // The config of the active profile. See `AppConfig::for_profile`.
+    pub config: Arc<AppConfig>,
+    // The config we were created with, that every profile's is derived from.
+    pub base_config: Arc<AppConfig>,
+    // Index of the active profile in `AppConfig::profiles`, if there are any.
+    pub active_profile: Option<usize>,
+    // Where the selection was when each profile was switched away from, by name,
+    // so switching back picks up from there.
+    pub profile_selections: HashMap<String, (usize, usize)>,
+
     // The promise token is mostly a type-system aid to "prove" to the compiler
     // that the result you're getting is the same you asked for earlier.
-    pub children_promise: PromiseToken<Vec<ContentSetMetadata>>,
+    pub children_promise: PromiseToken<Result<Vec<ContentSetMetadata>, FetchError>>,
+    // Passed to the collection load in flight. Replaced, and the old one
+    // cancelled, every time the collection is reloaded.
+    pub load_cancel: CancelHandle,
 
     // What's we're actually displaying.
     pub children: WidgetPod<ClipBox<Flex>>,
+    // Row 0, if it's `config.pinned_row`. It's laid out above `children`, so it
+    // doesn't scroll with the other rows.
+    pub pinned_row: Option<WidgetPod<ContentSet>>,
+    // Opened over the grid with Action::Search. Pages opened from its results go
+    // on top of it.
+    pub search: Option<WidgetPod<SearchPage>>,
+    // Pages opened over the grid, the last one on top. Dismiss closes the top one.
+    pub pages: Vec<WidgetPod<DetailPage>>,
+    // Opened over the grid with Action::ShowSettings. The settings themselves
+    // live in the session, and are applied to the Env passed to every child.
+    pub settings_page: Option<WidgetPod<SettingsPage>>,
+    // Plays the activated tile, over the grid and its pages. See
+    // `AppConfig::player_stream_url`.
+    pub player: Option<WidgetPod<VideoPlayer>>,
 
     // A very bare-bones "cursor" sent to every child to
     // choose which one should have the "is selected" outline and big size.
+    // `selection` is what children actually read; see `broadcast_selection`.
     pub selected_item: (usize, usize),
+    pub selection: SharedSelection,
+    pub session: SharedSession,
+    // Shared with every ContentSet, so that all loads count against the same limit.
+    pub workers: Arc<WorkerPool>,
+    // Where animations get the time from. See `with_clock`.
+    pub clock: SharedClock,
+    // Where every visible tile was last painted. See `tile_at`.
+    pub hit_map: SharedHitMap,
+    // False if the config asks for no initial selection and the user hasn't pressed
+    // a key yet. Nothing is highlighted until then.
+    pub selection_visible: bool,
+
+    // Where keyboard focus is. Usually the selected tile, but Tab moves it
+    // to the selected row's header without changing the selection.
+    pub focus: FocusTarget,
+    // While rows are collapsed to their headers, focus stays on headers, and the
+    // selection is left where it was until they're expanded again.
+    pub rows_collapsed: bool,
+
+    // Every call to `start_load` begins a new generation. `pending_rows` is None
+    // until the collection itself resolves, then holds the rows whose first page
+    // hasn't resolved yet. Rows waiting to come into view are taken out of it,
+    // since they may never load. `ready` is set once it's empty, and stays set
+    // until the next generation, even if the rows are rebuilt in between.
+    pub load_generation: u64,
+    pub pending_rows: Option<HashSet<usize>>,
+    pub ready: bool,
+    pub collection_state: CollectionState,
+    // Why the collection failed to load, shown with CollectionState::Failed.
+    pub collection_error: Option<String>,
+
+    // Called once per generation, when the collection and all the rows that started
+    // loading have resolved.
+    pub on_ready: Option<Box<dyn FnMut(u64)>>,
+
+    pub catalog_transform: Option<CatalogTransform>,
+
+    // Every row of the collection, after `catalog_transform`, including the ones
+    // outside their availability window. The rows actually built are the
+    // available ones, re-checked every time `availability_timer` fires.
+    pub catalog: Vec<ContentSetMetadata>,
+    pub availability_timer: TimerToken,
+    // Restarted by every wheel event, with ScrollSnap::vertical.
+    pub snap_timer: TimerToken,
+    // Where the grid is gliding to the selected row, while it is. See `scroll_grid_to`.
+    pub grid_scroll: Option<PanAnimation>,
+
+    // Told about navigation and activation, unless `config.sounds_muted` is set.
+    pub sound_handler: Box<dyn SoundHandler>,
+    // Told what the selected tile is, once the selection stays put for SPEECH_DELAY.
+    pub speech_handler: Box<dyn SpeechHandler>,
+    pub speech_timer: TimerToken,
+    // Runs while the session has unsaved changes. See `schedule_session_save`.
+    pub session_save_timer: TimerToken,
+
+    // Shown with the 'v' key. Its value is the volume streams are played at;
+    // `config.volume` is only where it starts.
+    pub volume_overlay: SliderOverlay,
+    // Shown with the '?' key. Lists the bindings of `config.keymap`.
+    pub help_overlay: HelpOverlay,
+    // Shown with the 'i' key, with the description of the selected tile.
+    pub info_overlay: InfoOverlay,
+    // The path to the page on top, over the pages. See `crumbs`.
+    pub breadcrumbs: Breadcrumbs,
+
+    // Kept up to date from the commands rows send as they load.
+    // `on_snapshot` is called every time it changes.
+    pub snapshot: CatalogSnapshot,
+    pub on_snapshot: Option<Box<dyn FnMut(&CatalogSnapshot)>>,
+
+    // Row force-loaded with the 'l' key, whose result we log once it resolves.
+    pub diagnosed_row: Option<usize>,
+
+    // Where the rows were when we last sent VISIBLE_AREA. None until the rows of
+    // the collection are first laid out.
+    pub visible_rows_key: Option<(Rect, Point, Size)>,
+    // How many whole tiles fit across a row, as of the last layout. With
+    // `PanAlignment::Paged`, Left and Right at the edge of a page move by this.
+    pub tiles_per_page: usize,
+
+    // Failed rows reloaded together with the 'R' key that haven't resolved yet,
+    // and how many of the ones that did have loaded. The total is logged once
+    // the last one resolves.
+    pub retrying_rows: Vec<usize>,
+    pub recovered_rows: usize,
+
+    // The answers to FIND_UNVISITED received so far, as (row, column).
+    pub unvisited_tiles: Vec<(usize, usize)>,
+
+    // Letters typed with Alt held, to jump to a tile of the selected row by its
+    // title. Starts over after TYPE_AHEAD_TIMEOUT.
+    pub type_ahead: String,
+    pub last_type_ahead: Option<Instant>,
+
+    // Whether to take keyboard focus as soon as we're added. The standalone app
+    // wants this; a larger app embedding the grid probably manages focus itself.
+    pub grab_focus: bool,
+    // Cache of the grid's keyboard focus, updated on StatusChange::FocusChanged.
+    pub has_focus: bool,
 }
 
 impl RootWidget {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<AppConfig>) -> Self {
         let placeholder = Spinner::new();
         let column = Flex::column().with_child(placeholder);
-        let clipbox = ClipBox::new(column).constrain_horizontal(true);
+        let clipbox = scrolling_clipbox(column, Axis::Vertical);
+        let base_config = config.clone();
+        let active_profile = base_config.profiles.first().map(|_| 0);
+        let config = match base_config.profiles.first() {
+            Some(profile) => Arc::new(base_config.for_profile(profile)),
+            None => config,
+        };
+        let selected_item = config.initial_selection.unwrap_or((0, 0));
+        let selection_visible = config.initial_selection.is_some();
+        let focus = match config.startup_focus {
+            StartupFocus::FirstTile | StartupFocus::Hero => {
+                FocusTarget::Tile(selected_item.0, selected_item.1)
+            }
+            StartupFocus::FirstRowHeader => FocusTarget::RowHeader(selected_item.0, 0),
+        };
+        let volume_overlay = SliderOverlay::new("Volume", config.volume, 0.05);
+        let session = SessionState::load(&config);
+        DECODED_IMAGES.with(|images| images.borrow_mut().budget = config.decoded_image_budget);
+        // For apps that embed the grid without going through our `main`.
+        configure_client(&config.http);
+        configure_image_workers(config.image_workers);
+        let workers = Arc::new(WorkerPool::new(config.background_workers));
         Self {
+            config,
+            base_config,
+            active_profile,
+            profile_selections: HashMap::new(),
             children_promise: PromiseToken::empty(),
+            load_cancel: CancelHandle::default(),
             children: WidgetPod::new(clipbox),
-            selected_item: (0, 0),
+            pinned_row: None,
+            search: None,
+            settings_page: None,
+            pages: Vec::new(),
+            player: None,
+            selected_item,
+            selection: SharedSelection::default(),
+            session: Rc::new(RefCell::new(session)),
+            workers,
+            clock: Rc::new(SystemClock),
+            hit_map: SharedHitMap::default(),
+            selection_visible,
+            focus,
+            rows_collapsed: false,
+            load_generation: 0,
+            pending_rows: None,
+            ready: false,
+            collection_state: CollectionState::Loading,
+            collection_error: None,
+            on_ready: None,
+            catalog_transform: None,
+            catalog: Vec::new(),
+            availability_timer: TimerToken::INVALID,
+            snap_timer: TimerToken::INVALID,
+            grid_scroll: None,
+            sound_handler: Box::new(Silent),
+            speech_handler: Box::new(Silent),
+            speech_timer: TimerToken::INVALID,
+            session_save_timer: TimerToken::INVALID,
+            volume_overlay,
+            help_overlay: HelpOverlay::new(),
+            info_overlay: InfoOverlay::new(),
+            breadcrumbs: Breadcrumbs::new(),
+            snapshot: CatalogSnapshot::default(),
+            on_snapshot: None,
+            diagnosed_row: None,
+            visible_rows_key: None,
+            tiles_per_page: 1,
+            retrying_rows: Vec::new(),
+            recovered_rows: 0,
+            unvisited_tiles: Vec::new(),
+            type_ahead: String::new(),
+            last_type_ahead: None,
+            grab_focus: false,
+            has_focus: false,
         }
     }
-}
 
-// --- TRAIT IMPL ---
+    // Request keyboard focus once the widget is added, instead of waiting for
+    // the user (or the parent app) to focus it.
+    pub fn with_grab_focus(mut self) -> Self {
+        self.grab_focus = true;
+        self
+    }
 
-impl Widget for RootWidget {
-    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
-        ctx.init();
-        match event {
-            // This happens after the callback passed to `ctx.compute_in_background` returns
-            Event::PromiseResult(result) => {
-                if let Some(children) = result.try_get(self.children_promise) {
-                    // TODO - Need to find a more idiomatic way to do this.
-                    self.children.recurse_pass(
-                        "custom_pass",
-                        &mut ctx.widget_state,
-                        // clipbox is an alias of self.children in this closure
-                        |clipbox, clipbox_state| {
-                            clipbox.child.recurse_pass(
-                                "custom_pass",
-                                clipbox_state,
-                                |flex, flex_state| {
-                                    flex.clear(flex_state);
-                                    for (row, child) in children.into_iter().enumerate() {
-                                        flex.add_child(flex_state, ContentSet::new(row, child));
-                                        flex.add_spacer(flex_state, 30.0);
-                                    }
-                                    // when this closure returns, the framework automatically merges
-                                    // invalidated state
-                                },
-                            );
-                        },
-                    );
+    // Register a callback fired when the catalog is done loading, eg to hide a
+    // splash screen. The argument is the load generation.
+    pub fn with_on_ready(mut self, on_ready: impl FnMut(u64) + 'static) -> Self {
+        self.on_ready = Some(Box::new(on_ready));
+        self
+    }
 
-                    ctx.skip_child(&mut self.children);
-                    return;
-                }
+    // Register a function that rewrites the list of rows once the collection loads.
+    pub fn with_catalog_transform(
+        mut self,
+        transform: impl FnMut(Vec<ContentSetMetadata>) -> Vec<ContentSetMetadata> + 'static,
+    ) -> Self {
+        self.catalog_transform = Some(Box::new(transform));
+        self
+    }
+
+    // Replace the clock animations are timed with, eg with a FakeClock in tests.
+    // Must be called before the grid is added, since rows are built with it.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Register a handler to play sounds on navigation and activation.
+    pub fn with_sound_handler(mut self, sound_handler: impl SoundHandler + 'static) -> Self {
+        self.sound_handler = Box::new(sound_handler);
+        self
+    }
+
+    // Register a handler to announce the selected tile, eg through text-to-speech.
+    pub fn with_speech_handler(mut self, speech_handler: impl SpeechHandler + 'static) -> Self {
+        self.speech_handler = Box::new(speech_handler);
+        self
+    }
+
+    // Register a callback fired every time the catalog snapshot changes.
+    pub fn with_on_snapshot(mut self, on_snapshot: impl FnMut(&CatalogSnapshot) + 'static) -> Self {
+        self.on_snapshot = Some(Box::new(on_snapshot));
+        self
+    }
+
+    // The (row, column) of the tile under `point`, in window coordinates. This goes
+    // by what was last painted, so it takes scrolling and the selected tile's bigger
+    // size into account, and gaps between tiles don't hit anything.
+    pub fn tile_at(&self, point: Point) -> Option<(usize, usize)> {
+        if self.grid_covered() {
+            return None;
+        }
+        self.hit_map.borrow().tile_at(point)
+    }
+
+    // The levels open over the grid, from the bottom up, starting with the grid
+    // itself. Level 1 and up are the search page, the detail pages and the settings
+    // page, in the order they're stacked.
+    pub fn crumbs(&self) -> Vec<String> {
+        let mut crumbs = vec!["Home".to_string()];
+        if self.search.is_some() {
+            crumbs.push("Search".to_string());
+        }
+        for page in &self.pages {
+            let title = page.widget().tile.title.clone();
+            crumbs.push(title.unwrap_or_else(|| "Details".to_string()));
+        }
+        if self.settings_page.is_some() {
+            crumbs.push("Settings".to_string());
+        }
+        crumbs
+    }
+
+    // Closes every level above `level`, top first. See `crumbs`.
+    fn pop_to_level(&mut self, ctx: &mut EventCtx, level: usize) {
+        let mut depth = self.crumbs().len() - 1;
+        while depth > level {
+            if self.settings_page.is_some() {
+                self.close_settings(ctx);
+            } else if !self.pages.is_empty() {
+                self.close_detail_page(ctx);
+            } else {
+                self.close_search(ctx);
             }
-            Event::KeyDown(key_event) => {
-                // This is a HUGE cheat.
-                match &key_event.key {
-                    Key::ArrowDown => {
-                        self.selected_item.0 = self.selected_item.0.saturating_add(1);
-                    }
-                    Key::ArrowLeft => {
-                        self.selected_item.1 = self.selected_item.1.saturating_sub(1);
-                    }
-                    Key::ArrowRight => {
-                        self.selected_item.1 = self.selected_item.1.saturating_add(1);
-                    }
-                    Key::ArrowUp => {
-                        self.selected_item.0 = self.selected_item.0.saturating_sub(1);
-                    }
-                    _ => {}
-                }
+            depth -= 1;
+        }
+    }
+
+    // Whether an overlay or a page is drawn over the grid.
+    fn grid_covered(&self) -> bool {
+        self.volume_overlay.visible
+            || self.help_overlay.visible
+            || self.info_overlay.visible
+            || self.search.is_some()
+            || self.settings_page.is_some()
+            || !self.pages.is_empty()
+            || self.player.is_some()
+    }
+
+    // Where the scrolling rows start, in window coordinates. That's below the
+    // pinned row, if there is one.
+    fn scroll_area_top(&self, ctx: &EventCtx) -> f64 {
+        ctx.window_origin().y + self.children.layout_rect().y0
+    }
+
+    // Whether the grid has keyboard focus. Only the grid itself takes focus, so
+    // this is the same as whether key presses reach it.
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
+    // A copy of the current state of the catalog, for polling.
+    pub fn snapshot(&self) -> CatalogSnapshot {
+        self.snapshot.clone()
+    }
+
+    fn publish_snapshot(&mut self) {
+        self.snapshot.load_generation = self.load_generation;
+        self.snapshot.selected_item = if self.selection_visible {
+            Some(self.selected_item)
+        } else {
+            None
+        };
+        if let Some(on_snapshot) = &mut self.on_snapshot {
+            on_snapshot(&self.snapshot);
+        }
+    }
+
+    // Tells every thumbnail and row header about the current selection and focus.
+    // The selection is written before the command is sent, so every receiver
+    // sees the latest one, even if several commands are in flight.
+    fn broadcast_selection(&mut self, ctx: &mut EventCtx) {
+        self.selection.set(if self.selection_visible {
+            Some(self.selected_item)
+        } else {
+            None
+        });
+        ctx.submit_command(CHANGE_SELECTED_ITEM);
+        ctx.submit_command(CHANGE_FOCUS.with(self.focus));
+        self.publish_snapshot();
+    }
+
+    // Rows can shrink when they're refreshed. If the selection was past the end of
+    // `row`, moves it to the row's new last tile, and pans there.
+    fn clamp_selection_to_row(&mut self, ctx: &mut EventCtx, row: usize) {
+        let (selected_row, selected_column) = self.selected_item;
+        if selected_row != row {
+            return;
+        }
+        let tile_count = match self.snapshot.rows.get(row) {
+            Some(row_snapshot) => row_snapshot.tiles.len(),
+            None => return,
+        };
+        if tile_count == 0 || selected_column < tile_count {
+            return;
+        }
+        let column = tile_count - 1;
+        tracing::trace!(
+            "Row {} shrank to {} tiles, moving the selection from column {} to {}",
+            row,
+            tile_count,
+            selected_column,
+            column
+        );
+        self.selected_item = (row, column);
+        if let FocusTarget::Tile(..) = self.focus {
+            self.focus = FocusTarget::Tile(row, column);
+        }
+        self.broadcast_selection(ctx);
+    }
 
-                ctx.submit_command(CHANGE_SELECTED_ITEM.with(self.selected_item));
+    // Diagnostic for flaky feeds: selects the next row that hasn't loaded,
+    // and forces it to load again. The result is logged by `report_diagnosed_row`.
+    fn load_next_unloaded_row(&mut self, ctx: &mut EventCtx) {
+        let row = match self.snapshot.next_unloaded_row(self.selected_item.0) {
+            Some(row) => row,
+            None => {
+                tracing::info!("Every row is loaded");
+                return;
             }
-            Event::Command(command) if command.is(REQUEST_FOCUS) => {
-                ctx.request_focus();
+        };
+        tracing::info!("Loading row {} ('{}')", row, self.snapshot.rows[row].ref_id);
+        self.diagnosed_row = Some(row);
+        self.selected_item = (row, 0);
+        self.focus = FocusTarget::Tile(row, 0);
+        self.selection_visible = true;
+        ctx.submit_command(RELOAD_ROW.with(row));
+        self.broadcast_selection(ctx);
+    }
+
+    fn report_diagnosed_row(&mut self, row: usize, phase: LoadPhase) {
+        if self.diagnosed_row != Some(row) {
+            return;
+        }
+        self.diagnosed_row = None;
+        let row_snapshot = &self.snapshot.rows[row];
+        match phase {
+            LoadPhase::Failed => {
+                tracing::warn!("Row {} ('{}') failed to load", row, row_snapshot.ref_id)
             }
-            _ => {}
+            _ => tracing::info!(
+                "Row {} ('{}') loaded with {} tiles",
+                row,
+                row_snapshot.ref_id,
+                row_snapshot.tiles.len()
+            ),
         }
-        self.children.on_event(ctx, event, env)
     }
 
-    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+    // Reloads every row that failed, eg after a refresh that only partly worked,
+    // leaving the rows that loaded alone. The result is logged by `report_retried_row`.
+    fn retry_failed_rows(&mut self, ctx: &mut EventCtx) {
+        let failed_rows: Vec<usize> = self
+            .snapshot
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(row, row_snapshot)| {
+                row_snapshot.phase == LoadPhase::Failed && !self.retrying_rows.contains(row)
+            })
+            .map(|(row, _)| row)
+            .collect();
+        if failed_rows.is_empty() {
+            tracing::info!("No failed rows to retry");
+            return;
+        }
+        tracing::info!("Retrying {} failed rows", failed_rows.len());
+        if self.retrying_rows.is_empty() {
+            self.recovered_rows = 0;
+        }
+        for row in failed_rows {
+            self.retrying_rows.push(row);
+            ctx.submit_command(RELOAD_ROW.with(row));
+        }
+    }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
-        const COLLECTION_URL: &str = "https://cd-static.bamgrid.com/dp-117731241344/home.json";
+    fn report_retried_row(&mut self, row: usize, phase: LoadPhase) {
+        let len = self.retrying_rows.len();
+        self.retrying_rows
+            .retain(|retrying_row| *retrying_row != row);
+        if self.retrying_rows.len() == len {
+            return;
+        }
+        if phase != LoadPhase::Failed {
+            self.recovered_rows += 1;
+        }
+        if self.retrying_rows.is_empty() {
+            tracing::info!("Recovered {} of the failed rows", self.recovered_rows);
+        }
+    }
 
-        ctx.init();
-        match event {
-            // This is a bit of a hack: first RootWidget registers as able to receive events,
-            // then it sends a Command to itself so that it can request_focus(). Requesting
-            // focus in necessary to get keyboard events.
-            // This is unnecessarily complicated; probably should change Druid's architecture
-            // to make it simpler.
-            LifeCycle::BuildFocusChain => {
-                ctx.register_for_focus();
-                ctx.submit_command(
-                    Command::from(REQUEST_FOCUS).to(Target::Widget(ctx.widget_id())),
-                );
-            }
-            // This is essentially a second constructor.
-            LifeCycle::WidgetAdded => {
-                self.children_promise =
-                    ctx.compute_in_background(move |_| load_collection(COLLECTION_URL).unwrap());
+    // Called after every event, since any widget holding the session can change it.
+    fn schedule_session_save(&mut self, ctx: &mut EventCtx) {
+        if !self.session.borrow().is_dirty() {
+            return;
+        }
+        match self.config.session_save_interval {
+            Some(interval) => {
+                if self.session_save_timer == TimerToken::INVALID {
+                    self.session_save_timer = ctx.request_timer(interval);
+                }
             }
-            _ => {}
+            None => self.session.borrow_mut().flush(),
         }
-        self.children.lifecycle(ctx, event, env)
     }
 
-    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
-        let layout = self.children.layout(ctx, bc, env);
-        self.children.set_origin(ctx, env, Point::ORIGIN);
-        layout
+    // Rebuilds the grid from the feed of another profile, with its own session state,
+    // and the selection it had when it was last switched away from.
+    fn switch_profile(&mut self, ctx: &mut EventCtx, index: usize) {
+        let profile = match self.base_config.profiles.get(index) {
+            Some(profile) => profile.clone(),
+            None => return,
+        };
+        if let Some(active_profile) = self.active_profile {
+            let name = self.base_config.profiles[active_profile].name.clone();
+            self.profile_selections.insert(name, self.selected_item);
+        }
+        tracing::info!("Switching to profile '{}'", profile.name);
+        self.config = Arc::new(self.base_config.for_profile(&profile));
+        self.active_profile = Some(index);
+        // Dropping the previous profile's session saves whatever it had left.
+        *self.session.borrow_mut() = SessionState::load(&self.config);
+
+        let selection = self
+            .profile_selections
+            .get(&profile.name)
+            .copied()
+            .or(self.config.initial_selection);
+        self.selected_item = selection.unwrap_or((0, 0));
+        self.selection_visible = selection.is_some();
+        self.focus = FocusTarget::Tile(self.selected_item.0, self.selected_item.1);
+        self.refresh(ctx);
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        ctx.init();
+    fn open_detail_page(&mut self, ctx: &mut EventCtx, tile: &TileMetadata) {
+        let page = DetailPage::new(tile.clone(), &self.config);
+        self.pages.push(WidgetPod::new(page));
+        ctx.children_changed();
+        ctx.request_layout();
+    }
 
-        const BACKGROUND_COLOR: Color = Color::from_rgba32_u32(0x07_1b_0f_ff);
+    fn close_detail_page(&mut self, ctx: &mut EventCtx) {
+        if self.pages.pop().is_some() {
+            ctx.children_changed();
+            ctx.request_paint();
+        }
+    }
 
-        let paint_rect = ctx.size().to_rect();
-        ctx.fill(paint_rect, &BACKGROUND_COLOR);
-        self.children.paint(ctx, env)
+    fn open_search(&mut self, ctx: &mut EventCtx) {
+        if self.search.is_some() {
+            return;
+        }
+        let search = SearchPage::new(
+            self.config.clone(),
+            self.session.clone(),
+            self.clock.clone(),
+        );
+        self.search = Some(WidgetPod::new(search));
+        ctx.children_changed();
+        ctx.request_layout();
     }
 
-    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
-        smallvec![&self.children as &dyn AsWidgetPod]
+    fn close_search(&mut self, ctx: &mut EventCtx) {
+        if self.search.take().is_some() {
+            ctx.children_changed();
+            ctx.request_paint();
+        }
     }
 
-    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
-        smallvec![&mut self.children as &mut dyn AsWidgetPod]
+    fn open_settings(&mut self, ctx: &mut EventCtx) {
+        if self.settings_page.is_some() {
+            return;
+        }
+        let settings = self.session.borrow().settings.settings;
+        self.settings_page = Some(WidgetPod::new(SettingsPage::new(settings)));
+        ctx.children_changed();
+        ctx.request_layout();
     }
 
-    // This isn't useful for the application itself, but it makes traces more readable
-    // when debugging
-    fn make_trace_span(&self) -> Span {
-        trace_span!("RootWidget")
+    fn close_settings(&mut self, ctx: &mut EventCtx) {
+        if self.settings_page.take().is_some() {
+            ctx.children_changed();
+            ctx.request_paint();
+        }
+    }
+
+    // `env`, with the settings of the session applied. Every child gets this one.
+    fn settings_env(&self, env: &Env) -> Env {
+        self.session.borrow().settings.settings.apply(env)
+    }
+
+    fn open_player(&mut self, ctx: &mut EventCtx, url: String, tile: &TileMetadata) {
+        let title = tile.title.clone().unwrap_or_default();
+        let player = VideoPlayer::new(url, title, self.volume_overlay.value, &self.config);
+        self.player = Some(WidgetPod::new(player));
+        ctx.children_changed();
+        ctx.request_layout();
+    }
+
+    // Dropping the player stops playback.
+    fn close_player(&mut self, ctx: &mut EventCtx) {
+        if self.player.take().is_some() {
+            ctx.children_changed();
+            ctx.request_paint();
+        }
+    }
+
+    // Selects the first tile of `unvisited_tiles` in reading order, or the first
+    // tile of the grid if every tile has been activated.
+    fn jump_to_unvisited(&mut self, ctx: &mut EventCtx) {
+        let first_tile = self
+            .snapshot
+            .rows
+            .iter()
+            .position(|row_snapshot| !row_snapshot.tiles.is_empty())
+            .map(|row| (row, 0));
+        let unvisited_tile = self.unvisited_tiles.drain(..).min();
+        let (row, column) = match unvisited_tile.or(first_tile) {
+            Some(tile) => tile,
+            None => return,
+        };
+        if unvisited_tile.is_none() {
+            tracing::info!("Every tile has been visited, going back to the first one");
+        }
+        self.select_tile(ctx, (row, column));
+    }
+
+    // Moves the selection and focus to `tile`, as if the user had navigated there.
+    fn select_tile(&mut self, ctx: &mut EventCtx, tile: (usize, usize)) {
+        self.selected_item = tile;
+        self.focus = FocusTarget::Tile(tile.0, tile.1);
+        self.selection_visible = true;
+        if !self.config.sounds_muted {
+            self.sound_handler.on_navigate();
+        }
+        self.speech_timer = ctx.request_timer(SPEECH_DELAY);
+        self.broadcast_selection(ctx);
+    }
+
+    // Clicking a tile selects it, and clicking the selected tile activates it.
+    // A double click does both.
+    fn click_tile(&mut self, ctx: &mut EventCtx, tile: (usize, usize)) {
+        // Thumbnails under an overlay still get the clicks meant for it.
+        if self.grid_covered() || self.rows_collapsed {
+            return;
+        }
+        ctx.request_focus();
+        if self.selection_visible && self.focus == FocusTarget::Tile(tile.0, tile.1) {
+            self.on_key_action(ctx, Some(Action::Activate));
+        } else {
+            self.select_tile(ctx, tile);
+        }
+    }
+
+    // Scrolls the rows by `delta_y`, for the mouse wheel. This interrupts any
+    // glide to the selected row.
+    fn scroll_grid_by(&mut self, ctx: &mut EventCtx, delta_y: f64) {
+        self.grid_scroll = None;
+        let mut moved = false;
+        self.children
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                let origin = clipbox.viewport_origin();
+                moved = pan_clipbox_to(clipbox, Point::new(origin.x, origin.y + delta_y));
+            });
+        if moved {
+            ctx.request_paint();
+        }
+    }
+
+    // Scrolls the rows to `y`, for CENTER_ROW and REVEAL_ROW. With pan animations,
+    // the grid glides there over ROW_SCROLL_DURATION, adjusted for the animation
+    // speed setting. It starts from wherever the grid is, so that moving on
+    // mid-glide just changes where it's headed.
+    fn scroll_grid_to(&mut self, ctx: &mut EventCtx, y: f64) {
+        // Eg when the selected row is told about the selection again.
+        if matches!(&self.grid_scroll, Some(animation) if animation.to == y) {
+            return;
+        }
+        let from = self.children.widget().viewport_origin().y;
+        if self.config.pan_animation_enabled() && (y - from).abs() >= 0.5 {
+            let speed = self.session.borrow().settings.settings.animation_speed;
+            let duration = ROW_SCROLL_DURATION.div_f64(speed.factor());
+            self.grid_scroll = Some(PanAnimation::new(from, y, duration));
+            ctx.request_anim_frame();
+            return;
+        }
+        self.grid_scroll = None;
+        let mut moved = false;
+        self.children
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                let origin = clipbox.viewport_origin();
+                moved = pan_clipbox_to(clipbox, Point::new(origin.x, y));
+            });
+        if moved {
+            ctx.request_paint();
+        }
+    }
+
+    // Moves the rows one frame further along `grid_scroll`. `interval` is the time
+    // since the last frame, in nanoseconds.
+    fn step_grid_scroll(&mut self, ctx: &mut EventCtx, interval: u64) {
+        let (y, done) = match &mut self.grid_scroll {
+            Some(animation) => (animation.step(interval), animation.is_done()),
+            None => return,
+        };
+        self.children
+            .recurse_pass("custom_pass", &mut ctx.widget_state, |clipbox, _| {
+                let origin = clipbox.viewport_origin();
+                pan_clipbox_to(clipbox, Point::new(origin.x, y));
+            });
+        ctx.request_paint();
+        if done {
+            self.grid_scroll = None;
+        } else {
+            ctx.request_anim_frame();
+        }
+    }
+
+    // What decides which rows are in view: where the scrolling area is, how far
+    // it's scrolled, and how tall the rows are.
+    fn visible_rows_key(&self) -> (Rect, Point, Size) {
+        let clipbox = self.children.widget();
+        (
+            self.children.layout_rect(),
+            clipbox.viewport_origin(),
+            clipbox.content_size(),
+        )
+    }
+
+    // Tells the rows where the visible part of the grid is, if they may have
+    // moved in or out of it since the last time. Pans don't go through layout, so
+    // this runs after every event. Layouts send CHECK_VISIBLE_ROWS instead.
+    fn check_visible_rows(&mut self, ctx: &mut EventCtx) {
+        let key = self.visible_rows_key();
+        match self.visible_rows_key {
+            // New rows wait for their first layout.
+            None => return,
+            Some(previous) if previous == key => return,
+            Some(_) => {}
+        }
+        self.visible_rows_key = Some(key);
+        self.send_visible_area(ctx);
+    }
+
+    fn send_visible_area(&self, ctx: &mut EventCtx) {
+        let area = Rect::from_origin_size(ctx.window_origin(), ctx.size());
+        ctx.submit_command(VISIBLE_AREA.with(area));
+    }
+
+    // Builds a row for every set of the collection. On error, the grid is left empty.
+    fn on_collection_loaded(
+        &mut self,
+        ctx: &mut EventCtx,
+        children: Result<Vec<ContentSetMetadata>, FetchError>,
+    ) {
+        let mut children = children.unwrap_or_else(|err| {
+            tracing::error!("Failed to load collection: {}", err);
+            self.collection_state = CollectionState::Failed;
+            self.collection_error = Some(err.to_string());
+            Vec::new()
+        });
+        if !self.session.borrow().favorites.tiles.is_empty() {
+            children.insert(
+                0,
+                ContentSetMetadata {
+                    title: "Favorites".to_string(),
+                    description: None,
+                    ref_id: "favorites".to_string(),
+                    set_type: FAVORITES_SET_TYPE.to_string(),
+                    available_from: None,
+                    available_until: None,
+                    load_priority: None,
+                    inline_set: None,
+                },
+            );
+        }
+        if let Some(transform) = &mut self.catalog_transform {
+            children = transform(children);
+        }
+        self.catalog = children;
+        self.build_rows(ctx);
+
+        if self.catalog.iter().any(|row| row.has_availability_window()) {
+            self.availability_timer = ctx.request_timer(AVAILABILITY_CHECK_INTERVAL);
+        }
+    }
+
+    fn available_rows(&self) -> Vec<ContentSetMetadata> {
+        let now = self.clock.wall_time();
+        let mut rows = self
+            .catalog
+            .iter()
+            .filter(|row| row.is_available_at(now))
+            .cloned()
+            .collect::<Vec<_>>();
+        if let Some(pinned_row) = &self.config.pinned_row {
+            if let Some(index) = rows.iter().position(|row| &row.ref_id == pinned_row) {
+                let row = rows.remove(index);
+                rows.insert(0, row);
+            }
+        }
+        if let Some(row_limit) = self.config.row_limit {
+            rows.truncate(row_limit);
+        }
+        rows
+    }
+
+    // Rebuilds the grid if a row appeared or disappeared since the last check.
+    // Every row is rebuilt, and loads again.
+    fn check_availability(&mut self, ctx: &mut EventCtx) {
+        self.availability_timer = ctx.request_timer(AVAILABILITY_CHECK_INTERVAL);
+
+        let shown = self.snapshot.rows.iter().map(|row| &row.ref_id);
+        let available = self.available_rows();
+        if shown.eq(available.iter().map(|row| &row.ref_id)) {
+            return;
+        }
+        self.build_rows(ctx);
+
+        let last_row = self.snapshot.rows.len().saturating_sub(1);
+        self.selected_item.0 = self.selected_item.0.min(last_row);
+        // Collapsed rows only have their headers to focus.
+        self.focus = if self.rows_collapsed {
+            FocusTarget::RowHeader(self.selected_item.0, 0)
+        } else {
+            FocusTarget::Tile(self.selected_item.0, self.selected_item.1)
+        };
+        self.broadcast_selection(ctx);
+    }
+
+    // Builds a ContentSet for every available row of `catalog`.
+    fn build_rows(&mut self, ctx: &mut EventCtx) {
+        let children = self.available_rows();
+        if self.collection_state != CollectionState::Failed {
+            self.collection_state = if children.is_empty() {
+                CollectionState::Empty
+            } else {
+                CollectionState::Loaded
+            };
+            ctx.request_paint();
+        }
+        // Rows rebuilt after the catalog was ready don't make it ready again.
+        if !self.ready {
+            self.pending_rows = Some((0..children.len()).collect());
+            self.fire_ready_if_done();
+        }
+
+        self.snapshot.rows = children
+            .iter()
+            .map(|child| RowSnapshot {
+                title: child.title.clone(),
+                ref_id: child.ref_id.clone(),
+                phase: LoadPhase::Pending,
+                tiles: Vec::new(),
+                complete: false,
+            })
+            .collect();
+        self.publish_snapshot();
+
+        let config = self.config.clone();
+        let selection = self.selection.clone();
+        let session = self.session.clone();
+        let workers = self.workers.clone();
+        let clock = self.clock.clone();
+        let hit_map = self.hit_map.clone();
+        let rows_collapsed = self.rows_collapsed;
+        let mut content_sets = build_guarded(children, |row, child| {
+            ContentSet::new(
+                row,
+                child,
+                config.clone(),
+                selection.clone(),
+                session.clone(),
+                workers.clone(),
+                clock.clone(),
+                hit_map.clone(),
+            )
+            .with_collapsed(rows_collapsed)
+        });
+
+        let pinned = match (&self.config.pinned_row, content_sets.first()) {
+            (Some(pinned_row), Some((_, Ok(content_set)))) => {
+                &content_set.data.ref_id == pinned_row
+            }
+            _ => false,
+        };
+        self.pinned_row = if pinned {
+            match content_sets.remove(0) {
+                (_, Ok(content_set)) => Some(WidgetPod::new(content_set)),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        ctx.children_changed();
+        // So that the next layout tells the new rows where the viewport is.
+        self.visible_rows_key = None;
+        self.grid_scroll = None;
+
+        let mut failed_rows = Vec::new();
+        // TODO - Need to find a more idiomatic way to do this.
+        self.children.recurse_pass(
+            "custom_pass",
+            &mut ctx.widget_state,
+            // clipbox is an alias of self.children in this closure
+            |clipbox, clipbox_state| {
+                clipbox
+                    .child
+                    .recurse_pass("custom_pass", clipbox_state, |flex, flex_state| {
+                        flex.clear(flex_state);
+                        for (row, content_set) in content_sets {
+                            match content_set {
+                                Ok(content_set) => flex.add_child(flex_state, content_set),
+                                Err(title) => {
+                                    tracing::error!("Failed to build row {} ('{}')", row, title);
+                                    let label = Label::new(unbuildable_row_message(&title));
+                                    flex.add_child(flex_state, label);
+                                    failed_rows.push(row);
+                                }
+                            }
+                            flex.add_spacer(flex_state, ROW_SPACING);
+                        }
+                        // when this closure returns, the framework automatically merges
+                        // invalidated state
+                    });
+            },
+        );
+
+        // These rows will never send ROW_LOADED.
+        for row in failed_rows {
+            self.snapshot.rows[row].phase = LoadPhase::Failed;
+            self.row_resolved(row);
+        }
+        self.publish_snapshot();
+    }
+
+    // `action` is None for keys that aren't in the keymap.
+    fn on_key_action(&mut self, ctx: &mut EventCtx, action: Option<Action>) {
+        // While the volume overlay is up, it takes Left/Right and Dismiss.
+        if self.volume_overlay.visible
+            && matches!(action, Some(Action::Left | Action::Right | Action::Dismiss))
+        {
+            match action {
+                Some(Action::Left) => self.volume_overlay.adjust(ctx, -1.0),
+                Some(Action::Right) => self.volume_overlay.adjust(ctx, 1.0),
+                _ => self.volume_overlay.hide(ctx),
+            }
+            return;
+        }
+        match action {
+            Some(Action::ShowVolume) => {
+                self.volume_overlay.show(ctx);
+                return;
+            }
+            Some(Action::LoadNextUnloadedRow) => {
+                self.load_next_unloaded_row(ctx);
+                return;
+            }
+            Some(Action::ShowHelp) => {
+                self.help_overlay.show(ctx);
+                return;
+            }
+            Some(Action::Search) => {
+                self.open_search(ctx);
+                return;
+            }
+            Some(Action::ShowSettings) => {
+                self.open_settings(ctx);
+                return;
+            }
+            Some(Action::ShowInfo) if self.selection_visible && !self.rows_collapsed => {
+                ctx.submit_command(SHOW_TILE_INFO.with(self.selected_item));
+                return;
+            }
+            Some(Action::SwitchProfile) if !self.base_config.profiles.is_empty() => {
+                let profile_count = self.base_config.profiles.len();
+                let next = self
+                    .active_profile
+                    .map_or(0, |index| (index + 1) % profile_count);
+                self.switch_profile(ctx, next);
+                return;
+            }
+            Some(Action::JumpToUnvisited) if !self.rows_collapsed => {
+                ctx.submit_command(FIND_UNVISITED);
+                return;
+            }
+            Some(Action::Refresh) => {
+                self.refresh(ctx);
+                return;
+            }
+            Some(Action::RetryFailedRows) => {
+                self.retry_failed_rows(ctx);
+                return;
+            }
+            Some(Action::Screenshot) => {
+                // The shell only gives us a render context to paint into, with no
+                // way to read the window's pixels back, so there's nothing to save yet.
+                tracing::warn!("Screenshots aren't supported by this windowing backend");
+                return;
+            }
+            Some(Action::ToggleCollapse) => {
+                self.toggle_collapse(ctx);
+                return;
+            }
+            Some(Action::ToggleFavorite) if self.selection_visible && !self.rows_collapsed => {
+                ctx.submit_command(TOGGLE_FAVORITE);
+                return;
+            }
+            // Activate is the retry action of the error screen.
+            Some(Action::Activate) if self.shows_load_error() => {
+                self.refresh(ctx);
+                return;
+            }
+            Some(Action::Activate) if self.selection_visible => {
+                if !self.config.sounds_muted {
+                    self.sound_handler.on_activate();
+                }
+                match self.focus {
+                    // Failed rows show an ErrorCard instead of tiles.
+                    FocusTarget::Tile(row, _) if self.row_failed(row) => {
+                        ctx.submit_command(RELOAD_ROW.with(row));
+                    }
+                    FocusTarget::Tile(..) => {
+                        ctx.submit_command(ACTIVATE_TILE.with(self.selected_item))
+                    }
+                    FocusTarget::RowHeader(row, index) if index > 0 => {
+                        match HEADER_CONTROLS[index - 1] {
+                            HeaderControl::SeeAll => ctx.submit_command(SEE_ALL.with(row)),
+                            HeaderControl::Sort => ctx.submit_command(CYCLE_SORT.with(row)),
+                        }
+                    }
+                    FocusTarget::RowHeader(..) => {}
+                }
+                return;
+            }
+            _ => {}
+        }
+        if !self.selection_visible {
+            // The first key press only reveals the selection, without moving it.
+            self.selection_visible = true;
+            self.broadcast_selection(ctx);
+            return;
+        }
+
+        let previous_selection = (self.selected_item, self.focus);
+        // This is a HUGE cheat.
+        let last_row = self.snapshot.rows.len().saturating_sub(1);
+        // In right-to-left mode, Left moves towards the end of the row.
+        let (action_previous, action_next) = if self.config.text_direction.is_rtl() {
+            (Action::Right, Action::Left)
+        } else {
+            (Action::Left, Action::Right)
+        };
+        let header_len = HEADER_CONTROLS.len() + 1;
+        match (self.focus, action) {
+            // While a header is focused, Up and Down move between headers
+            // and the selection stays where it is. Left and Right cycle between
+            // the title and the header's controls.
+            (FocusTarget::RowHeader(row, _), Some(Action::Down)) => {
+                self.focus = FocusTarget::RowHeader((row + 1).min(last_row), 0);
+            }
+            (FocusTarget::RowHeader(row, _), Some(Action::Up)) => {
+                self.focus = FocusTarget::RowHeader(row.saturating_sub(1), 0);
+            }
+            (FocusTarget::RowHeader(row, index), Some(action)) if action == action_previous => {
+                self.focus = FocusTarget::RowHeader(row, (index + header_len - 1) % header_len);
+            }
+            (FocusTarget::RowHeader(row, index), Some(action)) if action == action_next => {
+                self.focus = FocusTarget::RowHeader(row, (index + 1) % header_len);
+            }
+            // Collapsed rows have no tiles to go back to.
+            (FocusTarget::RowHeader(..), Some(Action::ToggleHeaderFocus))
+                if self.rows_collapsed => {}
+            (FocusTarget::RowHeader(..), Some(Action::ToggleHeaderFocus)) => {
+                self.focus = FocusTarget::Tile(self.selected_item.0, self.selected_item.1);
+            }
+            (FocusTarget::Tile(..), Some(Action::ToggleHeaderFocus)) => {
+                self.focus = FocusTarget::RowHeader(self.selected_item.0, 0);
+            }
+            (FocusTarget::Tile(..), action) => {
+                let (row, column) = self.selected_item;
+                match action {
+                    Some(Action::Down) => {
+                        let row = (row + 1).min(last_row);
+                        self.selected_item = (row, self.clamp_column(row, column));
+                    }
+                    Some(action) if action == action_previous => {
+                        self.selected_item.1 = self.previous_column(row, column);
+                    }
+                    Some(action) if action == action_next => {
+                        self.selected_item.1 = self.next_column(row, column);
+                    }
+                    Some(Action::Up) => {
+                        let row = row.saturating_sub(1);
+                        self.selected_item = (row, self.clamp_column(row, column));
+                    }
+                    _ => {}
+                }
+                self.focus = FocusTarget::Tile(self.selected_item.0, self.selected_item.1);
+            }
+            _ => {}
+        }
+
+        // Key presses against the edge of the grid don't make a sound.
+        if (self.selected_item, self.focus) != previous_selection && !self.config.sounds_muted {
+            self.sound_handler.on_navigate();
+        }
+        // Restarting the timer drops the previous announcement, so holding an
+        // arrow key only announces the tile it stops on.
+        if self.selected_item != previous_selection.0 {
+            self.speech_timer = ctx.request_timer(SPEECH_DELAY);
+        }
+        self.broadcast_selection(ctx);
+    }
+
+    // `column`, or the last tile of `row` if it's shorter than that. Rows without
+    // tiles yet keep the selection on their first column.
+    fn clamp_column(&self, row: usize, column: usize) -> usize {
+        let tile_count = self
+            .snapshot
+            .rows
+            .get(row)
+            .map_or(0, |row_snapshot| row_snapshot.tiles.len());
+        column.min(tile_count.saturating_sub(1))
+    }
+
+    // Whether the selection can wrap around the ends of `row`, see
+    // `AppConfig::wrap_selection`.
+    fn wraps(&self, row: usize) -> bool {
+        self.config.wrap_selection
+            && self.snapshot.rows.get(row).map_or(false, |row_snapshot| {
+                row_snapshot.complete && !row_snapshot.tiles.is_empty()
+            })
+    }
+
+    // How far Left or Right moves the selection from `column`. Paged rows move a
+    // whole page when the selection is on the edge of one, and the row follows it
+    // to the next screen.
+    fn column_step(&self, column: usize, forward: bool) -> usize {
+        let page = self.tiles_per_page;
+        let on_edge = if forward {
+            column % page == page - 1
+        } else {
+            column % page == 0
+        };
+        if self.config.pan_alignment == PanAlignment::Paged && on_edge {
+            page
+        } else {
+            1
+        }
+    }
+
+    fn next_column(&self, row: usize, column: usize) -> usize {
+        let next = self.clamp_column(row, column + self.column_step(column, true));
+        if next == column && self.wraps(row) {
+            0
+        } else {
+            next
+        }
+    }
+
+    fn previous_column(&self, row: usize, column: usize) -> usize {
+        if column == 0 && self.wraps(row) {
+            self.clamp_column(row, usize::MAX)
+        } else {
+            self.clamp_column(row, column.saturating_sub(self.column_step(column, false)))
+        }
+    }
+
+    // Adds `text` to the type-ahead search, and asks the selected row for the
+    // first tile matching it. Only tiles that have loaded can be found.
+    fn type_ahead_in_row(&mut self, ctx: &mut EventCtx, text: &str) {
+        if !self.selection_visible || self.rows_collapsed {
+            return;
+        }
+        let now = self.clock.now();
+        let timed_out = self.last_type_ahead.map_or(true, |last| {
+            now.saturating_duration_since(last) > TYPE_AHEAD_TIMEOUT
+        });
+        if timed_out {
+            self.type_ahead.clear();
+        }
+        self.last_type_ahead = Some(now);
+        self.type_ahead.push_str(&text.to_lowercase());
+        ctx.submit_command(FIND_TILE.with((self.selected_item.0, self.type_ahead.clone())));
+    }
+
+    // Collapses every row to its header, moving focus to the selected row's header,
+    // or expands them back with focus on the selected tile.
+    fn toggle_collapse(&mut self, ctx: &mut EventCtx) {
+        self.rows_collapsed = !self.rows_collapsed;
+        self.focus = if self.rows_collapsed {
+            FocusTarget::RowHeader(self.selected_item.0, 0)
+        } else {
+            FocusTarget::Tile(self.selected_item.0, self.selected_item.1)
+        };
+        ctx.submit_command(COLLAPSE_ROWS.with(self.rows_collapsed));
+        self.broadcast_selection(ctx);
+    }
+
+    // Forgets the previous collection, and begins a new load generation.
+    fn reset_collection(&mut self) {
+        self.load_cancel.cancel();
+        self.load_cancel = CancelHandle::default();
+        self.load_generation += 1;
+        self.pending_rows = None;
+        self.ready = false;
+        self.collection_state = CollectionState::Loading;
+        self.collection_error = None;
+        self.diagnosed_row = None;
+        self.retrying_rows.clear();
+        self.recovered_rows = 0;
+        self.catalog.clear();
+        self.snapshot.rows.clear();
+        self.publish_snapshot();
+    }
+
+    // The collection is fetched from `on_event`, where we can get its result
+    // right away in LoadMode::Synchronous.
+    fn start_load(&mut self, ctx: &mut LifeCycleCtx) {
+        self.reset_collection();
+        ctx.submit_command(Command::from(LOAD_COLLECTION).to(Target::Widget(ctx.widget_id())));
+    }
+
+    // Same as `start_load`, from a key press. The current rows stay up until
+    // the collection resolves.
+    fn refresh(&mut self, ctx: &mut EventCtx) {
+        self.reset_collection();
+        ctx.request_paint();
+        self.fetch_collection(ctx);
+    }
+
+    fn fetch_collection(&mut self, ctx: &mut EventCtx) {
+        match self.config.load_mode {
+            LoadMode::Background => {
+                let collection_url = self.config.collection_url.clone();
+                let timeout = self.config.http.home_timeout;
+                let workers = self.workers.clone();
+                let cancel = self.load_cancel.clone();
+                self.children_promise = ctx.compute_in_background(move |_| {
+                    workers.run(|| load_collection(&collection_url, timeout, &cancel))
+                });
+            }
+            LoadMode::Synchronous => {
+                let children = load_collection(
+                    &self.config.collection_url,
+                    self.config.http.home_timeout,
+                    &self.load_cancel,
+                );
+                self.on_collection_loaded(ctx, children);
+            }
+        }
+    }
+
+    // Whether the collection failed, and there isn't even a favorites row to show
+    // instead. The error screen is painted in place of the grid.
+    fn shows_load_error(&self) -> bool {
+        self.collection_state == CollectionState::Failed && self.snapshot.rows.is_empty()
+    }
+
+    fn row_failed(&self, row: usize) -> bool {
+        self.snapshot.rows.get(row).map_or(false, |row_snapshot| {
+            row_snapshot.phase == LoadPhase::Failed
+        })
+    }
+
+    // Shown instead of the grid when the collection failed to load.
+    fn paint_load_error(&self, ctx: &mut PaintCtx, area: Size, env: &Env) {
+        const LINE_SPACING: f64 = 8.0;
+
+        let error = self.collection_error.clone().unwrap_or_default();
+        let lines = [
+            (
+                "Couldn't load the catalog".to_string(),
+                24.0,
+                env.get(&theme::TEXT_COLOR),
+            ),
+            (retry_hint(&self.config), 14.0, Color::grey8(0xb0)),
+            (error, 12.0, Color::grey8(0x80)),
+        ];
+        let texts: Vec<_> = lines
+            .iter()
+            .filter(|(line, _, _)| !line.is_empty())
+            .map(|(line, font_size, color)| {
+                ctx.text()
+                    .new_text_layout(line.clone())
+                    .font(FontFamily::SYSTEM_UI, *font_size)
+                    .text_color(color.clone())
+                    .max_width(area.width * 0.8)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+
+        let height: f64 = texts.iter().map(|text| text.size().height).sum::<f64>()
+            + LINE_SPACING * texts.len().saturating_sub(1) as f64;
+        let mut y = (area.height - height) / 2.0;
+        for text in texts {
+            let size = text.size();
+            ctx.draw_text(&text, Point::new((area.width - size.width) / 2.0, y));
+            y += size.height + LINE_SPACING;
+        }
+    }
+
+    // Shown instead of the grid when the feed has no rows.
+    fn paint_empty_feed(&self, ctx: &mut PaintCtx, area: Size, env: &Env) {
+        const LINE_SPACING: f64 = 8.0;
+
+        let refresh_hint = match self.config.keymap.key(Action::Refresh) {
+            Some(key) => format!("Press {} to check again.", key),
+            None => String::new(),
+        };
+        let title_text = ctx
+            .text()
+            .new_text_layout("Nothing to watch right now")
+            .font(FontFamily::SYSTEM_UI, 24.0)
+            .text_color(env.get(&theme::TEXT_COLOR))
+            .build()
+            .unwrap();
+        let hint_text = ctx
+            .text()
+            .new_text_layout(refresh_hint)
+            .font(FontFamily::SYSTEM_UI, 14.0)
+            .text_color(Color::grey8(0xb0))
+            .build()
+            .unwrap();
+
+        let title_size = title_text.size();
+        let hint_size = hint_text.size();
+        let top = (area.height - title_size.height - LINE_SPACING - hint_size.height) / 2.0;
+        ctx.draw_text(
+            &title_text,
+            Point::new((area.width - title_size.width) / 2.0, top),
+        );
+        ctx.draw_text(
+            &hint_text,
+            Point::new(
+                (area.width - hint_size.width) / 2.0,
+                top + title_size.height + LINE_SPACING,
+            ),
+        );
+    }
+
+    // The saved window size can be bigger than the display the window opened on,
+    // eg if it was last used on a bigger monitor.
+    fn fit_window_to_display(&mut self, ctx: &mut EventCtx) {
+        let window = ctx.window();
+        let position = window.get_position();
+        let monitors = Screen::get_monitors();
+        let monitor = monitors
+            .iter()
+            .find(|monitor| monitor.virtual_rect().contains(position))
+            .or_else(|| monitors.iter().find(|monitor| monitor.is_primary()));
+        if let Some(monitor) = monitor {
+            let size = window.get_size();
+            let fitted = clamp_to_display(size, monitor.virtual_work_rect().size());
+            if fitted != size {
+                window.set_size(fitted);
+            }
+        }
+    }
+
+    // `row` doesn't hold up readiness anymore, either because its first page
+    // resolved or because it's waiting to come into view.
+    fn row_resolved(&mut self, row: usize) {
+        if let Some(pending_rows) = &mut self.pending_rows {
+            pending_rows.remove(&row);
+        }
+        self.fire_ready_if_done();
+    }
+
+    fn fire_ready_if_done(&mut self) {
+        let done = self
+            .pending_rows
+            .as_ref()
+            .map_or(false, |pending_rows| pending_rows.is_empty());
+        if done && !self.ready {
+            self.ready = true;
+            if let Some(on_ready) = &mut self.on_ready {
+                on_ready(self.load_generation);
+            }
+        }
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for RootWidget {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        let env = &self.settings_env(env);
+        // Before the match, since overlays take animation frames for themselves.
+        if let Event::AnimFrame(interval) = event {
+            self.step_grid_scroll(ctx, *interval);
+        }
+        match event {
+            // This happens after the callback passed to `ctx.compute_in_background` returns
+            Event::PromiseResult(result) => {
+                if let Some(children) = result.try_get(self.children_promise) {
+                    self.on_collection_loaded(ctx, children);
+
+                    ctx.skip_child(&mut self.children);
+                    return;
+                }
+            }
+            Event::WindowConnected => {
+                self.fit_window_to_display(ctx);
+            }
+            Event::WindowSize(size) => {
+                self.session.borrow_mut().window_size.record(*size);
+            }
+            Event::KeyDown(key_event) if self.help_overlay.visible => {
+                // The help overlay swallows every key until it's dismissed.
+                if matches!(
+                    self.config.keymap.action(&key_event.key),
+                    Some(Action::ShowHelp | Action::Dismiss)
+                ) {
+                    self.help_overlay.hide(ctx);
+                }
+            }
+            Event::KeyDown(key_event) if self.info_overlay.visible => {
+                // Like the help overlay, but Up/Down scroll the description.
+                match self.config.keymap.action(&key_event.key) {
+                    Some(Action::Up) => self.info_overlay.scroll(ctx, -1.0),
+                    Some(Action::Down) => self.info_overlay.scroll(ctx, 1.0),
+                    Some(Action::ShowInfo | Action::Dismiss) => self.info_overlay.hide(ctx),
+                    _ => {}
+                }
+            }
+            Event::AnimFrame(interval) if self.info_overlay.visible => {
+                self.info_overlay.on_anim_frame(ctx, *interval);
+            }
+            Event::KeyDown(key_event) if self.player.is_some() => {
+                // The player doesn't have focus, so it gets its keys as commands.
+                match self.config.keymap.action(&key_event.key) {
+                    Some(Action::Dismiss) => self.close_player(ctx),
+                    Some(Action::Activate) => ctx.submit_command(TOGGLE_PLAYBACK),
+                    Some(Action::Left) => ctx.submit_command(SEEK_BY.with(-SEEK_STEP)),
+                    Some(Action::Right) => ctx.submit_command(SEEK_BY.with(SEEK_STEP)),
+                    _ => {}
+                }
+            }
+            Event::KeyDown(key_event) if !self.pages.is_empty() => {
+                // The grid is hidden under the page, so it doesn't get keys.
+                if self.config.keymap.action(&key_event.key) == Some(Action::Dismiss) {
+                    self.close_detail_page(ctx);
+                }
+            }
+            Event::KeyDown(key_event) if self.search.is_some() => {
+                // Like the player, the search page gets its keys as commands.
+                if self.config.keymap.action(&key_event.key) == Some(Action::Dismiss) {
+                    self.close_search(ctx);
+                } else {
+                    ctx.submit_command(SEARCH_KEY.with(key_event.key.clone()));
+                }
+            }
+            Event::KeyDown(key_event) if self.settings_page.is_some() => {
+                match self.config.keymap.action(&key_event.key) {
+                    Some(Action::Dismiss | Action::ShowSettings) => self.close_settings(ctx),
+                    Some(action) => ctx.submit_command(SETTINGS_ACTION.with(action)),
+                    None => {}
+                }
+            }
+            // Crumbs are drawn over the pages, so they get the click first.
+            Event::MouseDown(mouse) if mouse.button.is_left() && self.player.is_none() => {
+                let level = match self.crumbs().len() {
+                    1 => None,
+                    _ => self.breadcrumbs.crumb_at(mouse.pos),
+                };
+                if let Some(level) = level {
+                    self.pop_to_level(ctx, level);
+                    ctx.set_handled();
+                }
+            }
+            Event::KeyDown(key_event) if key_event.mods.alt() => {
+                if let Key::Character(text) = &key_event.key {
+                    self.type_ahead_in_row(ctx, text);
+                }
+            }
+            Event::KeyDown(key_event) => {
+                let action = self.config.keymap.action(&key_event.key);
+                self.on_key_action(ctx, action);
+            }
+            Event::Timer(token) if *token == self.availability_timer => {
+                self.check_availability(ctx);
+                ctx.skip_child(&mut self.children);
+                return;
+            }
+            // With Shift held, the wheel scrolls the row under the mouse instead.
+            // See ContentSet.
+            Event::Wheel(mouse) if !mouse.mods.shift() && !self.grid_covered() => {
+                if mouse.wheel_delta.y != 0.0 {
+                    self.scroll_grid_by(ctx, mouse.wheel_delta.y);
+                }
+                if self.config.scroll_snap.vertical {
+                    self.snap_timer = ctx.request_timer(SCROLL_SNAP_DELAY);
+                }
+            }
+            Event::Timer(token) if *token == self.snap_timer => {
+                self.snap_timer = TimerToken::INVALID;
+                ctx.submit_command(SNAP_ROWS.with(self.scroll_area_top(ctx)));
+            }
+            Event::Timer(token) if *token == self.session_save_timer => {
+                self.session_save_timer = TimerToken::INVALID;
+                self.session.borrow_mut().flush();
+            }
+            Event::Timer(token) if *token == self.speech_timer => {
+                self.speech_timer = TimerToken::INVALID;
+                if self.selection_visible {
+                    ctx.submit_command(DESCRIBE_TILE.with(self.selected_item));
+                }
+            }
+            Event::Timer(token) => {
+                self.volume_overlay.on_timer(ctx, *token);
+            }
+            Event::Command(command) if command.is(LOAD_COLLECTION) => {
+                self.fetch_collection(ctx);
+                ctx.skip_child(&mut self.children);
+                return;
+            }
+            Event::Command(command) if command.is(REQUEST_FOCUS) => {
+                ctx.request_focus();
+            }
+            Event::Command(command) if command.is(FIND_UNVISITED_DONE) => {
+                self.jump_to_unvisited(ctx);
+            }
+            Event::Command(command) if command.is(CHECK_VISIBLE_ROWS) => {
+                self.send_visible_area(ctx);
+            }
+            Event::Command(command) => {
+                // The pinned row is always in view.
+                let pinned_selected = self.pinned_row.is_some() && self.selected_item.0 == 0;
+                if let Some(window_y) = command.try_get(CENTER_ROW).filter(|_| !pinned_selected) {
+                    let offset_y = window_y - self.scroll_area_top(ctx);
+                    let clipbox = self.children.widget();
+                    let center_y = clipbox.viewport_origin().y + offset_y;
+                    let target = centered_offset(clipbox, Point::new(0.0, center_y));
+                    self.scroll_grid_to(ctx, target.y);
+                }
+                if let Some((top, bottom)) =
+                    command.try_get(REVEAL_ROW).filter(|_| !pinned_selected)
+                {
+                    let scroll_area_top = self.scroll_area_top(ctx);
+                    let clipbox = self.children.widget();
+                    let origin = clipbox.viewport_origin();
+                    let span = Rect::new(
+                        origin.x,
+                        origin.y + top - scroll_area_top,
+                        origin.x,
+                        origin.y + bottom - scroll_area_top,
+                    );
+                    let target = revealing_offset(clipbox, span);
+                    self.scroll_grid_to(ctx, target.y);
+                }
+                if let Some(row) = command.try_get(ROW_DEFERRED) {
+                    self.row_resolved(*row);
+                }
+                if let Some((row, phase)) = command.try_get(ROW_LOADED) {
+                    // A row that failed and was reloaded resolves a second time.
+                    let was_pending = self.snapshot.rows.get(*row).map(|row| row.phase)
+                        == Some(LoadPhase::Pending);
+                    if was_pending {
+                        self.row_resolved(*row);
+                    }
+                    if let Some(row_snapshot) = self.snapshot.rows.get_mut(*row) {
+                        row_snapshot.phase = *phase;
+                    }
+                    self.publish_snapshot();
+                    self.report_diagnosed_row(*row, *phase);
+                    self.report_retried_row(*row, *phase);
+                    // Thumbnails are only created once their row loads, so the selected
+                    // row needs to be told about the selection again.
+                    if self.selection_visible && *row == self.selected_item.0 {
+                        self.broadcast_selection(ctx);
+                    }
+                }
+                if let Some(window_y) = command.try_get(SNAP_GRID_TO) {
+                    let offset_y = window_y - self.scroll_area_top(ctx);
+                    let mut moved = false;
+                    self.children.recurse_pass(
+                        "custom_pass",
+                        &mut ctx.widget_state,
+                        |clipbox, _| {
+                            let origin = clipbox.viewport_origin();
+                            moved =
+                                pan_clipbox_to(clipbox, Point::new(origin.x, origin.y + offset_y));
+                        },
+                    );
+                    if moved {
+                        ctx.request_paint();
+                    }
+                }
+                if let Some((row, column)) = command.try_get(JUMP_TO_TILE) {
+                    // The user may have moved on while the row was searching.
+                    if *row == self.selected_item.0 && (*row, *column) != self.selected_item {
+                        self.select_tile(ctx, (*row, *column));
+                    }
+                }
+                if let Some(info) = command.try_get(TILE_INFO) {
+                    let description = info
+                        .description
+                        .clone()
+                        .unwrap_or_else(|| "No description available.".to_string());
+                    self.info_overlay.show(ctx, info.title.clone(), description);
+                }
+                if let Some(description) = command.try_get(TILE_DESCRIPTION) {
+                    self.speech_handler.speak(description);
+                }
+                if let Some((row, old_len, tiles)) = command.try_get(REPLACE_FIRST_PAGE) {
+                    if let Some(row_snapshot) = self.snapshot.rows.get_mut(*row) {
+                        let old_len = (*old_len).min(row_snapshot.tiles.len());
+                        row_snapshot.tiles.splice(..old_len, tiles.iter().cloned());
+                    }
+                    self.publish_snapshot();
+                    self.clamp_selection_to_row(ctx, *row);
+                }
+                if let Some(name) = command.try_get(SWITCH_PROFILE) {
+                    let index = self
+                        .base_config
+                        .profiles
+                        .iter()
+                        .position(|profile| &profile.name == name);
+                    match index {
+                        Some(index) => self.switch_profile(ctx, index),
+                        None => tracing::warn!("No profile named '{}'", name),
+                    }
+                }
+                if let Some(item) = command.try_get(ACTIVATE_ITEM) {
+                    if let Some(url) = self.config.player_stream_url.clone() {
+                        self.open_player(ctx, url, &item.tile);
+                    } else if self.config.detail_pages {
+                        self.open_detail_page(ctx, &item.tile);
+                    }
+                }
+                if let Some(tile) = command.try_get(CLICK_TILE) {
+                    self.click_tile(ctx, *tile);
+                }
+                if let Some(settings) = command.try_get(SETTINGS_CHANGED) {
+                    self.session.borrow_mut().settings.set(*settings);
+                    // Widgets read the Env when they're laid out and painted.
+                    ctx.request_layout();
+                    ctx.request_paint();
+                }
+                if let Some(tile) = command.try_get(UNVISITED_TILE) {
+                    self.unvisited_tiles.push(*tile);
+                }
+                if let Some((row, tiles)) = command.try_get(APPEND_TILES) {
+                    if let Some(row_snapshot) = self.snapshot.rows.get_mut(*row) {
+                        row_snapshot.tiles.extend(tiles.iter().cloned());
+                    }
+                    self.publish_snapshot();
+                }
+                if let Some(row) = command.try_get(ROW_COMPLETE) {
+                    if let Some(row_snapshot) = self.snapshot.rows.get_mut(*row) {
+                        row_snapshot.complete = true;
+                    }
+                    self.publish_snapshot();
+                }
+            }
+            _ => {}
+        }
+        self.children.on_event(ctx, event, env);
+        if let Some(pinned_row) = &mut self.pinned_row {
+            pinned_row.on_event(ctx, event, env);
+        }
+        if let Some(search) = &mut self.search {
+            search.on_event(ctx, event, env);
+        }
+        if let Some(settings_page) = &mut self.settings_page {
+            settings_page.on_event(ctx, event, env);
+        }
+        for page in &mut self.pages {
+            page.on_event(ctx, event, env);
+        }
+        if let Some(player) = &mut self.player {
+            player.on_event(ctx, event, env);
+        }
+        // Commands are handled in the order they're submitted, so this comes after
+        // every UNVISITED_TILE the rows just sent.
+        if let Event::Command(command) = event {
+            if command.is(FIND_UNVISITED) {
+                self.unvisited_tiles.clear();
+                ctx.submit_command(
+                    Command::from(FIND_UNVISITED_DONE).to(Target::Widget(ctx.widget_id())),
+                );
+            }
+        }
+        self.check_visible_rows(ctx);
+        self.schedule_session_save(ctx);
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::FocusChanged(has_focus) = event {
+            if *has_focus != self.has_focus {
+                self.has_focus = *has_focus;
+                ctx.submit_command(GRID_FOCUS_CHANGED.with(*has_focus));
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.init();
+        let env = &self.settings_env(env);
+        match event {
+            // This is a bit of a hack: first RootWidget registers as able to receive events,
+            // then it sends a Command to itself so that it can request_focus(). Requesting
+            // focus in necessary to get keyboard events.
+            // This is unnecessarily complicated; probably should change Druid's architecture
+            // to make it simpler.
+            LifeCycle::BuildFocusChain => {
+                ctx.register_for_focus();
+                if self.grab_focus {
+                    ctx.submit_command(
+                        Command::from(REQUEST_FOCUS).to(Target::Widget(ctx.widget_id())),
+                    );
+                }
+            }
+            // This is essentially a second constructor.
+            LifeCycle::WidgetAdded => {
+                self.start_load(ctx);
+            }
+            _ => {}
+        }
+        self.children.lifecycle(ctx, event, env);
+        if let Some(pinned_row) = &mut self.pinned_row {
+            pinned_row.lifecycle(ctx, event, env);
+        }
+        if let Some(search) = &mut self.search {
+            search.lifecycle(ctx, event, env);
+        }
+        if let Some(settings_page) = &mut self.settings_page {
+            settings_page.lifecycle(ctx, event, env);
+        }
+        for page in &mut self.pages {
+            page.lifecycle(ctx, event, env);
+        }
+        if let Some(player) = &mut self.player {
+            player.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let env = &self.settings_env(env);
+        // When embedded, we get whatever space the parent gives us, rather than
+        // the whole window.
+        // The pinned row takes what it needs, and the other rows scroll in the rest.
+        let pinned_height = match &mut self.pinned_row {
+            Some(pinned_row) => {
+                let pinned_bc =
+                    BoxConstraints::new(Size::ZERO, Size::new(bc.max().width, f64::INFINITY));
+                let pinned_size = pinned_row.layout(ctx, &pinned_bc, env);
+                pinned_row.set_origin(ctx, env, Point::ORIGIN);
+                pinned_size.height + ROW_SPACING
+            }
+            None => 0.0,
+        };
+        let layout = self
+            .children
+            .layout(ctx, &bc.shrink((0.0, pinned_height)), env);
+        self.children
+            .set_origin(ctx, env, Point::new(0.0, pinned_height));
+        let size = bc.constrain(Size::new(layout.width, layout.height + pinned_height));
+        // Rows are as wide as we are.
+        self.tiles_per_page = tiles_per_page(size.width, cell_size(env));
+        if self.config.lazy_load_margin.is_some() {
+            let key = self.visible_rows_key();
+            if self.visible_rows_key != Some(key) {
+                self.visible_rows_key = Some(key);
+                ctx.submit_command(
+                    Command::from(CHECK_VISIBLE_ROWS).to(Target::Widget(ctx.widget_id())),
+                );
+            }
+        }
+        // Pages, the search page and the settings page cover the whole grid, but
+        // for the breadcrumbs above them.
+        let page_size = Size::new(size.width, (size.height - BREADCRUMB_HEIGHT).max(0.0));
+        let page_origin = Point::new(0.0, BREADCRUMB_HEIGHT);
+        if let Some(search) = &mut self.search {
+            search.layout(ctx, &BoxConstraints::tight(page_size), env);
+            search.set_origin(ctx, env, page_origin);
+        }
+        if let Some(settings_page) = &mut self.settings_page {
+            settings_page.layout(ctx, &BoxConstraints::tight(page_size), env);
+            settings_page.set_origin(ctx, env, page_origin);
+        }
+        for page in &mut self.pages {
+            page.layout(ctx, &BoxConstraints::tight(page_size), env);
+            page.set_origin(ctx, env, page_origin);
+        }
+        if let Some(player) = &mut self.player {
+            player.layout(ctx, &BoxConstraints::tight(size), env);
+            player.set_origin(ctx, env, Point::ORIGIN);
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        ctx.init();
+        let env = &self.settings_env(env);
+
+        let paint_rect = ctx.size().to_rect();
+        ctx.fill(paint_rect, &env.get(&BACKGROUND_COLOR));
+        if self.collection_state == CollectionState::Empty {
+            self.hit_map.borrow_mut().clear();
+            self.paint_empty_feed(ctx, paint_rect.size(), env);
+        } else if self.shows_load_error() {
+            self.hit_map.borrow_mut().clear();
+            self.paint_load_error(ctx, paint_rect.size(), env);
+        } else {
+            // Rows repainted below register their tiles again.
+            self.hit_map.borrow_mut().invalidate(ctx.region());
+            self.children.paint(ctx, env);
+            if let Some(pinned_row) = &mut self.pinned_row {
+                pinned_row.paint(ctx, env);
+            }
+        }
+        self.hit_map.borrow_mut().docked = self.pinned_row.as_ref().map(|pinned_row| {
+            let rect = pinned_row.layout_rect() + ctx.window_origin().to_vec2();
+            (0, rect)
+        });
+        // Pages below the top one are completely hidden.
+        if let Some(page) = self.pages.last_mut() {
+            page.paint(ctx, env);
+        } else if let Some(search) = &mut self.search {
+            search.paint(ctx, env);
+        }
+        if let Some(settings_page) = &mut self.settings_page {
+            settings_page.paint(ctx, env);
+        }
+        let crumbs = self.crumbs();
+        if crumbs.len() > 1 {
+            self.breadcrumbs
+                .paint(ctx, paint_rect.width(), &crumbs, env);
+        } else {
+            self.breadcrumbs.crumb_rects.clear();
+        }
+        if let Some(player) = &mut self.player {
+            player.paint(ctx, env);
+        }
+
+        self.volume_overlay.paint(ctx, paint_rect.size());
+        self.help_overlay
+            .paint(ctx, paint_rect.size(), &self.config.keymap);
+        self.info_overlay.paint(ctx, paint_rect.size());
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> =
+            smallvec![&self.children as &dyn AsWidgetPod];
+        if let Some(pinned_row) = &self.pinned_row {
+            children.push(pinned_row as &dyn AsWidgetPod);
+        }
+        if let Some(search) = &self.search {
+            children.push(search as &dyn AsWidgetPod);
+        }
+        if let Some(settings_page) = &self.settings_page {
+            children.push(settings_page as &dyn AsWidgetPod);
+        }
+        for page in &self.pages {
+            children.push(page as &dyn AsWidgetPod);
+        }
+        if let Some(player) = &self.player {
+            children.push(player as &dyn AsWidgetPod);
+        }
+        children
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> =
+            smallvec![&mut self.children as &mut dyn AsWidgetPod];
+        if let Some(pinned_row) = &mut self.pinned_row {
+            children.push(pinned_row as &mut dyn AsWidgetPod);
+        }
+        if let Some(search) = &mut self.search {
+            children.push(search as &mut dyn AsWidgetPod);
+        }
+        if let Some(settings_page) = &mut self.settings_page {
+            children.push(settings_page as &mut dyn AsWidgetPod);
+        }
+        for page in &mut self.pages {
+            children.push(page as &mut dyn AsWidgetPod);
+        }
+        if let Some(player) = &mut self.player {
+            children.push(player as &mut dyn AsWidgetPod);
+        }
+        children
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("RootWidget")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::path::PathBuf;
+
+    use serde_json::json;
+    use widget_cruncher::shell::keyboard_types::Key;
+    use widget_cruncher::testing::Harness;
+    use widget_cruncher::widget::SizedBox;
+    use widget_cruncher::{KeyEvent, Modifiers};
+
+    use super::*;
+    use crate::clock::{Clock, FakeClock};
+    use crate::config::{Profile, TextDirection};
+    use crate::overlay::IDLE_TIMEOUT;
+    use crate::search::SEARCH_DELAY;
+    use crate::testing::{
+        click, harness, home_json, home_path, paints_color, press, press_char, search_path, sent,
+        set_json, set_path, test_config, tile_json, tile_metadata, with_root, CommandLog,
+        MockServer, WINDOW_SIZE,
+    };
+    use crate::thumbnail::{
+        BASE_SCALE, FOCUS_OUTLINE_COLOR, FOCUS_OUTLINE_WIDTH, NEW_BADGE_COLOR, SELECTED_SCALE,
+        THUMBNAIL_MAX_SIZE,
+    };
+    use crate::title_card::title_color;
+
+    const ROWS: [(&str, &str); 3] = [
+        ("trending", "Trending"),
+        ("new", "New to Disney+"),
+        ("classics", "Classics"),
+    ];
+
+    fn catalog_server() -> MockServer {
+        let server = MockServer::start();
+        server.add_catalog(&ROWS, 5);
+        server
+    }
+
+    #[derive(Default)]
+    struct CountingSounds {
+        navigations: Rc<Cell<usize>>,
+        activations: Rc<Cell<usize>>,
+    }
+
+    impl SoundHandler for CountingSounds {
+        fn on_navigate(&mut self) {
+            self.navigations.set(self.navigations.get() + 1);
+        }
+
+        fn on_activate(&mut self) {
+            self.activations.set(self.activations.get() + 1);
+        }
+    }
+
+    struct RecordingSpeech(Rc<RefCell<Vec<String>>>);
+
+    impl SpeechHandler for RecordingSpeech {
+        fn speak(&mut self, text: &str) {
+            self.0.borrow_mut().push(text.to_string());
+        }
+    }
+
+    #[test]
+    fn on_ready_fires_once_per_generation() {
+        let server = catalog_server();
+        let generations = Rc::new(RefCell::new(Vec::new()));
+        let ready = generations.clone();
+        let root = RootWidget::new(Arc::new(test_config(&server)))
+            .with_grab_focus()
+            .with_on_ready(move |generation| ready.borrow_mut().push(generation));
+        let mut harness = harness(root);
+        assert_eq!(*generations.borrow(), vec![1]);
+
+        // Moving around doesn't make the catalog ready again, refreshing does.
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(*generations.borrow(), vec![1]);
+        press_char(&mut harness, "r");
+        assert_eq!(*generations.borrow(), vec![1, 2]);
+        assert_eq!(server.request_count(&home_path()), 2);
+    }
+
+    #[test]
+    fn snapshot_follows_the_catalog() {
+        let server = catalog_server();
+        let snapshots = Rc::new(RefCell::new(Vec::new()));
+        let published = snapshots.clone();
+        let root = RootWidget::new(Arc::new(test_config(&server)))
+            .with_grab_focus()
+            .with_on_snapshot(move |snapshot| published.borrow_mut().push(snapshot.clone()));
+        let mut harness = harness(root);
+        press(&mut harness, Key::ArrowDown);
+
+        let last = snapshots.borrow().last().cloned().unwrap();
+        assert_eq!(last.rows.len(), ROWS.len());
+        assert_eq!(last.selected_item, Some((1, 0)));
+        // Polling gets the same thing the callback did.
+        let polled = with_root(&harness, |root| root.snapshot());
+        assert_eq!(polled.selected_item, last.selected_item);
+        assert_eq!(polled.rows.len(), last.rows.len());
+    }
+
+    #[test]
+    fn catalog_transform_rewrites_the_rows() {
+        let server = catalog_server();
+        let root =
+            RootWidget::new(Arc::new(test_config(&server))).with_catalog_transform(|mut rows| {
+                rows.reverse();
+                rows.truncate(2);
+                rows
+            });
+        let harness = harness(root);
+
+        let titles: Vec<String> = with_root(&harness, |root| {
+            root.snapshot()
+                .rows
+                .iter()
+                .map(|row| row.title.clone())
+                .collect()
+        });
+        assert_eq!(titles, vec!["Classics", "New to Disney+"]);
+    }
+
+    #[test]
+    fn sound_handler_hears_navigation_and_activation() {
+        let server = catalog_server();
+        let sounds = CountingSounds::default();
+        let navigations = sounds.navigations.clone();
+        let activations = sounds.activations.clone();
+        let root = RootWidget::new(Arc::new(test_config(&server)))
+            .with_grab_focus()
+            .with_sound_handler(sounds);
+        let mut harness = harness(root);
+
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::Enter);
+        assert_eq!(navigations.get(), 2);
+        assert_eq!(activations.get(), 1);
+    }
+
+    #[test]
+    fn speech_handler_announces_the_settled_selection() {
+        let server = catalog_server();
+        let spoken = Rc::new(RefCell::new(Vec::new()));
+        let root = RootWidget::new(Arc::new(test_config(&server)))
+            .with_grab_focus()
+            .with_speech_handler(RecordingSpeech(spoken.clone()));
+        let mut harness = harness(root);
+
+        // Only the tile the selection stops on is announced.
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+        harness.move_timers_forward(SPEECH_DELAY);
+        assert_eq!(spoken.borrow().len(), 1);
+        assert!(spoken.borrow()[0].starts_with("Trending 2"));
+    }
+
+    #[test]
+    fn stale_rows_refresh_by_the_injected_clock() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config
+            .row_max_age
+            .insert("new".to_string(), Duration::from_secs(60));
+        let clock = Rc::new(FakeClock::new());
+        let root = RootWidget::new(Arc::new(config))
+            .with_grab_focus()
+            .with_clock(clock.clone());
+        let mut harness = harness(root);
+        assert_eq!(server.request_count(&set_path("new")), 1);
+
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowUp);
+        assert_eq!(server.request_count(&set_path("new")), 1);
+
+        clock.advance(Duration::from_secs(61));
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(server.request_count(&set_path("new")), 2);
+    }
+
+    #[test]
+    fn tile_at_goes_by_what_was_painted() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+
+        let center = with_root(&harness, |root| {
+            let hit_map = root.hit_map.borrow();
+            let tile = hit_map
+                .tiles
+                .iter()
+                .find(|tile| (tile.row, tile.column) == (1, 2))
+                .expect("tile (1, 2) wasn't painted");
+            tile.rect.center()
+        });
+        assert_eq!(
+            with_root(&harness, |root| root.tile_at(center)),
+            Some((1, 2))
+        );
+        // Nothing is hit through an overlay.
+        press_char(&mut harness, "?");
+        assert_eq!(with_root(&harness, |root| root.tile_at(center)), None);
+    }
+
+    #[test]
+    fn has_focus_tracks_grab_focus() {
+        let server = catalog_server();
+        let focused = harness(RootWidget::new(Arc::new(test_config(&server))).with_grab_focus());
+        assert!(with_root(&focused, |root| root.has_focus()));
+        let unfocused = harness(RootWidget::new(Arc::new(test_config(&server))));
+        assert!(!with_root(&unfocused, |root| root.has_focus()));
+    }
+
+    // A feed of `row_count` rows of 5 tiles, with ref ids row0, row1, ...
+    fn long_feed_server(row_count: usize) -> MockServer {
+        let server = MockServer::start();
+        let rows: Vec<(String, String)> = (0..row_count)
+            .map(|row| (format!("row{}", row), format!("Row {}", row)))
+            .collect();
+        let rows: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|(ref_id, title)| (ref_id.as_str(), title.as_str()))
+            .collect();
+        server.add_catalog(&rows, 5);
+        server
+    }
+
+    #[test]
+    fn ready_doesnt_wait_for_rows_out_of_view() {
+        let server = long_feed_server(12);
+        let mut config = test_config(&server);
+        config.lazy_load_margin = Some(0.0);
+        let ready = Rc::new(Cell::new(0));
+        let ready_count = ready.clone();
+        let root = RootWidget::new(Arc::new(config))
+            .with_on_ready(move |_| ready_count.set(ready_count.get() + 1));
+        let _harness = harness(root);
+
+        assert_eq!(ready.get(), 1);
+        assert_eq!(server.request_count(&set_path("row0")), 1);
+        assert_eq!(server.request_count(&set_path("row11")), 0);
+    }
+
+    // The focus and selection once the collection of `catalog_server` resolves.
+    fn startup_state(startup_focus: StartupFocus) -> (Harness, FocusTarget) {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.startup_focus = startup_focus;
+        let harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let focus = with_root(&harness, |root| {
+            assert_eq!(root.snapshot().rows.len(), ROWS.len());
+            assert_eq!(root.snapshot().selected_item, Some((0, 0)));
+            root.focus
+        });
+        (harness, focus)
+    }
+
+    #[test]
+    fn startup_focus_first_tile() {
+        let (_harness, focus) = startup_state(StartupFocus::FirstTile);
+        assert_eq!(focus, FocusTarget::Tile(0, 0));
+    }
+
+    #[test]
+    fn startup_focus_first_row_header() {
+        let (mut harness, focus) = startup_state(StartupFocus::FirstRowHeader);
+        assert_eq!(focus, FocusTarget::RowHeader(0, 0));
+        // The tile stays selected under the header, so Tab goes down to it.
+        press(&mut harness, Key::Tab);
+        assert_eq!(
+            with_root(&harness, |root| root.focus),
+            FocusTarget::Tile(0, 0)
+        );
+    }
+
+    #[test]
+    fn startup_focus_hero_falls_back_to_the_first_tile() {
+        let (_harness, focus) = startup_state(StartupFocus::Hero);
+        assert_eq!(focus, FocusTarget::Tile(0, 0));
+    }
+
+    // The whole startup, from the feed to the painted grid: the collection and every
+    // row load on the UI thread, and animations go by a clock that doesn't move.
+    #[test]
+    fn startup_populates_the_grid() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server)))
+            .with_grab_focus()
+            .with_clock(Rc::new(FakeClock::new()));
+        let harness = harness(root);
+
+        with_root(&harness, |root| {
+            let snapshot = root.snapshot();
+            let titles: Vec<&str> = snapshot.rows.iter().map(|row| row.title.as_str()).collect();
+            assert_eq!(titles, ["Trending", "New to Disney+", "Classics"]);
+            for row in &snapshot.rows {
+                assert_eq!(row.phase, LoadPhase::Loaded, "{}", row.title);
+                assert_eq!(row.tiles.len(), 5, "{}", row.title);
+            }
+            assert_eq!(
+                snapshot.rows[0].tiles[0].title.as_deref(),
+                Some("Trending 0")
+            );
+
+            // Every tile of the first row is painted, and the first one is
+            // highlighted: drawn bigger than its neighbors.
+            assert_eq!(root.selection.get(), Some((0, 0)));
+            let hit_map = root.hit_map.borrow();
+            let painted = |column| {
+                hit_map
+                    .tiles
+                    .iter()
+                    .find(|tile| (tile.row, tile.column) == (0, column))
+                    .map(|tile| tile.rect)
+            };
+            assert!((0..5).all(|column| painted(column).is_some()));
+            assert!(painted(0).unwrap().width() > painted(1).unwrap().width());
+        });
+    }
+
+    // A row of six tiles above a row of two.
+    fn uneven_rows_server() -> MockServer {
+        let server = MockServer::start();
+        let rows = [("long", "Long"), ("short", "Short")];
+        server.add(&home_path(), home_json(&rows).to_string());
+        for (ref_id, tile_count) in [("long", 6), ("short", 2)] {
+            let titles: Vec<String> = (0..tile_count)
+                .map(|column| format!("{} {}", ref_id, column))
+                .collect();
+            server.add(&set_path(ref_id), set_json(&titles).to_string());
+        }
+        server
+    }
+
+    fn selected_item(harness: &Harness) -> (usize, usize) {
+        with_root(harness, |root| root.selected_item)
+    }
+
+    #[test]
+    fn selection_stays_within_each_rows_tiles() {
+        let server = uneven_rows_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        for _ in 0..10 {
+            press(&mut harness, Key::ArrowRight);
+        }
+        assert_eq!(selected_item(&harness), (0, 5));
+
+        // Moving to a shorter row lands on its last tile.
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(selected_item(&harness), (1, 1));
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (1, 1));
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(selected_item(&harness), (1, 1));
+
+        press(&mut harness, Key::ArrowUp);
+        assert_eq!(selected_item(&harness), (0, 1));
+        press(&mut harness, Key::ArrowLeft);
+        press(&mut harness, Key::ArrowLeft);
+        assert_eq!(selected_item(&harness), (0, 0));
+    }
+
+    #[test]
+    fn selection_wraps_around_complete_rows() {
+        let server = uneven_rows_server();
+        let mut config = test_config(&server);
+        config.wrap_selection = true;
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        press(&mut harness, Key::ArrowLeft);
+        assert_eq!(selected_item(&harness), (0, 5));
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (0, 0));
+    }
+
+    // Where tile (row, column) was last painted, in window coordinates.
+    fn painted_rect(harness: &Harness, row: usize, column: usize) -> Option<Rect> {
+        with_root(harness, |root| {
+            let hit_map = root.hit_map.borrow();
+            hit_map
+                .tiles
+                .iter()
+                .find(|tile| (tile.row, tile.column) == (row, column))
+                .map(|tile| tile.rect)
+        })
+    }
+
+    #[test]
+    fn header_focus_and_tile_selection_are_both_drawn() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        let selected = painted_rect(&harness, 0, 0).unwrap();
+        // Everything above the cells of the first row, which is where its header is.
+        let cell_top = selected.center().y - THUMBNAIL_MAX_SIZE / 2.0;
+        let header_area = Rect::new(
+            0.0,
+            0.0,
+            WINDOW_SIZE.width,
+            cell_top - 2.0 * FOCUS_OUTLINE_WIDTH,
+        );
+        let image = harness.render();
+        assert!(!paints_color(&image, header_area, FOCUS_OUTLINE_COLOR));
+
+        press(&mut harness, Key::Tab);
+        with_root(&harness, |root| {
+            assert_eq!(root.focus, FocusTarget::RowHeader(0, 0));
+            assert_eq!(root.selection.get(), Some((0, 0)));
+        });
+        let image = harness.render();
+        assert!(paints_color(&image, header_area, FOCUS_OUTLINE_COLOR));
+        // The selected tile is still drawn bigger than its neighbors.
+        assert_eq!(painted_rect(&harness, 0, 0), Some(selected));
+        assert!(selected.width() > painted_rect(&harness, 0, 1).unwrap().width());
+    }
+
+    #[test]
+    fn right_to_left_mirrors_the_rows_and_the_arrows() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.text_direction = TextDirection::RightToLeft;
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        // The first tile is on the right end of its row, and the next ones go left.
+        let first = painted_rect(&harness, 0, 0).unwrap();
+        let second = painted_rect(&harness, 0, 1).unwrap();
+        assert!(first.center().x > second.center().x);
+        assert!(first.center().x > WINDOW_SIZE.width / 2.0);
+
+        press(&mut harness, Key::ArrowLeft);
+        assert_eq!(selected_item(&harness), (0, 1));
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (0, 0));
+    }
+
+    #[test]
+    fn selected_tiles_arent_clipped_by_their_row() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        // The hit map only has what's inside the row's viewport, so a clipped
+        // tile would be smaller than its image. The fixture images are 500x281.
+        let width = THUMBNAIL_MAX_SIZE * SELECTED_SCALE;
+        let height = width * 281.0 / 500.0;
+        for tile in [(0, 0), (1, 0), (1, 4)] {
+            while selected_item(&harness) != tile {
+                let key = if selected_item(&harness).0 < tile.0 {
+                    Key::ArrowDown
+                } else {
+                    Key::ArrowRight
+                };
+                press(&mut harness, key);
+            }
+            let painted = painted_rect(&harness, tile.0, tile.1).unwrap();
+            assert!((painted.width() - width).abs() < 0.5, "{:?}", painted);
+            assert!((painted.height() - height).abs() < 0.5, "{:?}", painted);
+        }
+    }
+
+    #[test]
+    fn no_initial_selection_waits_for_the_first_key_press() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.initial_selection = None;
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let widths = |harness: &Harness| -> Vec<f64> {
+            (0..5)
+                .map(|column| painted_rect(harness, 0, column).unwrap().width())
+                .collect()
+        };
+
+        assert_eq!(with_root(&harness, |root| root.selection.get()), None);
+        assert_eq!(
+            with_root(&harness, |root| root.snapshot().selected_item),
+            None
+        );
+        let unselected = widths(&harness);
+        assert!(unselected.iter().all(|width| *width == unselected[0]));
+
+        // The first press only shows the selection, on the first tile.
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(
+            with_root(&harness, |root| root.selection.get()),
+            Some((0, 0))
+        );
+        let selected = widths(&harness);
+        assert!(selected[0] > selected[1]);
+    }
+
+    #[test]
+    fn volume_overlay_adjusts_and_hides_when_idle() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.volume = 0.5;
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        press_char(&mut harness, "v");
+        assert!(with_root(&harness, |root| root.volume_overlay.visible));
+        // While it's up, the arrows adjust it instead of moving the selection.
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowLeft);
+        let volume = with_root(&harness, |root| root.volume_overlay.value);
+        assert!((volume - 0.55).abs() < 1e-9, "{}", volume);
+        assert_eq!(selected_item(&harness), (0, 0));
+
+        // Every adjustment restarts the idle timeout.
+        harness.move_timers_forward(IDLE_TIMEOUT / 2);
+        press(&mut harness, Key::ArrowRight);
+        harness.move_timers_forward(IDLE_TIMEOUT * 3 / 4);
+        assert!(with_root(&harness, |root| root.volume_overlay.visible));
+        harness.move_timers_forward(IDLE_TIMEOUT / 2);
+        assert!(!with_root(&harness, |root| root.volume_overlay.visible));
+    }
+
+    #[test]
+    fn load_next_unloaded_row_goes_to_the_first_pending_or_failed_row() {
+        let server = long_feed_server(12);
+        // The second row fails, until its set is fixed below.
+        server.add(&set_path("row1"), "not a set");
+        let mut config = test_config(&server);
+        config.lazy_load_margin = Some(0.0);
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let phases = |harness: &Harness| -> Vec<LoadPhase> {
+            with_root(harness, |root| {
+                root.snapshot().rows.iter().map(|row| row.phase).collect()
+            })
+        };
+
+        let initial = phases(&harness);
+        assert_eq!(initial[0], LoadPhase::Loaded);
+        assert_eq!(initial[1], LoadPhase::Failed);
+        // Rows out of view wait to be scrolled to.
+        let first_pending = initial
+            .iter()
+            .position(|phase| *phase == LoadPhase::Pending)
+            .expect("every row started loading");
+        let pending_path = set_path(&format!("row{}", first_pending));
+        assert_eq!(server.request_count(&pending_path), 0);
+
+        let titles: Vec<String> = (0..5).map(|column| format!("Row 1 {}", column)).collect();
+        server.add(&set_path("row1"), set_json(&titles).to_string());
+        press_char(&mut harness, "l");
+        assert_eq!(selected_item(&harness), (1, 0));
+        assert_eq!(server.request_count(&set_path("row1")), 2);
+        assert_eq!(phases(&harness)[1], LoadPhase::Loaded);
+
+        press_char(&mut harness, "l");
+        assert_eq!(selected_item(&harness), (first_pending, 0));
+        assert_eq!(server.request_count(&pending_path), 1);
+        assert_eq!(phases(&harness)[first_pending], LoadPhase::Loaded);
+    }
+
+    #[test]
+    fn text_only_mode_draws_title_cards_without_fetching_images() {
+        let server = MockServer::start();
+        server.add(
+            &home_path(),
+            home_json(&[("trending", "Trending")]).to_string(),
+        );
+        // Unlike the usual fixtures, these images could actually be fetched.
+        let titles = ["Moana", "Frozen"];
+        let mut set = set_json(
+            &titles
+                .iter()
+                .map(|title| title.to_string())
+                .collect::<Vec<_>>(),
+        );
+        for (index, title) in titles.iter().enumerate() {
+            set["data"]["CuratedSet"]["items"][index]["image"]["tile"]["1.78"]["program"]
+                ["default"]["url"] = json!(format!("{}/images/{}.jpg", server.base_url, title));
+        }
+        server.add(&set_path("trending"), set.to_string());
+        let mut harness = harness(RootWidget::new(Arc::new(test_config(&server))));
+
+        let image = harness.render();
+        for (column, title) in titles.iter().enumerate() {
+            assert_eq!(server.request_count(&format!("/images/{}.jpg", title)), 0);
+            let card = painted_rect(&harness, 0, column).unwrap();
+            assert!(paints_color(&image, card, title_color(title)), "{}", title);
+        }
+    }
+
+    #[test]
+    fn conflicting_selection_commands_leave_one_tile_selected() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+
+        // Both in the same frame, like a click landing with a key press.
+        harness.submit_command(JUMP_TO_TILE.with((0, 2)));
+        harness.submit_command(CLICK_TILE.with((1, 3)));
+        let _ = harness.render();
+
+        assert_eq!(selected_item(&harness), (1, 3));
+        let unselected_width = THUMBNAIL_MAX_SIZE * BASE_SCALE;
+        let grown: Vec<(usize, usize)> = with_root(&harness, |root| {
+            let hit_map = root.hit_map.borrow();
+            hit_map
+                .tiles
+                .iter()
+                .filter(|tile| tile.rect.width() > unselected_width + 0.5)
+                .map(|tile| (tile.row, tile.column))
+                .collect()
+        });
+        assert_eq!(grown, [(1, 3)]);
+    }
+
+    #[test]
+    fn embedded_grid_stays_in_its_box_and_leaves_focus_alone() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server)));
+        let hit_map = root.hit_map.clone();
+        let selection = root.selection.clone();
+        let grid_size = Size::new(600.0, 400.0);
+        let app = Flex::column().with_child(Label::new("My app")).with_child(
+            SizedBox::new(root)
+                .width(grid_size.width)
+                .height(grid_size.height),
+        );
+        let mut harness = Harness::create_with_size(app, WINDOW_SIZE);
+        let _ = harness.render();
+
+        let painted: Vec<Rect> = hit_map
+            .borrow()
+            .tiles
+            .iter()
+            .map(|tile| tile.rect)
+            .collect();
+        assert!(!painted.is_empty());
+        let top = painted
+            .iter()
+            .map(|rect| rect.y0)
+            .fold(f64::INFINITY, f64::min);
+        let bottom = painted.iter().map(|rect| rect.y1).fold(0.0, f64::max);
+        assert!(painted.iter().all(|rect| rect.x1 <= grid_size.width + 0.5));
+        assert!(bottom - top <= grid_size.height, "{} to {}", top, bottom);
+
+        // Without `with_grab_focus`, the grid doesn't take the keyboard.
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(selection.get(), Some((0, 0)));
+    }
+
+    #[test]
+    fn row_limit_caps_the_rows_built() {
+        let server = long_feed_server(10);
+        let mut config = test_config(&server);
+        config.row_limit = Some(3);
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        let ref_ids: Vec<String> = with_root(&harness, |root| {
+            root.snapshot()
+                .rows
+                .iter()
+                .map(|row| row.ref_id.clone())
+                .collect()
+        });
+        assert_eq!(ref_ids, ["row0", "row1", "row2"]);
+        assert_eq!(server.request_count(&set_path("row3")), 0);
+
+        // Navigation stops at the last row shown.
+        for _ in 0..5 {
+            press(&mut harness, Key::ArrowDown);
+        }
+        assert_eq!(selected_item(&harness), (2, 0));
+    }
+
+    #[test]
+    fn custom_help_key_opens_the_help_overlay() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config
+            .keymap
+            .bind(Key::Character("h".to_string()), Action::ShowHelp);
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        press_char(&mut harness, "h");
+        assert!(with_root(&harness, |root| root.help_overlay.visible));
+        // It swallows other keys until it's dismissed.
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (0, 0));
+        press(&mut harness, Key::Escape);
+        assert!(!with_root(&harness, |root| root.help_overlay.visible));
+    }
+
+    // The test harness doesn't run the framework's background executor, so this
+    // is the only way the catalog loads in it.
+    #[test]
+    fn synchronous_loads_complete_without_an_executor() {
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.load_mode = LoadMode::Synchronous;
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let loaded_rows = |harness: &Harness| {
+            with_root(harness, |root| {
+                let snapshot = root.snapshot();
+                let loaded = snapshot
+                    .rows
+                    .iter()
+                    .filter(|row| row.phase == LoadPhase::Loaded && row.tiles.len() == 5)
+                    .count();
+                (snapshot.load_generation, loaded)
+            })
+        };
+        assert_eq!(loaded_rows(&harness), (1, ROWS.len()));
+
+        // Refreshes go through the same path as the first load.
+        press_char(&mut harness, "r");
+        assert_eq!(loaded_rows(&harness), (2, ROWS.len()));
+        assert_eq!(server.request_count(&set_path("trending")), 2);
+    }
+
+    #[test]
+    fn clicking_the_first_crumb_goes_back_home() {
+        let server = catalog_server();
+        let results = json!({
+            "data": {
+                "SearchResults": {
+                    "items": [tile_json("Moana")],
+                    "meta": { "hits": 1, "offset": 0, "page_size": 1 }
+                }
+            }
+        });
+        server.add(&search_path("moana"), results.to_string());
+        let config = test_config(&server);
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+
+        // Two levels deep: the search page, and a result's detail page over it.
+        press_char(&mut harness, "/");
+        for character in ["m", "o", "a", "n", "a"] {
+            press_char(&mut harness, character);
+        }
+        harness.move_timers_forward(SEARCH_DELAY);
+        press(&mut harness, Key::Enter);
+        assert_eq!(
+            with_root(&harness, |root| root.crumbs()),
+            ["Home", "Search", "Moana"]
+        );
+
+        let home_crumb = with_root(&harness, |root| root.breadcrumbs.crumb_rects[0].center());
+        click(&mut harness, home_crumb);
+        with_root(&harness, |root| {
+            assert_eq!(root.crumbs(), ["Home"]);
+            assert!(root.search.is_none());
+            assert!(root.pages.is_empty());
+            // The trail isn't drawn over the grid alone, so it can't be clicked.
+            assert!(root.breadcrumbs.crumb_rects.is_empty());
+        });
+    }
+
+    #[test]
+    fn home_and_set_fetches_use_their_own_timeouts() {
+        let server = catalog_server();
+        let slow = Duration::from_millis(500);
+        server.delay(&home_path(), slow);
+        server.delay(&set_path("trending"), slow);
+        let load = |home_timeout, set_timeout| {
+            let mut config = test_config(&server);
+            config.http.retry.max_attempts = 1;
+            config.http.home_timeout = home_timeout;
+            config.http.set_timeout = set_timeout;
+            harness(RootWidget::new(Arc::new(config)))
+        };
+        let short = Duration::from_millis(100);
+        let long = Duration::from_secs(5);
+
+        // The slow sets are never reached.
+        let harness = load(short, long);
+        with_root(&harness, |root| {
+            assert!(root.collection_error.is_some());
+            assert!(root.snapshot().rows.is_empty());
+        });
+
+        // The collection gets through, but the slow row doesn't. The others are
+        // fast enough for the same timeout.
+        let harness = load(long, short);
+        with_root(&harness, |root| {
+            assert!(root.collection_error.is_none());
+            let phases: Vec<_> = root.snapshot().rows.iter().map(|row| row.phase).collect();
+            assert_eq!(
+                phases,
+                [LoadPhase::Failed, LoadPhase::Loaded, LoadPhase::Loaded]
+            );
+        });
+    }
+
+    #[test]
+    fn only_tiles_added_since_the_last_visit_are_new() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending")], 3);
+        let root = RootWidget::new(Arc::new(test_config(&server)));
+        {
+            let mut session = root.session.borrow_mut();
+            let seen_tiles = &mut session.seen_tiles;
+            seen_tiles.first_visit = false;
+            seen_tiles.previous = ["Trending 0", "Trending 1"]
+                .iter()
+                .map(|title| tile_metadata(title).image_url)
+                .collect();
+        }
+        let mut harness = harness(root);
+
+        let image = harness.render();
+        let badge_area = |column| {
+            let image_rect = painted_rect(&harness, 0, column).unwrap();
+            Rect::from_origin_size(image_rect.origin(), Size::new(40.0, 24.0))
+        };
+        assert!(!paints_color(&image, badge_area(1), NEW_BADGE_COLOR));
+        assert!(paints_color(&image, badge_area(2), NEW_BADGE_COLOR));
+        // The new tile is recorded, so it won't be new on the next visit.
+        with_root(&harness, |root| {
+            let current: Vec<_> = root
+                .session
+                .borrow()
+                .seen_tiles
+                .current
+                .iter()
+                .cloned()
+                .collect();
+            assert_eq!(current, [tile_metadata("Trending 2").image_url]);
+        });
+    }
+
+    #[test]
+    fn rows_only_show_inside_their_availability_window() {
+        let clock = Rc::new(FakeClock::new());
+        let now = clock
+            .wall_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let hour = 3600;
+        let server = MockServer::start();
+        let rows = [
+            ("past", "Past"),
+            ("current", "Current"),
+            ("future", "Future"),
+            ("always", "Always"),
+        ];
+        server.add_catalog(&rows, 3);
+        let mut home = home_json(&rows);
+        let windows = [
+            json!({ "end": now - hour }),
+            json!({ "start": now - hour, "end": now + hour }),
+            json!({ "start": now + hour }),
+        ];
+        for (index, window) in windows.iter().enumerate() {
+            home["data"]["StandardCollection"]["containers"][index]["set"]["availability"] =
+                window.clone();
+        }
+        server.add(&home_path(), home.to_string());
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_clock(clock.clone());
+        let mut harness = harness(root.with_grab_focus());
+        let shown_rows = |harness: &Harness| {
+            with_root(harness, |root| {
+                let snapshot = root.snapshot();
+                let rows = snapshot.rows.iter().map(|row| row.ref_id.clone());
+                rows.collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(shown_rows(&harness), ["current", "always"]);
+
+        // Two hours later, the current row has ended and the future one started.
+        // Rows were collapsed meanwhile, and stay that way.
+        press_char(&mut harness, "c");
+        clock.advance(Duration::from_secs(2 * hour));
+        harness.move_timers_forward(AVAILABILITY_CHECK_INTERVAL);
+        assert_eq!(shown_rows(&harness), ["future", "always"]);
+        with_root(&harness, |root| {
+            assert!(root.rows_collapsed);
+            assert_eq!(root.focus, FocusTarget::RowHeader(0, 0));
+        });
+    }
+
+    #[test]
+    fn a_row_that_fails_to_build_leaves_the_others_alone() {
+        let rows = ROWS
+            .iter()
+            .map(|(ref_id, title)| ContentSetMetadata {
+                title: title.to_string(),
+                description: None,
+                ref_id: ref_id.to_string(),
+                set_type: "CuratedSet".to_string(),
+                available_from: None,
+                available_until: None,
+                load_priority: None,
+                inline_set: None,
+            })
+            .collect();
+        let built = build_guarded(rows, |row, child| {
+            if row == 1 {
+                panic!("weird metadata");
+            }
+            child.ref_id
+        });
+        assert_eq!(
+            built,
+            [
+                (0, Ok("trending".to_string())),
+                (1, Err("New to Disney+".to_string())),
+                (2, Ok("classics".to_string())),
+            ]
+        );
+        assert_eq!(
+            unbuildable_row_message("New to Disney+"),
+            "'New to Disney+' couldn't be displayed"
+        );
+    }
+
+    #[test]
+    fn an_empty_collection_shows_the_empty_feed() {
+        let server = MockServer::start();
+        server.add(&home_path(), home_json(&[]).to_string());
+        let mut harness =
+            harness(RootWidget::new(Arc::new(test_config(&server))).with_grab_focus());
+        with_root(&harness, |root| {
+            assert_eq!(root.collection_state, CollectionState::Empty);
+            assert!(root.collection_error.is_none());
+            assert!(root.snapshot().rows.is_empty());
+        });
+
+        // Refreshing picks up rows added since.
+        server.add_catalog(&ROWS, 5);
+        press_char(&mut harness, "r");
+        with_root(&harness, |root| {
+            assert_eq!(root.collection_state, CollectionState::Loaded);
+            assert_eq!(root.snapshot().rows.len(), ROWS.len());
+        });
+    }
+
+    #[test]
+    fn header_controls_are_reached_from_the_header_and_activated() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let log = CommandLog::new(root);
+        let commands = log.commands.clone();
+        let mut harness = Harness::create_with_size(log, WINDOW_SIZE);
+        let _ = harness.render();
+        let focus = |harness: &Harness| {
+            let root = harness.root_widget();
+            let log = root.downcast::<CommandLog<RootWidget>>().unwrap();
+            log.inner.widget().focus
+        };
+
+        press(&mut harness, Key::Tab);
+        assert_eq!(focus(&harness), FocusTarget::RowHeader(0, 0));
+        // Right goes from the title to the controls, then wraps around.
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(focus(&harness), FocusTarget::RowHeader(0, 1));
+        assert_eq!(HEADER_CONTROLS[0], HeaderControl::SeeAll);
+
+        press(&mut harness, Key::Enter);
+        assert_eq!(sent(&commands, SEE_ALL), [0]);
+        // Activating a control doesn't activate the tile under the header.
+        assert!(sent(&commands, ACTIVATE_TILE).is_empty());
+    }
+
+    #[test]
+    fn activated_items_carry_the_ref_id_of_their_row() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let log = CommandLog::new(root);
+        let commands = log.commands.clone();
+        let mut harness = Harness::create_with_size(log, WINDOW_SIZE);
+        let _ = harness.render();
+
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::Enter);
+        let activated = sent(&commands, ACTIVATE_ITEM);
+        assert_eq!(activated.len(), 1);
+        let item = &activated[0];
+        assert_eq!((item.row, item.column), (1, 2));
+        assert_eq!(item.ref_id, "new");
+        assert_eq!(item.tile.title.as_deref(), Some("New to Disney+ 2"));
+    }
+
+    #[test]
+    fn collapsed_rows_hide_their_tiles_and_only_headers_are_navigable() {
+        let server = catalog_server();
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        let first_tile_color = title_color("Trending 0");
+        let whole_window = Rect::from_origin_size(Point::ORIGIN, WINDOW_SIZE);
+        assert!(paints_color(
+            &harness.render(),
+            whole_window,
+            first_tile_color
+        ));
+        let state = |harness: &Harness| {
+            with_root(harness, |root| {
+                let tile_count = root.hit_map.borrow().tiles.len();
+                (root.focus, root.selected_item, tile_count)
+            })
+        };
+
+        press_char(&mut harness, "c");
+        assert_eq!(state(&harness), (FocusTarget::RowHeader(0, 0), (0, 0), 0));
+        assert!(!paints_color(
+            &harness.render(),
+            whole_window,
+            first_tile_color
+        ));
+
+        // Every key moves between headers and their controls, and the selected
+        // tile stays where it was.
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(state(&harness), (FocusTarget::RowHeader(1, 0), (0, 0), 0));
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(state(&harness), (FocusTarget::RowHeader(1, 1), (0, 0), 0));
+        press(&mut harness, Key::Tab);
+        assert_eq!(state(&harness), (FocusTarget::RowHeader(1, 1), (0, 0), 0));
+
+        // Expanding goes back to the selected tile.
+        press_char(&mut harness, "c");
+        let (focus, selected_item, tile_count) = state(&harness);
+        assert_eq!((focus, selected_item), (FocusTarget::Tile(0, 0), (0, 0)));
+        assert!(tile_count > 0);
+        assert!(paints_color(
+            &harness.render(),
+            whole_window,
+            first_tile_color
+        ));
+    }
+
+    #[test]
+    fn typing_with_alt_jumps_within_the_selected_row() {
+        let server = MockServer::start();
+        let rows = [("classics", "Classics"), ("sequels", "Sequels")];
+        server.add(&home_path(), home_json(&rows).to_string());
+        let classics = ["Moana", "Frozen", "Mulan", "Encanto"];
+        let sequels = ["Mulan II", "Moana 2"];
+        for (ref_id, titles) in [("classics", &classics[..]), ("sequels", &sequels[..])] {
+            let titles: Vec<String> = titles.iter().map(|title| title.to_string()).collect();
+            server.add(&set_path(ref_id), set_json(&titles).to_string());
+        }
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        let type_with_alt = |harness: &mut Harness, text: &str| {
+            for character in text.chars() {
+                let key = Key::Character(character.to_string());
+                let event = KeyEvent::for_test(Modifiers::ALT, key);
+                harness.process_event(Event::KeyDown(event));
+                let _ = harness.render();
+            }
+        };
+
+        // "Mulan II" starts with "mu" too, but it's in another row.
+        type_with_alt(&mut harness, "mu");
+        let selected_item = with_root(&harness, |root| root.snapshot().selected_item);
+        assert_eq!(selected_item, Some((0, 2)));
+    }
+
+    #[test]
+    fn the_info_overlay_scrolls_its_description_and_stops_at_the_ends() {
+        let server = MockServer::start();
+        server.add(
+            &home_path(),
+            home_json(&[("trending", "Trending")]).to_string(),
+        );
+        let mut tile = tile_json("Moana");
+        let description = "A description too long for the overlay. ".repeat(100);
+        tile["text"]["description"] =
+            json!({ "full": { "program": { "default": { "content": description } } } });
+        let set = json!({ "data": { "CuratedSet": { "items": [tile] } } });
+        server.add(&set_path("trending"), set.to_string());
+        let root = RootWidget::new(Arc::new(test_config(&server))).with_grab_focus();
+        let mut harness = harness(root);
+        let scroll = |harness: &Harness| {
+            with_root(harness, |root| {
+                let overlay = &root.info_overlay;
+                (
+                    overlay.scroll_offset,
+                    overlay.scroll_target,
+                    overlay.max_scroll,
+                )
+            })
+        };
+        let frame = Duration::from_millis(16).as_nanos() as u64;
+
+        press_char(&mut harness, "i");
+        assert!(with_root(&harness, |root| root.info_overlay.visible));
+        let (_, _, max_scroll) = scroll(&harness);
+        assert!(max_scroll > 0.0);
+        // Already at the top.
+        press(&mut harness, Key::ArrowUp);
+        assert_eq!(scroll(&harness), (0.0, 0.0, max_scroll));
+
+        // The description glides towards where Down sends it.
+        press(&mut harness, Key::ArrowDown);
+        harness.process_event(Event::AnimFrame(frame));
+        let (offset, target, _) = scroll(&harness);
+        assert!(0.0 < offset && offset < target, "{} {}", offset, target);
+
+        for _ in 0..50 {
+            press(&mut harness, Key::ArrowDown);
+        }
+        for _ in 0..100 {
+            harness.process_event(Event::AnimFrame(frame));
+        }
+        assert_eq!(scroll(&harness), (max_scroll, max_scroll, max_scroll));
+
+        for _ in 0..50 {
+            press(&mut harness, Key::ArrowUp);
+        }
+        for _ in 0..100 {
+            harness.process_event(Event::AnimFrame(frame));
+        }
+        assert_eq!(scroll(&harness), (0.0, 0.0, max_scroll));
+    }
+
+    #[test]
+    fn a_row_that_shrinks_on_refresh_moves_the_selection_back_in() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending"), ("new", "New")], 20);
+        let mut config = test_config(&server);
+        config
+            .row_max_age
+            .insert("trending".to_string(), Duration::from_secs(60));
+        let clock = Rc::new(FakeClock::new());
+        let root = RootWidget::new(Arc::new(config))
+            .with_grab_focus()
+            .with_clock(clock.clone());
+        let mut harness = harness(root);
+        for _ in 0..19 {
+            press(&mut harness, Key::ArrowRight);
+        }
+        press(&mut harness, Key::ArrowDown);
+        assert_eq!(with_root(&harness, |root| root.selected_item), (1, 19));
+
+        // Coming back to the stale row refreshes it, with fewer tiles than before.
+        let titles: Vec<String> = (0..8)
+            .map(|column| format!("Trending {}", column))
+            .collect();
+        server.add(&set_path("trending"), set_json(&titles).to_string());
+        clock.advance(Duration::from_secs(61));
+        press(&mut harness, Key::ArrowUp);
+        assert_eq!(server.request_count(&set_path("trending")), 2);
+
+        let (selected_item, focus) = with_root(&harness, |root| (root.selected_item, root.focus));
+        assert_eq!(selected_item, (0, 7));
+        assert_eq!(focus, FocusTarget::Tile(0, 7));
+        // The row pans to the new last tile, which is painted whole.
+        let rect = with_root(&harness, |root| {
+            let hit_map = root.hit_map.borrow();
+            hit_map
+                .tiles
+                .iter()
+                .find(|tile| (tile.row, tile.column) == (0, 7))
+                .map(|tile| tile.rect)
+        })
+        .expect("the selected tile wasn't painted");
+        assert!(
+            rect.width() >= THUMBNAIL_MAX_SIZE * BASE_SCALE - 0.5,
+            "{:?}",
+            rect
+        );
+        assert!(rect.x1 <= WINDOW_SIZE.width, "{:?}", rect);
+    }
+
+    #[test]
+    fn the_pinned_row_stays_on_top_while_the_feed_scrolls() {
+        let server = MockServer::start();
+        server.add_catalog(
+            &[
+                ("trending", "Trending"),
+                ("new", "New"),
+                ("classics", "Classics"),
+                ("continue", "Continue watching"),
+                ("marvel", "Marvel"),
+                ("pixar", "Pixar"),
+            ],
+            5,
+        );
+        let mut config = test_config(&server);
+        config.pinned_row = Some("continue".to_string());
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let tile_rect = |harness: &Harness, row: usize| {
+            with_root(harness, |root| {
+                let hit_map = root.hit_map.borrow();
+                hit_map
+                    .tiles
+                    .iter()
+                    .find(|tile| (tile.row, tile.column) == (row, 0))
+                    .map(|tile| tile.rect)
+            })
+        };
+
+        let pinned_ref_id = with_root(&harness, |root| {
+            root.pinned_row
+                .as_ref()
+                .map(|pinned_row| pinned_row.widget().data.ref_id.clone())
+        });
+        assert_eq!(pinned_ref_id.as_deref(), Some("continue"));
+        let pinned_rect = tile_rect(&harness, 0).expect("the pinned row wasn't painted");
+
+        // Going down to the last row scrolls the feed, but not the pinned row.
+        for _ in 0..5 {
+            press(&mut harness, Key::ArrowDown);
+        }
+        assert_eq!(with_root(&harness, |root| root.selected_item), (5, 0));
+        let last_rect = tile_rect(&harness, 5).expect("the last row wasn't painted");
+        assert!(last_rect.y1 <= WINDOW_SIZE.height, "{:?}", last_rect);
+        assert!(last_rect.y0 > pinned_rect.y1, "{:?}", last_rect);
+        assert_eq!(tile_rect(&harness, 0), Some(pinned_rect));
+
+        // Rows scrolled beneath the pinned row can't be clicked through it.
+        assert_eq!(
+            with_root(&harness, |root| root.tile_at(pinned_rect.center())),
+            Some((0, 0))
+        );
+    }
+
+    #[test]
+    fn retrying_failed_rows_reloads_only_those() {
+        let server = catalog_server();
+        // The last two rows fail, until their sets are fixed below.
+        server.add(&set_path("new"), "not a set");
+        server.add(&set_path("classics"), "not a set");
+        let mut harness =
+            harness(RootWidget::new(Arc::new(test_config(&server))).with_grab_focus());
+        let phases = |harness: &Harness| -> Vec<LoadPhase> {
+            with_root(harness, |root| {
+                root.snapshot().rows.iter().map(|row| row.phase).collect()
+            })
+        };
+        assert_eq!(
+            phases(&harness),
+            vec![LoadPhase::Loaded, LoadPhase::Failed, LoadPhase::Failed]
+        );
+
+        server.add_catalog(&ROWS, 5);
+        press_char(&mut harness, "R");
+        assert_eq!(phases(&harness), vec![LoadPhase::Loaded; 3]);
+        assert_eq!(server.request_count(&set_path("trending")), 1);
+        assert_eq!(server.request_count(&set_path("new")), 2);
+        assert_eq!(server.request_count(&set_path("classics")), 2);
+        assert_eq!(server.request_count(&home_path()), 1);
+        let (retrying_rows, recovered_rows) = with_root(&harness, |root| {
+            (root.retrying_rows.clone(), root.recovered_rows)
+        });
+        assert!(retrying_rows.is_empty(), "{:?}", retrying_rows);
+        assert_eq!(recovered_rows, 2);
+
+        // With nothing left to retry, nothing is fetched.
+        press_char(&mut harness, "R");
+        assert_eq!(server.requested_paths().len(), 6);
+    }
+
+    #[test]
+    fn jumping_to_unvisited_lands_on_the_first_tile_not_opened_yet() {
+        let server = catalog_server();
+        let mut harness =
+            harness(RootWidget::new(Arc::new(test_config(&server))).with_grab_focus());
+
+        // Open every tile of the first row, and the first tile of the second one.
+        press(&mut harness, Key::Enter);
+        for _ in 1..5 {
+            press(&mut harness, Key::ArrowRight);
+            press(&mut harness, Key::Enter);
+        }
+        press(&mut harness, Key::ArrowDown);
+        for _ in 0..5 {
+            press(&mut harness, Key::ArrowLeft);
+        }
+        assert_eq!(with_root(&harness, |root| root.selected_item), (1, 0));
+        press(&mut harness, Key::Enter);
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+
+        press_char(&mut harness, "u");
+        let (selected_item, focus) = with_root(&harness, |root| (root.selected_item, root.focus));
+        assert_eq!(selected_item, (1, 1));
+        assert_eq!(focus, FocusTarget::Tile(1, 1));
+    }
+
+    #[test]
+    fn session_changes_are_written_once_per_interval_and_on_exit() {
+        let dir = std::env::temp_dir().join(format!("session-save-{}", std::process::id()));
+        let path = dir.join("window_size");
+        let server = catalog_server();
+        let mut config = test_config(&server);
+        config.window_size_path = Some(path.clone());
+        config.session_save_interval = Some(Duration::from_secs(1));
+        let mut harness = harness(RootWidget::new(Arc::new(config)));
+        let saved = || std::fs::read_to_string(&path).ok();
+
+        // Resizes within the interval are saved together, once it's over.
+        harness.process_event(Event::WindowSize(Size::new(800.0, 600.0)));
+        harness.process_event(Event::WindowSize(Size::new(900.0, 650.0)));
+        assert_eq!(saved(), None);
+        harness.move_timers_forward(Duration::from_secs(1));
+        assert_eq!(saved().as_deref(), Some("900 650\n"));
+
+        // With nothing new, nothing is written again.
+        std::fs::remove_file(&path).unwrap();
+        harness.move_timers_forward(Duration::from_secs(2));
+        assert_eq!(saved(), None);
+
+        // What's left when the app closes is saved then.
+        harness.process_event(Event::WindowSize(Size::new(1000.0, 700.0)));
+        assert_eq!(saved(), None);
+        drop(harness);
+        assert_eq!(saved().as_deref(), Some("1000 700\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn switching_profiles_rebuilds_the_grid_and_restores_their_selection() {
+        let server = catalog_server();
+        let kids_rows = [("cartoons", "Cartoons"), ("princesses", "Princesses")];
+        let kids_home = "/kids/home.json";
+        server.add(kids_home, home_json(&kids_rows).to_string());
+        for (ref_id, title) in &kids_rows {
+            let titles: Vec<String> = (0..5)
+                .map(|column| format!("{} {}", title, column))
+                .collect();
+            server.add(&set_path(ref_id), set_json(&titles).to_string());
+        }
+        let dir = std::env::temp_dir().join(format!("profiles-{}", std::process::id()));
+        let mut config = test_config(&server);
+        config.profiles_dir = dir.clone();
+        // Only the file name is kept: each profile saves its own, in its directory.
+        config.favorites_path = Some(PathBuf::from("favorites.json"));
+        config.profiles = vec![
+            Profile {
+                name: "grown-ups".to_string(),
+                collection_url: None,
+            },
+            Profile {
+                name: "kids".to_string(),
+                collection_url: Some(format!("{}{}", server.base_url, kids_home)),
+            },
+        ];
+        let mut harness = harness(RootWidget::new(Arc::new(config)).with_grab_focus());
+        let row_titles = |harness: &Harness| -> Vec<String> {
+            with_root(harness, |root| {
+                root.snapshot()
+                    .rows
+                    .iter()
+                    .map(|row| row.title.clone())
+                    .collect()
+            })
+        };
+        let selected_item = |harness: &Harness| with_root(harness, |root| root.selected_item);
+        assert_eq!(
+            row_titles(&harness),
+            vec!["Trending", "New to Disney+", "Classics"]
+        );
+
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (2, 1));
+
+        harness.submit_command(SWITCH_PROFILE.with("kids".to_string()));
+        assert_eq!(row_titles(&harness), vec!["Cartoons", "Princesses"]);
+        assert_eq!(server.request_count(kids_home), 1);
+        assert_eq!(selected_item(&harness), (0, 0));
+        press(&mut harness, Key::ArrowDown);
+        press(&mut harness, Key::ArrowRight);
+        press(&mut harness, Key::ArrowRight);
+        assert_eq!(selected_item(&harness), (1, 2));
+        press_char(&mut harness, "f");
+
+        // Each profile gets its feed again, with the selection it had.
+        harness.submit_command(SWITCH_PROFILE.with("grown-ups".to_string()));
+        assert_eq!(
+            row_titles(&harness),
+            vec!["Trending", "New to Disney+", "Classics"]
+        );
+        assert_eq!(server.request_count(&home_path()), 2);
+        assert_eq!(selected_item(&harness), (2, 1));
+        harness.submit_command(SWITCH_PROFILE.with("kids".to_string()));
+        assert_eq!(
+            row_titles(&harness),
+            vec!["Favorites", "Cartoons", "Princesses"]
+        );
+        assert_eq!(selected_item(&harness), (1, 2));
+        // The profile's directory was made when its favorites were first saved.
+        assert!(dir.join("kids").join("favorites.json").exists());
+        assert!(!dir.join("grown-ups").exists());
+
+        drop(harness);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,584 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::promise::PromiseToken;
+use widget_cruncher::shell::keyboard_types::Key;
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{AsWidgetPod, WidgetPod};
+use widget_cruncher::{Color, Command, Point, Rect, Selector, Target, TimerToken};
+
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, LoadMode};
+use crate::content_set::{
+    load_content_set, ActivatedItem, ContentSetPage, TileMetadata, ACTIVATE_ITEM,
+};
+use crate::keymap::Action;
+use crate::net::{CancelHandle, FetchError};
+use crate::session::SharedSession;
+use crate::thumbnail::{
+    cell_size, SharedSelection, Thumbnail, CHANGE_SELECTED_ITEM, CLICK_TILE, THUMBNAIL_OVERFLOW,
+};
+
+// Sent by the RootWidget with each key pressed while the search page is open,
+// since the RootWidget keeps keyboard focus.
+pub const SEARCH_KEY: Selector<Key> = Selector::new("search_key");
+
+// Results are shown as Thumbnails in this row, which no row of the grid has, so
+// that commands about the grid's tiles never match them.
+pub const SEARCH_ROW: usize = usize::MAX;
+
+// The key of the results in the documents of the search endpoint. Like sets, a
+// document with a single key is fine whatever it is. See `api::fetch_set`.
+const SEARCH_SET_TYPE: &str = "SearchResults";
+
+// How long after the last key press the query is sent, so typing a word
+// doesn't send a request for every letter.
+pub const SEARCH_DELAY: Duration = Duration::from_millis(300);
+
+const PADDING: f64 = 32.0;
+const FIELD_HEIGHT: f64 = 48.0;
+const FIELD_COLOR: Color = Color::rgb8(0x1c, 0x20, 0x2c);
+const PLACEHOLDER_COLOR: Color = Color::rgb8(0x80, 0x84, 0x90);
+const BACKGROUND_COLOR: Color = Color::rgb8(0x0e, 0x10, 0x18);
+
+// A single line of text typed with the keyboard, with a caret at its end. It
+// doesn't get keys itself: its owner passes them to `edit`.
+pub struct SearchField {
+    pub text: String,
+    // Shown while the text is empty.
+    pub placeholder: String,
+}
+
+// Searches the catalog as the user types, and shows the results in a grid of
+// Thumbnails. The RootWidget opens it over the grid, under any DetailPage or
+// player opened from its results.
+pub struct SearchPage {
+    pub config: Arc<AppConfig>,
+    pub session: SharedSession,
+    pub clock: SharedClock,
+
+    pub field: WidgetPod<SearchField>,
+    pub results: Vec<WidgetPod<Thumbnail>>,
+    // The tiles of `results`, for ACTIVATE_ITEM.
+    pub result_data: Vec<TileMetadata>,
+    // The result's Thumbnails read this, like the grid's read the RootWidget's.
+    pub selection: SharedSelection,
+    pub selected: Option<usize>,
+
+    // How many results fit in a line, and how far the results are scrolled so
+    // that the selected one is visible. Both are updated on layout.
+    pub columns: usize,
+    pub scroll_y: f64,
+
+    pub search_timer: TimerToken,
+    pub search_promise: PromiseToken<Result<ContentSetPage, FetchError>>,
+    pub cancel: CancelHandle,
+    // The query of the last search sent, and how it went.
+    pub searched_query: String,
+    pub searching: bool,
+    pub error: Option<String>,
+}
+
+// --- METHODS ---
+
+impl SearchField {
+    pub fn new(placeholder: impl Into<String>) -> Self {
+        Self {
+            text: String::new(),
+            placeholder: placeholder.into(),
+        }
+    }
+
+    // Types `key` into the field. Returns whether the text changed: keys that
+    // aren't characters or Backspace are left to the owner.
+    pub fn edit(&mut self, key: &Key) -> bool {
+        match key {
+            Key::Character(text) => {
+                self.text.push_str(text);
+                true
+            }
+            Key::Backspace => self.text.pop().is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl SearchPage {
+    pub fn new(config: Arc<AppConfig>, session: SharedSession, clock: SharedClock) -> Self {
+        Self {
+            config,
+            session,
+            clock,
+            field: WidgetPod::new(SearchField::new("Search titles")),
+            results: Vec::new(),
+            result_data: Vec::new(),
+            selection: Rc::new(Cell::new(None)),
+            selected: None,
+            columns: 1,
+            scroll_y: 0.0,
+            search_timer: TimerToken::INVALID,
+            search_promise: PromiseToken::empty(),
+            cancel: CancelHandle::default(),
+            searched_query: String::new(),
+            searching: false,
+            error: None,
+        }
+    }
+
+    fn query(&self) -> &str {
+        self.field.widget().text.trim()
+    }
+
+    fn on_key(&mut self, ctx: &mut EventCtx, key: &Key) {
+        // Characters always go into the query, even the ones bound to actions,
+        // like Space.
+        if self.field.widget_mut().edit(key) {
+            self.on_query_changed(ctx);
+            return;
+        }
+        let columns = self.columns as isize;
+        match self.config.keymap.action(key) {
+            Some(Action::Left) => self.move_selection(ctx, -1),
+            Some(Action::Right) => self.move_selection(ctx, 1),
+            Some(Action::Up) => self.move_selection(ctx, -columns),
+            Some(Action::Down) => self.move_selection(ctx, columns),
+            Some(Action::Activate) => self.activate_selected(ctx),
+            _ => {}
+        }
+    }
+
+    // Cancels the search in flight, and sends the new query once the user stops
+    // typing. Until then, the previous results are hidden.
+    fn on_query_changed(&mut self, ctx: &mut EventCtx) {
+        self.cancel.cancel();
+        self.search_promise = PromiseToken::empty();
+        self.searching = false;
+        if self.query().is_empty() {
+            self.search_timer = TimerToken::INVALID;
+            self.set_results(ctx, Vec::new());
+            self.searched_query.clear();
+            self.error = None;
+        } else {
+            self.search_timer = ctx.request_timer(SEARCH_DELAY);
+        }
+        ctx.request_paint();
+    }
+
+    fn start_search(&mut self, ctx: &mut EventCtx) {
+        let query = self.query().to_string();
+        let url = search_url(&self.config, &query);
+        let dedupe = self.config.dedupe_tiles;
+        let timeout = self.config.http.set_timeout;
+        self.cancel = CancelHandle::default();
+        self.searched_query = query;
+        self.searching = true;
+        self.error = None;
+        match self.config.load_mode {
+            LoadMode::Background => {
+                let cancel = self.cancel.clone();
+                self.search_promise = ctx.compute_in_background(move |_| {
+                    load_content_set(&url, SEARCH_SET_TYPE, dedupe, timeout, &cancel)
+                });
+            }
+            LoadMode::Synchronous => {
+                let results =
+                    load_content_set(&url, SEARCH_SET_TYPE, dedupe, timeout, &self.cancel);
+                self.on_results_loaded(ctx, results);
+            }
+        }
+        ctx.request_paint();
+    }
+
+    fn on_results_loaded(
+        &mut self,
+        ctx: &mut EventCtx,
+        results: Result<ContentSetPage, FetchError>,
+    ) {
+        self.searching = false;
+        match results {
+            Ok(page) => self.set_results(ctx, page.tiles),
+            // Only if the query changed since, so another search replaces this one.
+            Err(FetchError::Cancelled) => {}
+            Err(err) => {
+                tracing::error!("Search for '{}' failed: {}", self.searched_query, err);
+                self.error = Some(err.to_string());
+                self.set_results(ctx, Vec::new());
+            }
+        }
+        ctx.request_paint();
+    }
+
+    // Replaces the results, and selects the first one.
+    fn set_results(&mut self, ctx: &mut EventCtx, tiles: Vec<TileMetadata>) {
+        self.selected = if tiles.is_empty() { None } else { Some(0) };
+        self.selection
+            .set(self.selected.map(|column| (SEARCH_ROW, column)));
+        self.results = tiles
+            .iter()
+            .enumerate()
+            .map(|(column, tile)| {
+                let thumbnail = Thumbnail::new(
+                    SEARCH_ROW,
+                    column,
+                    tile.clone(),
+                    &self.config,
+                    self.selection.clone(),
+                    self.session.clone(),
+                    self.clock.clone(),
+                )
+                .with_state(false);
+                WidgetPod::new(thumbnail)
+            })
+            .collect();
+        self.result_data = tiles;
+        self.scroll_y = 0.0;
+        ctx.children_changed();
+        ctx.request_layout();
+    }
+
+    // Moves the selection by `delta` results, without going past either end.
+    fn move_selection(&mut self, ctx: &mut EventCtx, delta: isize) {
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return,
+        };
+        let last = self.results.len() as isize - 1;
+        let target = (selected as isize + delta).max(0).min(last) as usize;
+        self.select_result(ctx, target);
+    }
+
+    fn select_result(&mut self, ctx: &mut EventCtx, index: usize) {
+        let previous = self.selected;
+        if previous == Some(index) {
+            return;
+        }
+        self.selected = Some(index);
+        self.selection.set(Some((SEARCH_ROW, index)));
+        // Only the two results that changed are told, so that the grid's
+        // Thumbnails under the page don't react to it.
+        for column in previous.into_iter().chain(Some(index)) {
+            if let Some(result) = self.results.get(column) {
+                ctx.submit_command(
+                    Command::from(CHANGE_SELECTED_ITEM).to(Target::Widget(result.id())),
+                );
+            }
+        }
+        ctx.request_layout();
+    }
+
+    // Like activating a tile of the grid. Results aren't from a set, so they're
+    // sent without a ref id.
+    fn activate_selected(&mut self, ctx: &mut EventCtx) {
+        let column = match self.selected {
+            Some(column) => column,
+            None => return,
+        };
+        let tile = self.result_data[column].clone();
+        self.session
+            .borrow_mut()
+            .visited_tiles
+            .insert(tile.image_url.clone());
+        ctx.submit_command(ACTIVATE_ITEM.with(ActivatedItem {
+            row: SEARCH_ROW,
+            column,
+            ref_id: String::new(),
+            tile,
+        }));
+    }
+
+    // Where the results start, below the field.
+    fn results_top(&self) -> f64 {
+        PADDING + FIELD_HEIGHT + PADDING
+    }
+
+    // Shown instead of the results while there are none.
+    fn status_text(&self) -> Option<String> {
+        if self.query().is_empty() {
+            Some("Type to search for a title.".to_string())
+        } else if let Some(error) = &self.error {
+            Some(format!("Couldn't search: {}", error))
+        } else if self.searching || self.search_timer != TimerToken::INVALID {
+            Some("Searching\u{2026}".to_string())
+        } else if self.results.is_empty() {
+            Some(format!("No results for '{}'.", self.searched_query))
+        } else {
+            None
+        }
+    }
+}
+
+// Where results for `query` are fetched from, according to
+// `config.search_url_template`.
+pub fn search_url(config: &AppConfig, query: &str) -> String {
+    config
+        .search_url_template
+        .replace("{query}", &encode_path_segment(query))
+}
+
+// Percent-encodes everything but unreserved characters, so the query can't
+// change the shape of the URL.
+fn encode_path_segment(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+impl Drop for SearchPage {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for SearchField {
+    fn on_event(&mut self, _ctx: &mut EventCtx, _event: &Event, _env: &Env) {}
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        bc.constrain(Size::new(bc.max().width, FIELD_HEIGHT))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+        const TEXT_PADDING: f64 = 14.0;
+        const CARET_WIDTH: f64 = 2.0;
+
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect.to_rounded_rect(4.0), &FIELD_COLOR);
+
+        let (text, color) = if self.text.is_empty() {
+            (self.placeholder.clone(), PLACEHOLDER_COLOR)
+        } else {
+            (self.text.clone(), Color::WHITE)
+        };
+        let layout = ctx
+            .text()
+            .new_text_layout(text)
+            .font(FontFamily::SYSTEM_UI, 20.0)
+            .text_color(color)
+            .build()
+            .unwrap();
+        let text_size = layout.size();
+        let text_origin = Point::new(TEXT_PADDING, (rect.height() - text_size.height) / 2.0);
+        ctx.clip(rect);
+        ctx.draw_text(&layout, text_origin);
+
+        let caret_x = if self.text.is_empty() {
+            text_origin.x
+        } else {
+            text_origin.x + text_size.width
+        };
+        let caret = Rect::new(
+            caret_x,
+            text_origin.y,
+            caret_x + CARET_WIDTH,
+            text_origin.y + text_size.height,
+        );
+        ctx.fill(caret, &Color::WHITE);
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("SearchField")
+    }
+}
+
+impl Widget for SearchPage {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        match event {
+            // This happens after the callback passed to `ctx.compute_in_background` returns
+            Event::PromiseResult(result) => {
+                if let Some(results) = result.try_get(self.search_promise) {
+                    self.on_results_loaded(ctx, results);
+                    return;
+                }
+            }
+            Event::Timer(token) if *token == self.search_timer => {
+                self.search_timer = TimerToken::INVALID;
+                self.start_search(ctx);
+                return;
+            }
+            Event::Command(command) => {
+                if let Some(key) = command.try_get(SEARCH_KEY) {
+                    self.on_key(ctx, key);
+                }
+                // Clicking a result selects it, and clicking the selected one
+                // activates it, like on the grid.
+                if let Some((row, column)) = command.try_get(CLICK_TILE) {
+                    if *row == SEARCH_ROW && *column < self.results.len() {
+                        if self.selected == Some(*column) {
+                            self.activate_selected(ctx);
+                        } else {
+                            self.select_result(ctx, *column);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.field.on_event(ctx, event, env);
+        for result in &mut self.results {
+            result.on_event(ctx, event, env);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.init();
+        self.field.lifecycle(ctx, event, env);
+        for result in &mut self.results {
+            result.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = bc.max();
+        let field_bc = BoxConstraints::tight(Size::new(size.width - 2.0 * PADDING, FIELD_HEIGHT));
+        self.field.layout(ctx, &field_bc, env);
+        self.field
+            .set_origin(ctx, env, Point::new(PADDING, PADDING));
+
+        // Results leave room around them for the selected one to grow into.
+        let cell = cell_size(env);
+        let results_width = size.width - 2.0 * (PADDING + THUMBNAIL_OVERFLOW);
+        self.columns = ((results_width / cell).floor() as usize).max(1);
+        let results_top = self.results_top() + THUMBNAIL_OVERFLOW;
+        let visible_height = size.height - results_top - THUMBNAIL_OVERFLOW;
+        if let Some(selected) = self.selected {
+            let line_top = (selected / self.columns) as f64 * cell;
+            let line_bottom = line_top + cell;
+            if line_top < self.scroll_y {
+                self.scroll_y = line_top;
+            } else if line_bottom > self.scroll_y + visible_height {
+                self.scroll_y = (line_bottom - visible_height).max(0.0);
+            }
+        }
+
+        let cell_bc = BoxConstraints::tight(Size::new(cell, cell));
+        for (index, result) in self.results.iter_mut().enumerate() {
+            result.layout(ctx, &cell_bc, env);
+            let origin = Point::new(
+                PADDING + THUMBNAIL_OVERFLOW + (index % self.columns) as f64 * cell,
+                results_top + (index / self.columns) as f64 * cell - self.scroll_y,
+            );
+            result.set_origin(ctx, env, origin);
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &BACKGROUND_COLOR);
+        self.field.paint(ctx, env);
+
+        if let Some(status) = self.status_text() {
+            let status_text = ctx
+                .text()
+                .new_text_layout(status)
+                .font(FontFamily::SYSTEM_UI, 16.0)
+                .text_color(PLACEHOLDER_COLOR)
+                .max_width(rect.width() - 2.0 * PADDING)
+                .build()
+                .unwrap();
+            ctx.draw_text(&status_text, Point::new(PADDING, self.results_top()));
+            return;
+        }
+
+        // Results scrolled above the top of the area don't draw over the field.
+        let results_area = Rect::new(0.0, self.results_top(), rect.x1, rect.y1);
+        let selected = self.selected;
+        let results = &mut self.results;
+        ctx.with_save(|ctx| {
+            ctx.clip(results_area);
+            // Like in a TileRow, the selected result is painted last, so that it
+            // shows above its neighbors while it's bigger than its cell.
+            for (index, result) in results.iter_mut().enumerate() {
+                if Some(index) != selected {
+                    result.paint(ctx, env);
+                }
+            }
+            if let Some(result) = selected.and_then(|index| results.get_mut(index)) {
+                result.paint(ctx, env);
+            }
+        });
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> =
+            smallvec![&self.field as &dyn AsWidgetPod];
+        children.extend(self.results.iter().map(|result| result as &dyn AsWidgetPod));
+        children
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> =
+            smallvec![&mut self.field as &mut dyn AsWidgetPod];
+        children.extend(
+            self.results
+                .iter_mut()
+                .map(|result| result as &mut dyn AsWidgetPod),
+        );
+        children
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("SearchPage")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreserved_characters_are_kept() {
+        assert_eq!(encode_path_segment("Moana-2_v1.0~"), "Moana-2_v1.0~");
+    }
+
+    #[test]
+    fn everything_else_is_percent_encoded() {
+        assert_eq!(encode_path_segment("star wars"), "star%20wars");
+        assert_eq!(encode_path_segment("a/b?c#d"), "a%2Fb%3Fc%23d");
+        // Byte by byte, for characters outside of ASCII.
+        assert_eq!(encode_path_segment("Café"), "Caf%C3%A9");
+    }
+
+    #[test]
+    fn search_url_fills_in_the_encoded_query() {
+        let mut config = AppConfig::default();
+        config.set_api("https://cdn.invalid", "collection");
+        assert_eq!(
+            search_url(&config, "star wars"),
+            "https://cdn.invalid/collection/search/star%20wars.json"
+        );
+    }
+}
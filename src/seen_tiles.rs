@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use crate::paths::write_file;
+
+// Tiles the user has had in their catalog, so that the ones added since their
+// last visit can get a "NEW" badge. Keyed by image URL, like Favorites.
+// Saved to `path` as one URL per line.
+pub struct SeenTiles {
+    pub path: Option<PathBuf>,
+    // What was saved by the last visit. This doesn't change during a session,
+    // so tiles stay "new" until the next one.
+    pub previous: BTreeSet<String>,
+    pub current: BTreeSet<String>,
+    // On the very first visit, every tile would count as new, so none does.
+    pub first_visit: bool,
+    // Whether `current` changed since it was last saved. See `SessionState::flush`.
+    pub dirty: bool,
+}
+
+impl SeenTiles {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let contents = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        let first_visit = contents.is_none();
+        let previous = contents
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_string())
+            .collect();
+        Self {
+            path,
+            previous,
+            current: BTreeSet::new(),
+            first_visit,
+            dirty: false,
+        }
+    }
+
+    pub fn is_new(&self, image_url: &str) -> bool {
+        !self.first_visit && !self.previous.contains(image_url)
+    }
+
+    // Called with every page of tiles as it loads.
+    pub fn record<'a>(&mut self, image_urls: impl Iterator<Item = &'a str>) {
+        let before = self.current.len();
+        let previous = &self.previous;
+        self.current.extend(
+            image_urls
+                .filter(|url| !previous.contains(*url))
+                .map(|url| url.to_string()),
+        );
+        if self.current.len() != before {
+            self.dirty = true;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.save();
+        }
+    }
+
+    // Tiles we didn't get to load this session are kept, so they don't come
+    // back as "new" next time.
+    fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let contents = self
+            .previous
+            .union(&self.current)
+            .map(|url| format!("{}\n", url))
+            .collect::<String>();
+        if let Err(err) = write_file(path, contents) {
+            tracing::warn!("Failed to save seen tiles to {}: {}", path.display(), err);
+        }
+    }
+}
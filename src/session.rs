@@ -0,0 +1,59 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use crate::config::AppConfig;
+use crate::favorites::Favorites;
+use crate::seen_tiles::SeenTiles;
+use crate::settings::SavedSettings;
+use crate::window_size::WindowSize;
+
+// What we remember about the user between runs.
+// Changes are only written out by `flush`, which the RootWidget calls at most
+// once per `AppConfig::session_save_interval`, and which runs one last time
+// when the session is dropped.
+pub struct SessionState {
+    pub favorites: Favorites,
+    pub seen_tiles: SeenTiles,
+    pub window_size: WindowSize,
+    pub settings: SavedSettings,
+    // Tiles activated this session, by image URL.
+    pub visited_tiles: BTreeSet<String>,
+}
+
+// Like SharedSelection, owned by the RootWidget and cloned into every widget
+// that needs it. Thumbnails read it when they paint.
+pub type SharedSession = Rc<RefCell<SessionState>>;
+
+impl SessionState {
+    pub fn load(config: &AppConfig) -> Self {
+        Self {
+            favorites: Favorites::load(config.favorites_path.clone()),
+            seen_tiles: SeenTiles::load(config.seen_tiles_path.clone()),
+            window_size: WindowSize::load(config.window_size_path.clone()),
+            settings: SavedSettings::load(config.settings_path.clone()),
+            visited_tiles: BTreeSet::new(),
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.favorites.dirty
+            || self.seen_tiles.dirty
+            || self.window_size.dirty
+            || self.settings.dirty
+    }
+
+    // Saves whatever changed since the last flush.
+    pub fn flush(&mut self) {
+        self.favorites.flush();
+        self.seen_tiles.flush();
+        self.window_size.flush();
+        self.settings.flush();
+    }
+}
+
+impl Drop for SessionState {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
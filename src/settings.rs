@@ -0,0 +1,225 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use widget_cruncher::{theme, Color, Env, Key};
+
+use crate::paths::write_file;
+
+// How fast the selection, row and grid animations run, relative to their usual
+// speed. The Env doesn't have to set it: widgets run at 1.0 without it.
+pub const ANIMATION_SPEED: Key<f64> = Key::new("disney_streaming_clone.animation_speed");
+// How big thumbnails are drawn, relative to their usual size. Defaults to 1.0.
+pub const THUMBNAIL_SCALE: Key<f64> = Key::new("disney_streaming_clone.thumbnail_scale");
+// Painted behind the grid and the settings page.
+pub const BACKGROUND_COLOR: Key<Color> = Key::new("disney_streaming_clone.background_color");
+
+// The preferences picked on the SettingsPage. The RootWidget applies them to the
+// Env it passes down, so every widget picks up a change on its next layout and paint.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub animation_speed: AnimationSpeed,
+    pub thumbnail_size: ThumbnailSize,
+    pub theme: Theme,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnimationSpeed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailSize {
+    Small,
+    Regular,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+// The settings of the last run, so that the app looks the same next time.
+// Saved to `path` as TOML.
+pub struct SavedSettings {
+    pub path: Option<PathBuf>,
+    pub settings: Settings,
+    // Whether `settings` changed since they were last saved. See `SessionState::flush`.
+    pub dirty: bool,
+}
+
+// The animation speed `env` asks for, or 1.0 if it doesn't say.
+pub fn animation_speed(env: &Env) -> f64 {
+    env.try_get(&ANIMATION_SPEED).unwrap_or(1.0)
+}
+
+pub fn thumbnail_scale(env: &Env) -> f64 {
+    env.try_get(&THUMBNAIL_SCALE).unwrap_or(1.0)
+}
+
+// --- METHODS ---
+
+impl Settings {
+    // `env`, with these settings applied.
+    pub fn apply(&self, env: &Env) -> Env {
+        let mut env = env.clone();
+        env.set(ANIMATION_SPEED, self.animation_speed.factor());
+        env.set(THUMBNAIL_SCALE, self.thumbnail_size.scale());
+        env.set(BACKGROUND_COLOR, self.theme.background_color());
+        // Labels, like the row titles, use these. The dark theme keeps the defaults.
+        if self.theme == Theme::Light {
+            env.set(theme::TEXT_COLOR, Color::rgb8(0x1a, 0x1c, 0x24));
+            env.set(theme::DISABLED_TEXT_COLOR, Color::rgb8(0x60, 0x64, 0x70));
+        }
+        env
+    }
+}
+
+impl AnimationSpeed {
+    pub fn factor(self) -> f64 {
+        match self {
+            AnimationSpeed::Slow => 0.5,
+            AnimationSpeed::Normal => 1.0,
+            AnimationSpeed::Fast => 2.0,
+        }
+    }
+
+    // The order the settings page cycles through.
+    pub fn next(self) -> Self {
+        match self {
+            AnimationSpeed::Slow => AnimationSpeed::Normal,
+            AnimationSpeed::Normal => AnimationSpeed::Fast,
+            AnimationSpeed::Fast => AnimationSpeed::Slow,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AnimationSpeed::Slow => "Slow",
+            AnimationSpeed::Normal => "Normal",
+            AnimationSpeed::Fast => "Fast",
+        }
+    }
+}
+
+impl ThumbnailSize {
+    pub fn scale(self) -> f64 {
+        match self {
+            ThumbnailSize::Small => 0.8,
+            ThumbnailSize::Regular => 1.0,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ThumbnailSize::Small => ThumbnailSize::Regular,
+            ThumbnailSize::Regular => ThumbnailSize::Small,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThumbnailSize::Small => "Small",
+            ThumbnailSize::Regular => "Regular",
+        }
+    }
+}
+
+impl Theme {
+    pub fn background_color(self) -> Color {
+        match self {
+            Theme::Dark => Color::from_rgba32_u32(0x07_1b_0f_ff),
+            Theme::Light => Color::rgb8(0xee, 0xf0, 0xf4),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Dark,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+}
+
+impl SavedSettings {
+    // A missing file just means nothing was changed yet. A malformed one is
+    // ignored, and overwritten the next time something is.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let settings = match path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        {
+            Some(contents) => toml::from_str(&contents).unwrap_or_else(|err| {
+                tracing::warn!("Ignoring malformed settings: {}", err);
+                Settings::default()
+            }),
+            None => Settings::default(),
+        };
+        Self {
+            path,
+            settings,
+            dirty: false,
+        }
+    }
+
+    pub fn set(&mut self, settings: Settings) {
+        if self.settings != settings {
+            self.settings = settings;
+            self.dirty = true;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let result = toml::to_string(&self.settings)
+            .map_err(|err| err.to_string())
+            .and_then(|contents| write_file(path, contents).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            tracing::warn!("Failed to save settings to {}: {}", path.display(), err);
+        }
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Default for AnimationSpeed {
+    fn default() -> Self {
+        AnimationSpeed::Normal
+    }
+}
+
+impl Default for ThumbnailSize {
+    fn default() -> Self {
+        ThumbnailSize::Regular
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Dark
+    }
+}
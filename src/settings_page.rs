@@ -0,0 +1,153 @@
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::AsWidgetPod;
+use widget_cruncher::{theme, Color, Point, Rect, Selector};
+
+use crate::keymap::Action;
+use crate::settings::{Settings, BACKGROUND_COLOR};
+
+// Sent by the RootWidget for every key pressed while the settings page is open,
+// since the page doesn't have focus. The payload is the key's action.
+pub const SETTINGS_ACTION: Selector<Action> = Selector::new("settings_action");
+
+// Sent by the settings page every time a setting changes. The RootWidget saves
+// them, and applies them to the Env right away.
+pub const SETTINGS_CHANGED: Selector<Settings> = Selector::new("settings_changed");
+
+const PADDING: f64 = 48.0;
+const LINE_HEIGHT: f64 = 44.0;
+const SELECTED_COLOR: Color = Color::rgb8(0x4d, 0xa3, 0xff);
+
+// The lines of the page, top to bottom.
+const SETTING_COUNT: usize = 3;
+
+// Preferences for the whole app, one per line: Up and Down pick a line, and Left,
+// Right or Activate change its value. Changes apply as soon as they're made.
+pub struct SettingsPage {
+    pub settings: Settings,
+    // The selected line.
+    pub selected: usize,
+}
+
+// --- METHODS ---
+
+impl SettingsPage {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            settings,
+            selected: 0,
+        }
+    }
+
+    fn on_action(&mut self, ctx: &mut EventCtx, action: Action) {
+        match action {
+            Action::Up => self.selected = self.selected.saturating_sub(1),
+            Action::Down => self.selected = (self.selected + 1).min(SETTING_COUNT - 1),
+            // Every setting only has a couple of values, so both ways cycle forward.
+            Action::Left | Action::Right | Action::Activate => {
+                self.cycle_selected();
+                ctx.submit_command(SETTINGS_CHANGED.with(self.settings));
+            }
+            _ => return,
+        }
+        ctx.request_paint();
+    }
+
+    fn cycle_selected(&mut self) {
+        let settings = &mut self.settings;
+        match self.selected {
+            0 => settings.animation_speed = settings.animation_speed.next(),
+            1 => settings.thumbnail_size = settings.thumbnail_size.next(),
+            _ => settings.theme = settings.theme.next(),
+        }
+    }
+
+    // As (name, value).
+    fn lines(&self) -> [(&'static str, &'static str); SETTING_COUNT] {
+        [
+            ("Animation speed", self.settings.animation_speed.label()),
+            ("Thumbnail size", self.settings.thumbnail_size.label()),
+            ("Theme", self.settings.theme.label()),
+        ]
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for SettingsPage {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
+        ctx.init();
+        if let Event::Command(command) = event {
+            if let Some(action) = command.try_get(SETTINGS_ACTION) {
+                self.on_action(ctx, *action);
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let rect = ctx.size().to_rect();
+        let background = env.try_get(&BACKGROUND_COLOR).unwrap_or(Color::BLACK);
+        let text_color = env.get(&theme::TEXT_COLOR);
+        ctx.fill(rect, &background);
+
+        let title_text = ctx
+            .text()
+            .new_text_layout("Settings")
+            .font(FontFamily::SYSTEM_UI, 28.0)
+            .text_color(text_color.clone())
+            .build()
+            .unwrap();
+        ctx.draw_text(&title_text, Point::new(PADDING, PADDING));
+
+        let mut y = PADDING + title_text.size().height + PADDING / 2.0;
+        let value_x = rect.width() / 2.0;
+        for (index, (name, value)) in self.lines().iter().enumerate() {
+            if index == self.selected {
+                let line_rect = Rect::new(
+                    PADDING / 2.0,
+                    y,
+                    rect.width() - PADDING / 2.0,
+                    y + LINE_HEIGHT,
+                );
+                ctx.stroke(line_rect.to_rounded_rect(4.0), &SELECTED_COLOR, 2.0);
+            }
+            for (text, x) in [(*name, PADDING), (*value, value_x)] {
+                let text = ctx
+                    .text()
+                    .new_text_layout(text)
+                    .font(FontFamily::SYSTEM_UI, 18.0)
+                    .text_color(text_color.clone())
+                    .build()
+                    .unwrap();
+                let text_y = y + (LINE_HEIGHT - text.size().height) / 2.0;
+                ctx.draw_text(&text, Point::new(x, text_y));
+            }
+            y += LINE_HEIGHT;
+        }
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("SettingsPage")
+    }
+}
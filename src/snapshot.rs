@@ -0,0 +1,34 @@
+use crate::content_set::{LoadPhase, TileMetadata};
+
+// A read-only copy of what the grid currently knows about the catalog.
+// The state it aggregates is otherwise spread across the RootWidget and every
+// ContentSet; this is meant for debug windows, or any UI outside the widget tree.
+#[derive(Clone, Debug, Default)]
+pub struct CatalogSnapshot {
+    pub load_generation: u64,
+    // Empty until the collection has loaded.
+    pub rows: Vec<RowSnapshot>,
+    // None if nothing is highlighted yet.
+    pub selected_item: Option<(usize, usize)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RowSnapshot {
+    pub title: String,
+    pub ref_id: String,
+    pub phase: LoadPhase,
+    // Every tile loaded so far, across all pages.
+    pub tiles: Vec<TileMetadata>,
+    // Whether every page has loaded, so that `tiles` is the whole row.
+    pub complete: bool,
+}
+
+impl CatalogSnapshot {
+    // The first row after `from` that hasn't loaded yet, wrapping around to the top.
+    pub fn next_unloaded_row(&self, from: usize) -> Option<usize> {
+        let row_count = self.rows.len();
+        (1..=row_count)
+            .map(|step| (from + step) % row_count)
+            .find(|&row| self.rows[row].phase != LoadPhase::Loaded)
+    }
+}
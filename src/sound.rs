@@ -0,0 +1,16 @@
+// Hook for UI sound effects, eg the click of a TV remote. The grid doesn't
+// play any audio itself; deployments that want sounds plug in a handler with
+// `RootWidget::with_sound_handler`.
+// Both methods default to doing nothing.
+pub trait SoundHandler {
+    // The selection or keyboard focus moved.
+    fn on_navigate(&mut self) {}
+
+    // The selected tile was activated.
+    fn on_activate(&mut self) {}
+}
+
+// The handler used until one is set.
+pub struct Silent;
+
+impl SoundHandler for Silent {}
@@ -0,0 +1,12 @@
+use crate::sound::Silent;
+
+// Hook for screen reader style announcements. The grid doesn't speak itself;
+// deployments plug a platform text-to-speech engine in with
+// `RootWidget::with_speech_handler`.
+pub trait SpeechHandler {
+    // The selection settled on a tile, described like "Moana, 3 of 10 in Trending".
+    // Selections the user only passes through aren't announced.
+    fn speak(&mut self, _text: &str) {}
+}
+
+impl SpeechHandler for Silent {}
@@ -0,0 +1,458 @@
+// Shared by the tests of every module: a local HTTP server to serve feeds from,
+// JSON fixtures in the shape of the CDN's, and a few helpers to drive a RootWidget
+// in the framework's test harness.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use image::{DynamicImage, ImageOutputFormat, RgbaImage};
+use serde_json::{json, Value};
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use widget_cruncher::shell::keyboard_types::Key;
+use widget_cruncher::testing::Harness;
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{AsWidgetPod, WidgetPod};
+use widget_cruncher::{
+    Color, Command, ImageBuf, KeyEvent, Modifiers, MouseButton, MouseButtons, MouseEvent, Point,
+    Rect, Selector, Vec2,
+};
+
+use crate::api;
+use crate::config::{AppConfig, LoadMode};
+use crate::content_set::{parse_tile, TileMetadata};
+use crate::root_widget::RootWidget;
+
+pub const WINDOW_SIZE: Size = Size::new(1280.0, 720.0);
+
+// The collection id the fixtures are served under. See `AppConfig::set_api`.
+const COLLECTION_ID: &str = "collection";
+
+// Serves canned documents over HTTP on a local port, until the test process exits.
+// Paths that weren't given a document get a 404.
+pub struct MockServer {
+    pub base_url: String,
+    documents: Arc<Mutex<HashMap<String, (u16, Vec<u8>)>>>,
+    // Responses served once each, in order, before the path's document.
+    queued: Arc<Mutex<HashMap<String, VecDeque<QueuedResponse>>>>,
+    // How long the server waits before answering each path, for the slow ones.
+    delays: Arc<Mutex<HashMap<String, Duration>>>,
+    // Every path requested so far, in order.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind the mock server");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let documents = Arc::new(Mutex::new(HashMap::new()));
+        let queued = Arc::new(Mutex::new(HashMap::new()));
+        let delays = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let server_documents = documents.clone();
+        let server_queued = queued.clone();
+        let server_delays = delays.clone();
+        let server_requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let documents = server_documents.clone();
+                let queued = server_queued.clone();
+                let delays = server_delays.clone();
+                let requests = server_requests.clone();
+                thread::spawn(move || serve(stream, &documents, &queued, &delays, &requests));
+            }
+        });
+        Self {
+            base_url,
+            documents,
+            queued,
+            delays,
+            requests,
+        }
+    }
+
+    // Serves `body` at `path`, with a 200.
+    pub fn add(&self, path: &str, body: impl Into<Vec<u8>>) {
+        self.add_with_status(path, 200, body);
+    }
+
+    pub fn add_with_status(&self, path: &str, status: u16, body: impl Into<Vec<u8>>) {
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (status, body.into()));
+    }
+
+    // Answers the next request for `path` with `status`, the extra `headers` and
+    // `body`, instead of its document. Requests after that get the document again,
+    // or the next queued response.
+    pub fn add_once(
+        &self,
+        path: &str,
+        status: u16,
+        headers: &[(&str, &str)],
+        body: impl Into<Vec<u8>>,
+    ) {
+        let response = QueuedResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                .collect(),
+            body: body.into(),
+        };
+        self.queued
+            .lock()
+            .unwrap()
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    // Makes the server wait `delay` before answering requests for `path`.
+    pub fn delay(&self, path: &str, delay: Duration) {
+        self.delays.lock().unwrap().insert(path.to_string(), delay);
+    }
+
+    // Serves a home collection with a row per (ref id, title), and a set of
+    // `tiles_per_row` tiles for each of them.
+    pub fn add_catalog(&self, rows: &[(&str, &str)], tiles_per_row: usize) {
+        self.add(&home_path(), home_json(rows).to_string());
+        for (ref_id, title) in rows {
+            let titles: Vec<String> = (0..tiles_per_row)
+                .map(|column| format!("{} {}", title, column))
+                .collect();
+            self.add(&set_path(ref_id), set_json(&titles).to_string());
+        }
+    }
+
+    // Every path requested so far, in the order the requests came in.
+    pub fn requested_paths(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // How many times `path` was requested.
+    pub fn request_count(&self, path: &str) -> usize {
+        let requests = self.requests.lock().unwrap();
+        requests.iter().filter(|request| *request == path).count()
+    }
+}
+
+// Answers a single request. Connections are closed after every response, so
+// that the client doesn't try to reuse them.
+struct QueuedResponse {
+    status: u16,
+    // Header lines, each ending with CRLF.
+    headers: String,
+    body: Vec<u8>,
+}
+
+fn serve(
+    stream: TcpStream,
+    documents: &Mutex<HashMap<String, (u16, Vec<u8>)>>,
+    queued: &Mutex<HashMap<String, VecDeque<QueuedResponse>>>,
+    delays: &Mutex<HashMap<String, Duration>>,
+    requests: &Mutex<Vec<String>>,
+) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    // Skip the headers; nothing we serve depends on them.
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if header.trim().is_empty() => break,
+            Ok(_) => {}
+        }
+    }
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    requests.lock().unwrap().push(path.clone());
+    let delay = delays.lock().unwrap().get(&path).copied();
+    if let Some(delay) = delay {
+        thread::sleep(delay);
+    }
+    let queued_response = queued
+        .lock()
+        .unwrap()
+        .get_mut(&path)
+        .and_then(VecDeque::pop_front);
+    let (status, headers, body) = match queued_response {
+        Some(response) => (response.status, response.headers, response.body),
+        None => {
+            let (status, body) = documents
+                .lock()
+                .unwrap()
+                .get(&path)
+                .cloned()
+                .unwrap_or_else(|| (404, b"{}".to_vec()));
+            (status, String::new(), body)
+        }
+    };
+    let head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+        status,
+        body.len(),
+        headers
+    );
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(&body);
+}
+
+pub fn home_path() -> String {
+    format!("/{}/home.json", COLLECTION_ID)
+}
+
+pub fn set_path(ref_id: &str) -> String {
+    format!("/{}/sets/{}.json", COLLECTION_ID, ref_id)
+}
+
+pub fn search_path(query: &str) -> String {
+    format!("/{}/search/{}.json", COLLECTION_ID, query)
+}
+
+// A piece of text of the feed, for items of `kind`.
+fn text_field(kind: &str, content: &str) -> Value {
+    json!({ "full": { kind: { "default": { "content": content } } } })
+}
+
+// A home collection with a SetRef container per (ref id, title).
+pub fn home_json(rows: &[(&str, &str)]) -> Value {
+    let containers: Vec<Value> = rows
+        .iter()
+        .map(|(ref_id, title)| {
+            json!({
+                "set": {
+                    "refId": ref_id,
+                    "refType": "CuratedSet",
+                    "type": "SetRef",
+                    "text": { "title": text_field("set", title) }
+                }
+            })
+        })
+        .collect();
+    json!({ "data": { "StandardCollection": { "containers": containers } } })
+}
+
+// A set document whose single page is a tile per title.
+pub fn set_json(titles: &[String]) -> Value {
+    let items: Vec<Value> = titles.iter().map(|title| tile_json(title)).collect();
+    json!({
+        "data": {
+            "CuratedSet": {
+                "items": items,
+                "meta": { "hits": titles.len(), "offset": 0, "page_size": titles.len() }
+            }
+        }
+    })
+}
+
+pub fn tile_json(title: &str) -> Value {
+    json!({
+        "type": "DmcVideo",
+        "contentId": title,
+        "text": { "title": text_field("program", title) },
+        "image": {
+            "tile": {
+                "1.78": {
+                    "program": {
+                        "default": {
+                            "url": format!("https://images.invalid/{}.jpg", title),
+                            "masterWidth": 500,
+                            "masterHeight": 281
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+pub fn tile_metadata(title: &str) -> TileMetadata {
+    let item: api::Tile = serde_json::from_value(tile_json(title)).unwrap();
+    parse_tile(&item).expect("the fixture tile has no image")
+}
+
+// A gray PNG of the given size, for the server to serve as a tile's image.
+pub fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        width,
+        height,
+        image::Rgba([0x80, 0x80, 0x80, 0xff]),
+    ));
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .unwrap();
+    bytes
+}
+
+// The default config, without anything saved to or loaded from the disk.
+pub fn offline_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.favorites_path = None;
+    config.seen_tiles_path = None;
+    config.window_size_path = None;
+    config.settings_path = None;
+    config.image_cache_dir = None;
+    config
+}
+
+// A config that loads the fixtures of `server`, on the UI thread, and doesn't
+// touch the disk. Tiles are title cards, so no image is ever fetched.
+pub fn test_config(server: &MockServer) -> AppConfig {
+    let mut config = offline_config();
+    config.set_api(&server.base_url, COLLECTION_ID);
+    config.load_mode = LoadMode::Synchronous;
+    config.lazy_load_margin = None;
+    config.text_only = true;
+    config.reduce_motion = true;
+    config.spinner_delay = None;
+    config.session_save_interval = None;
+    config
+}
+
+pub fn harness(root: RootWidget) -> Harness {
+    let mut harness = Harness::create_with_size(root, WINDOW_SIZE);
+    // So that the rows are laid out, and the hit map filled in.
+    let _ = harness.render();
+    harness
+}
+
+pub fn press(harness: &mut Harness, key: Key) {
+    let event = KeyEvent::for_test(Modifiers::empty(), key);
+    harness.process_event(Event::KeyDown(event));
+    let _ = harness.render();
+}
+
+// Runs `f` with the RootWidget at the root of `harness`.
+pub fn with_root<R>(harness: &Harness, f: impl FnOnce(&RootWidget) -> R) -> R {
+    let root = harness.root_widget();
+    let root_widget = root
+        .downcast::<RootWidget>()
+        .expect("the root isn't a RootWidget");
+    f(&*root_widget)
+}
+
+pub fn press_char(harness: &mut Harness, character: &str) {
+    press(harness, Key::Character(character.to_string()));
+}
+
+// A left click at `pos`, in window coordinates.
+pub fn click(harness: &mut Harness, pos: Point) {
+    let mouse = MouseEvent {
+        pos,
+        window_pos: pos,
+        buttons: MouseButtons::new().with(MouseButton::Left),
+        mods: Modifiers::empty(),
+        count: 1,
+        focus: false,
+        button: MouseButton::Left,
+        wheel_delta: Vec2::ZERO,
+    };
+    harness.process_event(Event::MouseDown(mouse.clone()));
+    let mouse = MouseEvent {
+        buttons: MouseButtons::new(),
+        count: 0,
+        ..mouse
+    };
+    harness.process_event(Event::MouseUp(mouse));
+    let _ = harness.render();
+}
+
+// Whether some pixel of `image` inside `area` is `color`, give or take what
+// antialiasing blends into thin lines.
+pub fn paints_color(image: &ImageBuf, area: Rect, color: Color) -> bool {
+    const TOLERANCE: i32 = 0x40;
+    let (red, green, blue, _) = color.as_rgba8();
+    image.pixel_colors().enumerate().any(|(y, row)| {
+        row.enumerate().any(|(x, pixel)| {
+            let (pixel_red, pixel_green, pixel_blue, _) = pixel.as_rgba8();
+            area.contains(Point::new(x as f64 + 0.5, y as f64 + 0.5))
+                && [(red, pixel_red), (green, pixel_green), (blue, pixel_blue)]
+                    .iter()
+                    .all(|(a, b)| (*a as i32 - *b as i32).abs() <= TOLERANCE)
+        })
+    })
+}
+
+// Every command that went through a CommandLog, in order.
+pub type SentCommands = Rc<RefCell<Vec<Command>>>;
+
+// Wraps the widget under test, and keeps the commands that go through it, like
+// the ones a RootWidget sends for the app embedding it.
+pub struct CommandLog<W> {
+    pub inner: WidgetPod<W>,
+    pub commands: SentCommands,
+}
+
+impl<W: Widget> CommandLog<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: WidgetPod::new(inner),
+            commands: SentCommands::default(),
+        }
+    }
+}
+
+// The payloads of the commands with `selector` in `commands`, in order.
+pub fn sent<T: Clone + 'static>(commands: &SentCommands, selector: Selector<T>) -> Vec<T> {
+    commands
+        .borrow()
+        .iter()
+        .filter_map(|command| command.try_get(selector).cloned())
+        .collect()
+}
+
+impl<W: Widget> Widget for CommandLog<W> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        if let Event::Command(command) = event {
+            self.commands.borrow_mut().push(command.clone());
+        }
+        self.inner.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.init();
+        self.inner.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.inner.layout(ctx, bc, env);
+        self.inner.set_origin(ctx, env, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.inner.paint(ctx, env);
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        smallvec![&self.inner as &dyn AsWidgetPod]
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        smallvec![&mut self.inner as &mut dyn AsWidgetPod]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("CommandLog")
+    }
+}
@@ -1,35 +1,490 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
 use smallvec::{smallvec, SmallVec};
 use tracing::{trace_span, Span};
 
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
 use widget_cruncher::widget::prelude::*;
-use widget_cruncher::widget::{AsWidgetPod, WebImage, WidgetPod};
-use widget_cruncher::{Color, Selector};
+use widget_cruncher::widget::{AsWidgetPod, Label, LineBreaking, WidgetPod};
+use widget_cruncher::{Color, Insets, Key, Point, Rect, Selector, Size, TimerToken};
+
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, Corner, SelectionStyle};
+use crate::content_set::{LiveAiring, TileBrand, TileMetadata};
+use crate::session::SharedSession;
+use crate::settings::{animation_speed, thumbnail_scale};
+use crate::tile_image::{TileImage, IMAGE_DIMENSIONS};
+use crate::title_card::TitleCard;
 
-pub const CHANGE_SELECTED_ITEM: Selector<(usize, usize)> = Selector::new("change_selected_item");
+// Sent when the selection changes. Receivers read the new selection from their
+// SharedSelection, so that if several of these are sent in the same frame, they
+// all agree on which tile is selected.
+pub const CHANGE_SELECTED_ITEM: Selector = Selector::new("change_selected_item");
+pub const CHANGE_FOCUS: Selector<FocusTarget> = Selector::new("change_focus");
+// Sent when a live row has refreshed what's airing. The payload is the row,
+// and the current program title of each of its tiles, by column.
+pub const UPDATE_LIVE_PROGRAMS: Selector<(usize, Vec<Option<String>>)> =
+    Selector::new("update_live_programs");
+// Sent by a Thumbnail when it's clicked, with its (row, column). The RootWidget
+// decides whether that selects or activates it.
+pub const CLICK_TILE: Selector<(usize, usize)> = Selector::new("click_tile");
 pub const THUMBNAIL_MAX_SIZE: f64 = 200.0;
 
+// How wide and tall the cell of every thumbnail is, once the thumbnail size
+// setting is applied. Rows and the search page space their tiles by this.
+pub fn cell_size(env: &Env) -> f64 {
+    THUMBNAIL_MAX_SIZE * thumbnail_scale(env)
+}
+
+// Unselected tiles are drawn at BASE_SCALE of their cell; the selected tile grows
+// to SELECTED_SCALE, which is bigger than the cell, so it pops out over its neighbors.
+// These are the defaults; see `Thumbnail::with_scale`.
+pub const BASE_SCALE: f64 = 0.90;
+pub const SELECTED_SCALE: f64 = 1.10;
+
+// How long it takes to go from unselected to selected.
+const SELECTION_ANIMATION_DURATION: Duration = Duration::from_millis(80);
+
+// How far a fully selected tile paints past each side of its cell.
+// Containers need to leave this much room around their tiles so it doesn't get clipped.
+pub const THUMBNAIL_OVERFLOW: f64 = THUMBNAIL_MAX_SIZE * (SELECTED_SCALE - 1.0) / 2.0;
+
+// The keyboard focus outline is deliberately thinner and a different color than
+// the selection border, so the two can be told apart when they're on different items.
+pub const FOCUS_OUTLINE_WIDTH: f64 = 1.5;
+pub const FOCUS_OUTLINE_COLOR: Color = Color::rgb8(0x4d, 0xa3, 0xff);
+
+// The halo of SelectionStyle::glow. It's drawn inside THUMBNAIL_OVERFLOW, so
+// it isn't clipped by rows.
+const GLOW_RADIUS: f64 = THUMBNAIL_OVERFLOW / 2.0;
+const GLOW_COLOR: Color = Color::rgb8(0xff, 0xff, 0xff);
+
+// How the brand chip is drawn. The Env doesn't have to set them: these default
+// to a dark translucent chip with white text, like the other badges.
+pub const BRAND_CHIP_BACKGROUND: Key<Color> =
+    Key::new("disney_streaming_clone.brand_chip_background");
+pub const BRAND_CHIP_TEXT_COLOR: Key<Color> =
+    Key::new("disney_streaming_clone.brand_chip_text_color");
+const DEFAULT_BRAND_CHIP_BACKGROUND: Color = Color::rgba8(0, 0, 0, 0xb0);
+
+// The background of the "LIVE" badge of live channels.
+pub const LIVE_BADGE_COLOR: Color = Color::rgb8(0xe5, 0x09, 0x14);
+// The background of the "NEW" badge of tiles added since the last visit.
+pub const NEW_BADGE_COLOR: Color = Color::rgb8(0x1f, 0x80, 0xe0);
+
+// Drawn over the image of the tile under the mouse, unless it's selected.
+const HOVER_COLOR: Color = Color::rgba8(0xff, 0xff, 0xff, 0x30);
+
+// Space between the image and the caption under it.
+const CAPTION_SPACING: f64 = 4.0;
+const CAPTION_TEXT_SIZE: f64 = 12.0;
+
+// Brand logos are fit in a box this size, in the chip's corner.
+const BRAND_LOGO_SIZE: Size = Size::new(64.0, 24.0);
+const BRAND_CHIP_PADDING: f64 = 4.0;
+
+// The selected tile, as (row, column). Only the RootWidget writes it; every widget
+// that needs to know about the selection holds a clone. None while the selection is hidden.
+pub type SharedSelection = Rc<Cell<Option<(usize, usize)>>>;
+
+// Where keyboard focus is. This is tracked separately from the selected tile:
+// focus can move to a row header while the selection stays where it was.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FocusTarget {
+    Tile(usize, usize),
+    // (row, index). Index 0 is the row's title, and the following ones are its
+    // HEADER_CONTROLS.
+    RowHeader(usize, usize),
+}
+
 pub struct Thumbnail {
     // We store which row and column this is in, to handle arrow selection "manually"
     pub row: usize,
     pub column: usize,
 
-    // An image loaded from a URL, with a spinner placeholder
-    pub inner: WidgetPod<WebImage>,
+    // An image loaded from a URL, with a spinner placeholder.
+    // In text-only mode, a TitleCard instead.
+    pub inner: WidgetPod<Box<dyn Widget>>,
 
-    // Animation state for the "selected" animation
+    // For live channels, what's airing now. Drawn as a "LIVE" badge and a caption.
+    pub live: Option<LiveAiring>,
+
+    // Width / height of the image. It's drawn as large as fits in the cell.
+    // From the feed, or `default_tile_aspect_ratio`, until the image has decoded
+    // and we know its actual size.
+    pub aspect_ratio: f64,
+
+    // Favorite tiles get a heart badge, and tiles added since the last visit
+    // get a "NEW" badge.
+    pub image_url: String,
+    pub session: SharedSession,
+
+    // Animation state for the "selected" animation.
+    // `selected` is a cache of `selection`, updated on CHANGE_SELECTED_ITEM.
+    pub selection: SharedSelection,
     pub selected: bool,
-    pub selected_progress: u32,
+    // From 0.0 (unselected) to 1.0 (selected).
+    pub selected_progress: f64,
+    // When the last animation step ran, while the animation is running.
+    pub last_step: Option<Instant>,
+    pub clock: SharedClock,
+    // If false, the tile jumps straight to its selected or unselected size.
+    pub animate_selection: bool,
+    // Whether the tile brings itself into view when it's selected. Off with pan
+    // animations, since the RootWidget glides the grid to the selected row instead.
+    pub pan_to_selection: bool,
+    // With a frame rate cap, animation steps are driven by a timer instead of
+    // animation frames.
+    pub frame_interval: Option<Duration>,
+    pub frame_timer: TimerToken,
+
+    // Size of the image relative to the cell, when unselected and fully selected.
+    pub base_scale: f64,
+    pub selected_scale: f64,
+    pub selection_style: SelectionStyle,
+
+    // Whether this tile has keyboard focus. Drawn as a thin outline around the cell.
+    pub focused: bool,
+    // Whether the mouse is over this tile.
+    pub hovered: bool,
+
+    // Alternate artwork drawn over `inner` once the tile has stayed selected for
+    // `spotlight_dwell`. It's only added as a child the first time it's shown, so
+    // tiles that are never dwelled on don't load it, and then kept so it only loads once.
+    pub pending_preview: Option<TileImage>,
+    pub preview: Option<WidgetPod<Box<dyn Widget>>>,
+    pub showing_preview: bool,
+    pub spotlight_dwell: Option<Duration>,
+    pub spotlight_timer: TimerToken,
+
+    // Drawn as a chip with the brand's name in `brand_corner`, or as its logo if
+    // it has one. None if the tile has no brand, or brands are turned off.
+    pub brand: Option<TileBrand>,
+    pub brand_corner: Corner,
+    pub brand_logo: Option<WidgetPod<Box<dyn Widget>>>,
+
+    // The title, content type and rating, under the image. It fades in with the
+    // selection animation, and is hidden while the tile isn't selected.
+    // None if captions are turned off, or we know none of these.
+    pub caption: Option<WidgetPod<Label>>,
 }
 
 impl Thumbnail {
-    pub fn new(row: usize, column: usize, thumbnail_url: String) -> Self {
-        let image = WebImage::new(thumbnail_url);
+    pub fn new(
+        row: usize,
+        column: usize,
+        tile: TileMetadata,
+        config: &AppConfig,
+        selection: SharedSelection,
+        session: SharedSession,
+        clock: SharedClock,
+    ) -> Self {
+        let caption_lines: Vec<String> = tile
+            .title
+            .clone()
+            .into_iter()
+            .chain(tile.caption_details())
+            .collect();
+        let caption = if config.tile_captions && !caption_lines.is_empty() {
+            let label = Label::new(caption_lines.join("\n"))
+                .with_text_size(CAPTION_TEXT_SIZE)
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .with_text_color(Color::TRANSPARENT);
+            Some(WidgetPod::new(label))
+        } else {
+            None
+        };
+        let title = tile.title.unwrap_or_default();
+        let pending_preview = match (&tile.preview_image_url, config.spotlight_dwell) {
+            (Some(url), Some(_)) if !config.text_only => {
+                Some(TileImage::new(url.clone(), title.clone(), config))
+            }
+            _ => None,
+        };
+        let (brand, brand_corner) = match (tile.brand, config.brand_chip_corner) {
+            (Some(brand), Some(corner)) => (Some(brand), corner),
+            _ => (None, Corner::BottomRight),
+        };
+        let brand_logo = match &brand {
+            Some(TileBrand {
+                logo_url: Some(url),
+                name,
+            }) if !config.text_only => {
+                let logo: Box<dyn Widget> = Box::new(TileImage::new(
+                    url.clone(),
+                    name.clone().unwrap_or_default(),
+                    config,
+                ));
+                Some(WidgetPod::new(logo))
+            }
+            _ => None,
+        };
+        let inner: Box<dyn Widget> = if config.text_only {
+            Box::new(TitleCard::new(title))
+        } else {
+            Box::new(TileImage::new(tile.image_url.clone(), title, config))
+        };
         Self {
             row,
             column,
-            inner: WidgetPod::new(image),
+            inner: WidgetPod::new(inner),
+            live: tile.live,
+            image_url: tile.image_url,
+            aspect_ratio: tile
+                .image_aspect_ratio
+                .unwrap_or(config.default_tile_aspect_ratio),
+            session,
+            selection,
             selected: false,
-            selected_progress: 0,
+            selected_progress: 0.0,
+            last_step: None,
+            clock,
+            animate_selection: config.selection_animation_enabled(),
+            pan_to_selection: !config.pan_animation_enabled(),
+            frame_interval: config
+                .animation_fps
+                .map(|fps| Duration::from_secs_f64(1.0 / fps.max(1) as f64)),
+            frame_timer: TimerToken::INVALID,
+            base_scale: BASE_SCALE,
+            selected_scale: SELECTED_SCALE,
+            selection_style: config.selection_style,
+            focused: false,
+            hovered: false,
+            pending_preview,
+            preview: None,
+            showing_preview: false,
+            spotlight_dwell: config.spotlight_dwell,
+            spotlight_timer: TimerToken::INVALID,
+            brand,
+            brand_corner,
+            brand_logo,
+            caption,
+        }
+    }
+
+    // Changes how big the tile is drawn when unselected and selected.
+    // A selected scale above SELECTED_SCALE paints past THUMBNAIL_OVERFLOW, so
+    // the container needs to leave more room than usual around the tile.
+    pub fn with_scale(mut self, base_scale: f64, selected_scale: f64) -> Self {
+        self.base_scale = base_scale;
+        self.selected_scale = selected_scale;
+        self
+    }
+
+    fn request_animation_step(&mut self, ctx: &mut EventCtx) {
+        match self.frame_interval {
+            Some(frame_interval) => {
+                if self.frame_timer == TimerToken::INVALID {
+                    self.frame_timer = ctx.request_timer(frame_interval);
+                }
+            }
+            None => ctx.request_anim_frame(),
+        }
+    }
+
+    // Called when `selected` changes.
+    fn start_selection_animation(&mut self, ctx: &mut EventCtx) {
+        if self.animate_selection {
+            // If the animation is already running, it just changes direction.
+            if self.last_step.is_none() {
+                self.last_step = Some(self.clock.now());
+                self.request_animation_step(ctx);
+            }
+        } else {
+            self.selected_progress = if self.selected { 1.0 } else { 0.0 };
+        }
+    }
+
+    // Advances the animation by however much time passed since the last step,
+    // so it lasts as long whatever the frame rate. `speed` comes from the Env's
+    // ANIMATION_SPEED.
+    // Once the tile is fully selected or unselected, this stops requesting steps,
+    // so an idle grid doesn't draw any frames.
+    fn animation_step(&mut self, ctx: &mut EventCtx, speed: f64) {
+        let last_step = match self.last_step {
+            Some(last_step) => last_step,
+            None => return,
+        };
+        let now = self.clock.now();
+        let delta = now.saturating_duration_since(last_step).as_secs_f64() * speed
+            / SELECTION_ANIMATION_DURATION.as_secs_f64();
+        let (progress, target) = if self.selected {
+            ((self.selected_progress + delta).min(1.0), 1.0)
+        } else {
+            ((self.selected_progress - delta).max(0.0), 0.0)
+        };
+        self.selected_progress = progress;
+        ctx.request_layout();
+        if progress == target {
+            self.last_step = None;
+        } else {
+            self.last_step = Some(now);
+            self.request_animation_step(ctx);
+        }
+    }
+
+    fn has_preview(&self) -> bool {
+        self.pending_preview.is_some() || self.preview.is_some()
+    }
+
+    fn show_preview(&mut self, ctx: &mut EventCtx) {
+        if let Some(image) = self.pending_preview.take() {
+            let image: Box<dyn Widget> = Box::new(image);
+            self.preview = Some(WidgetPod::new(image));
+            ctx.children_changed();
+        }
+        self.showing_preview = true;
+        ctx.request_layout();
+    }
+
+    // The side of the image for the current animation state.
+    pub fn image_side(&self) -> f64 {
+        if !self.selection_style.scale {
+            return THUMBNAIL_MAX_SIZE * self.base_scale;
+        }
+        // We essentially do a linear interpolation
+        // between "90% of max size" and "110% of max size" (by default)
+        let scale =
+            self.base_scale + (self.selected_scale - self.base_scale) * self.selected_progress;
+        THUMBNAIL_MAX_SIZE * scale
+    }
+
+    // Used when a Thumbnail is recreated for a tile that was already selected
+    // or focused, so it doesn't replay the selection animation.
+    pub fn with_state(mut self, focused: bool) -> Self {
+        let selected = self.selection.get() == Some((self.row, self.column));
+        self.selected = selected;
+        self.selected_progress = if selected { 1.0 } else { 0.0 };
+        self.focused = focused;
+        self
+    }
+
+    fn paint_favorite_badge(&self, ctx: &mut PaintCtx) {
+        const BADGE_COLOR: Color = Color::rgba8(0, 0, 0, 0xb0);
+        const HEART_COLOR: Color = Color::rgb8(0xff, 0x4d, 0x6d);
+        const PADDING: f64 = 4.0;
+
+        let image_rect = self.inner.layout_rect();
+
+        let heart_text = ctx
+            .text()
+            .new_text_layout("\u{2665}")
+            .font(FontFamily::SYSTEM_UI, 14.0)
+            .text_color(HEART_COLOR)
+            .build()
+            .unwrap();
+        let heart_size = heart_text.size();
+        let heart_origin = Point::new(
+            image_rect.x1 - PADDING - heart_size.width,
+            image_rect.y0 + PADDING,
+        );
+        let badge_rect = Rect::from_origin_size(heart_origin, heart_size)
+            .inset(PADDING / 2.0)
+            .to_rounded_rect(heart_size.height);
+        ctx.fill(badge_rect, &BADGE_COLOR);
+        ctx.draw_text(&heart_text, heart_origin);
+    }
+
+    // Drawn where the "LIVE" badge would be, so live tiles don't get it.
+    fn paint_new_badge(&self, ctx: &mut PaintCtx) {
+        const PADDING: f64 = 4.0;
+
+        let image_rect = self.inner.layout_rect();
+
+        let badge_text = ctx
+            .text()
+            .new_text_layout("NEW")
+            .font(FontFamily::SYSTEM_UI, 11.0)
+            .text_color(Color::WHITE)
+            .build()
+            .unwrap();
+        let badge_origin = Point::new(image_rect.x0 + PADDING, image_rect.y0 + PADDING);
+        let badge_rect = Rect::from_origin_size(badge_origin, badge_text.size())
+            .inset(PADDING / 2.0)
+            .to_rounded_rect(2.0);
+        ctx.fill(badge_rect, &NEW_BADGE_COLOR);
+        ctx.draw_text(&badge_text, badge_origin);
+    }
+
+    // Where something of `size` goes in `brand_corner` of the image.
+    fn brand_chip_origin(&self, size: Size) -> Point {
+        let image_rect = self.inner.layout_rect().inset(-BRAND_CHIP_PADDING);
+        let x = match self.brand_corner {
+            Corner::TopLeft | Corner::BottomLeft => image_rect.x0,
+            Corner::TopRight | Corner::BottomRight => image_rect.x1 - size.width,
+        };
+        let y = match self.brand_corner {
+            Corner::TopLeft | Corner::TopRight => image_rect.y0,
+            Corner::BottomLeft | Corner::BottomRight => image_rect.y1 - size.height,
+        };
+        Point::new(x, y)
+    }
+
+    // Brands with a logo are drawn by `brand_logo` instead.
+    fn paint_brand_chip(&self, ctx: &mut PaintCtx, name: &str, env: &Env) {
+        let background = env
+            .try_get(&BRAND_CHIP_BACKGROUND)
+            .unwrap_or(DEFAULT_BRAND_CHIP_BACKGROUND);
+        let text_color = env.try_get(&BRAND_CHIP_TEXT_COLOR).unwrap_or(Color::WHITE);
+
+        let chip_text = ctx
+            .text()
+            .new_text_layout(name.to_string())
+            .font(FontFamily::SYSTEM_UI, 11.0)
+            .text_color(text_color)
+            .build()
+            .unwrap();
+        let chip_origin = self.brand_chip_origin(chip_text.size());
+        let chip_rect = Rect::from_origin_size(chip_origin, chip_text.size())
+            .inset(BRAND_CHIP_PADDING / 2.0)
+            .to_rounded_rect(2.0);
+        ctx.fill(chip_rect, &background);
+        ctx.draw_text(&chip_text, chip_origin);
+    }
+
+    fn paint_live_badge(&self, ctx: &mut PaintCtx, live: &LiveAiring) {
+        const CAPTION_BACKGROUND: Color = Color::rgba8(0, 0, 0, 0xb0);
+        const PADDING: f64 = 4.0;
+
+        let image_rect = self.inner.layout_rect();
+
+        let badge_text = ctx
+            .text()
+            .new_text_layout("LIVE")
+            .font(FontFamily::SYSTEM_UI, 11.0)
+            .text_color(Color::WHITE)
+            .build()
+            .unwrap();
+        let badge_origin = Point::new(image_rect.x0 + PADDING, image_rect.y0 + PADDING);
+        let badge_rect = Rect::from_origin_size(badge_origin, badge_text.size())
+            .inset(PADDING / 2.0)
+            .to_rounded_rect(2.0);
+        ctx.fill(badge_rect, &LIVE_BADGE_COLOR);
+        ctx.draw_text(&badge_text, badge_origin);
+
+        if let Some(program_title) = &live.program_title {
+            let caption_text = ctx
+                .text()
+                .new_text_layout(program_title.clone())
+                .font(FontFamily::SYSTEM_UI, 12.0)
+                .text_color(Color::WHITE)
+                .max_width(image_rect.width() - 2.0 * PADDING)
+                .build()
+                .unwrap();
+            let caption_height = caption_text.size().height + 2.0 * PADDING;
+            let caption_rect = Rect::new(
+                image_rect.x0,
+                image_rect.y1 - caption_height,
+                image_rect.x1,
+                image_rect.y1,
+            );
+            ctx.fill(caption_rect, &CAPTION_BACKGROUND);
+            ctx.draw_text(
+                &caption_text,
+                Point::new(caption_rect.x0 + PADDING, caption_rect.y0 + PADDING),
+            );
         }
     }
 }
@@ -41,79 +496,240 @@ impl Widget for Thumbnail {
         ctx.init();
         match event {
             Event::Command(command) => {
-                if let Some((row, col)) = command.try_get(CHANGE_SELECTED_ITEM) {
-                    if (*row, *col) == (self.row, self.column) {
+                if command.is(CHANGE_SELECTED_ITEM) {
+                    if self.selection.get() == Some((self.row, self.column)) {
                         self.selected = true;
-                        ctx.request_anim_frame();
+                        self.start_selection_animation(ctx);
                         ctx.request_layout();
-                        ctx.request_pan_to_this();
+                        if self.pan_to_selection {
+                            ctx.request_pan_to_this();
+                        }
+                        if let Some(dwell) = self.spotlight_dwell {
+                            if self.has_preview() {
+                                self.spotlight_timer = ctx.request_timer(dwell);
+                            }
+                        }
                     } else if self.selected {
                         self.selected = false;
-                        ctx.request_anim_frame();
+                        self.start_selection_animation(ctx);
                         ctx.request_layout();
+                        self.spotlight_timer = TimerToken::INVALID;
+                        if self.showing_preview {
+                            self.showing_preview = false;
+                            ctx.request_paint();
+                        }
                     }
                 }
-            }
-            // TODO - handle frame interval?
-            Event::AnimFrame(_interval) => {
-                if self.selected {
-                    if self.selected_progress < 5 {
-                        self.selected_progress += 1;
-                        ctx.request_anim_frame();
-                        ctx.request_layout();
+                if let Some((row, programs)) = command.try_get(UPDATE_LIVE_PROGRAMS) {
+                    if let (Some(live), Some(program_title)) =
+                        (&mut self.live, programs.get(self.column))
+                    {
+                        if *row == self.row && live.program_title != *program_title {
+                            live.program_title = program_title.clone();
+                            ctx.request_paint();
+                        }
                     }
-                } else {
-                    if self.selected_progress > 0 {
-                        self.selected_progress -= 1;
-                        ctx.request_anim_frame();
-                        ctx.request_layout();
+                }
+                if let Some((url, size)) = command.try_get(IMAGE_DIMENSIONS) {
+                    if *url == self.image_url && size.width > 0.0 && size.height > 0.0 {
+                        let aspect_ratio = size.width / size.height;
+                        if aspect_ratio != self.aspect_ratio {
+                            self.aspect_ratio = aspect_ratio;
+                            ctx.request_layout();
+                        }
                     }
                 }
+                if let Some(focus) = command.try_get(CHANGE_FOCUS) {
+                    let focused = *focus == FocusTarget::Tile(self.row, self.column);
+                    if focused != self.focused {
+                        self.focused = focused;
+                        ctx.request_paint();
+                    }
+                }
+            }
+            Event::AnimFrame(_interval) if self.frame_interval.is_none() => {
+                self.animation_step(ctx, animation_speed(env));
+            }
+            Event::Timer(token) if *token == self.frame_timer => {
+                self.frame_timer = TimerToken::INVALID;
+                self.animation_step(ctx, animation_speed(env));
+            }
+            Event::Timer(token) if *token == self.spotlight_timer => {
+                self.spotlight_timer = TimerToken::INVALID;
+                if self.selected {
+                    self.show_preview(ctx);
+                }
+            }
+            Event::MouseDown(mouse) if mouse.button.is_left() => {
+                ctx.submit_command(CLICK_TILE.with((self.row, self.column)));
+                ctx.set_handled();
             }
             _ => {}
         }
-        self.inner.on_event(ctx, event, env)
+        self.inner.on_event(ctx, event, env);
+        if let Some(preview) = &mut self.preview {
+            preview.on_event(ctx, event, env);
+        }
+        if let Some(brand_logo) = &mut self.brand_logo {
+            brand_logo.on_event(ctx, event, env);
+        }
+        if let Some(caption) = &mut self.caption {
+            caption.on_event(ctx, event, env);
+        }
     }
 
-    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::HotChanged(hot) = event {
+            self.hovered = *hot;
+            ctx.request_paint();
+        }
+    }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
-        self.inner.lifecycle(ctx, event, env)
+        self.inner.lifecycle(ctx, event, env);
+        if let Some(preview) = &mut self.preview {
+            preview.lifecycle(ctx, event, env);
+        }
+        if let Some(brand_logo) = &mut self.brand_logo {
+            brand_logo.lifecycle(ctx, event, env);
+        }
+        if let Some(caption) = &mut self.caption {
+            caption.lifecycle(ctx, event, env);
+        }
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, _bc: &BoxConstraints, env: &Env) -> Size {
-        // We essentially do a linear interpolation
-        // between "90% of max size" and "max size"
-        let square_side = THUMBNAIL_MAX_SIZE * (0.90 + (self.selected_progress as f64) / 50.0);
-        let child_constraints = BoxConstraints::new(
-            Size::new(square_side, square_side),
-            Size::new(square_side, square_side),
-        );
+        let cell_size = cell_size(env);
+        let side = self.image_side() * thumbnail_scale(env);
+        let child_size = if self.aspect_ratio >= 1.0 {
+            Size::new(side, side / self.aspect_ratio)
+        } else {
+            Size::new(side * self.aspect_ratio, side)
+        };
+        let child_constraints = BoxConstraints::tight(child_size);
 
-        let outer_size = Size::new(THUMBNAIL_MAX_SIZE, THUMBNAIL_MAX_SIZE);
+        let outer_size = Size::new(cell_size, cell_size);
         let image_size = self.inner.layout(ctx, &child_constraints, env);
         let origin = (outer_size - image_size) / 2.0;
         self.inner.set_origin(ctx, env, origin.to_vec2().to_point());
+        if let Some(preview) = &mut self.preview {
+            preview.layout(ctx, &child_constraints, env);
+            preview.set_origin(ctx, env, origin.to_vec2().to_point());
+        }
+        if let Some(brand_logo) = &mut self.brand_logo {
+            brand_logo.layout(ctx, &BoxConstraints::tight(BRAND_LOGO_SIZE), env);
+            let logo_origin = self.brand_chip_origin(BRAND_LOGO_SIZE);
+            brand_logo.set_origin(ctx, env, logo_origin);
+        }
+        if let Some(caption) = &mut self.caption {
+            // Layout runs on every step of the selection animation, which the
+            // caption fades with.
+            let color = Color::WHITE.with_alpha(self.selected_progress);
+            caption.widget_mut().set_text_color(color);
+            let caption_bc =
+                BoxConstraints::new(Size::ZERO, Size::new(image_size.width, f64::INFINITY));
+            let caption_size = caption.layout(ctx, &caption_bc, env);
+            // Square and portrait images leave no room under them, so their
+            // caption goes over their bottom edge instead.
+            let max_y = cell_size + THUMBNAIL_OVERFLOW - caption_size.height;
+            let caption_y = (origin.height + image_size.height + CAPTION_SPACING).min(max_y);
+            caption.set_origin(ctx, env, Point::new(origin.width, caption_y));
+        }
+        // The image can be bigger than our own size, which is fine as long as the
+        // framework knows about it.
+        let overflow = cell_size * (self.selected_scale.max(self.base_scale) - 1.0) / 2.0;
+        ctx.set_paint_insets(Insets::uniform(overflow.max(0.0)));
         outer_size
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if self.selection_style.glow && self.selected_progress > 0.0 {
+            let glow_color = GLOW_COLOR.with_alpha(self.selected_progress);
+            ctx.blurred_rect(self.inner.layout_rect(), GLOW_RADIUS, &glow_color);
+        }
         self.inner.paint(ctx, env);
+        // Drawn over the regular artwork, which shows through until the preview loads.
+        if let Some(preview) = &mut self.preview {
+            if self.showing_preview {
+                preview.paint(ctx, env);
+            }
+        }
 
-        if self.selected {
+        if self.hovered && !self.selected {
+            ctx.fill(self.inner.layout_rect(), &HOVER_COLOR);
+        }
+
+        if self.selected && self.selection_style.border {
             let border_width = 4.0;
             let border_color = Color::WHITE;
             let border_rect = self.inner.layout_rect();
             ctx.stroke(border_rect, &border_color, border_width);
         }
+
+        if let Some(live) = &self.live {
+            self.paint_live_badge(ctx, live);
+        }
+
+        let (favorite, new) = {
+            let session = self.session.borrow();
+            (
+                session.favorites.contains(&self.image_url),
+                session.seen_tiles.is_new(&self.image_url),
+            )
+        };
+        if favorite {
+            self.paint_favorite_badge(ctx);
+        }
+        if new && self.live.is_none() {
+            self.paint_new_badge(ctx);
+        }
+
+        if let Some(brand_logo) = &mut self.brand_logo {
+            brand_logo.paint(ctx, env);
+        } else if let Some(name) = self.brand.as_ref().and_then(|brand| brand.name.as_deref()) {
+            self.paint_brand_chip(ctx, name, env);
+        }
+
+        if let Some(caption) = &mut self.caption {
+            if self.selected_progress > 0.0 {
+                caption.paint(ctx, env);
+            }
+        }
+
+        if self.focused {
+            let outline_rect = ctx.size().to_rect().inset(-FOCUS_OUTLINE_WIDTH / 2.0);
+            ctx.stroke(outline_rect, &FOCUS_OUTLINE_COLOR, FOCUS_OUTLINE_WIDTH);
+        }
     }
 
     fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
-        smallvec![&self.inner as &dyn AsWidgetPod]
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> =
+            smallvec![&self.inner as &dyn AsWidgetPod];
+        if let Some(preview) = &self.preview {
+            children.push(preview as &dyn AsWidgetPod);
+        }
+        if let Some(brand_logo) = &self.brand_logo {
+            children.push(brand_logo as &dyn AsWidgetPod);
+        }
+        if let Some(caption) = &self.caption {
+            children.push(caption as &dyn AsWidgetPod);
+        }
+        children
     }
 
     fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
-        smallvec![&mut self.inner as &mut dyn AsWidgetPod]
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> =
+            smallvec![&mut self.inner as &mut dyn AsWidgetPod];
+        if let Some(preview) = &mut self.preview {
+            children.push(preview as &mut dyn AsWidgetPod);
+        }
+        if let Some(brand_logo) = &mut self.brand_logo {
+            children.push(brand_logo as &mut dyn AsWidgetPod);
+        }
+        if let Some(caption) = &mut self.caption {
+            children.push(caption as &mut dyn AsWidgetPod);
+        }
+        children
     }
 
     // This isn't useful for the application itself, but it makes traces more readable
@@ -122,3 +738,360 @@ impl Widget for Thumbnail {
         trace_span!("Thumbnail")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use widget_cruncher::testing::Harness;
+    use widget_cruncher::ImageBuf;
+
+    use super::*;
+    use crate::clock::{FakeClock, SystemClock};
+    use crate::session::SessionState;
+    use crate::testing::{
+        offline_config, png_bytes, test_config, tile_metadata, MockServer, WINDOW_SIZE,
+    };
+
+    fn thumbnail(config: &AppConfig) -> Thumbnail {
+        thumbnail_of(tile_metadata("Moana"), config)
+    }
+
+    fn thumbnail_of(tile: TileMetadata, config: &AppConfig) -> Thumbnail {
+        Thumbnail::new(
+            0,
+            0,
+            tile,
+            config,
+            Rc::new(Cell::new(None)),
+            Rc::new(RefCell::new(SessionState::load(config))),
+            Rc::new(SystemClock),
+        )
+    }
+
+    #[test]
+    fn with_scale_sets_both_ends_of_the_selection_animation() {
+        let mut thumbnail = thumbnail(&offline_config()).with_scale(0.5, 1.5);
+        assert_eq!(thumbnail.image_side(), THUMBNAIL_MAX_SIZE * 0.5);
+        thumbnail.selected_progress = 1.0;
+        assert_eq!(thumbnail.image_side(), THUMBNAIL_MAX_SIZE * 1.5);
+    }
+
+    #[test]
+    fn default_scale() {
+        let thumbnail = thumbnail(&offline_config());
+        assert_eq!(thumbnail.image_side(), THUMBNAIL_MAX_SIZE * BASE_SCALE);
+    }
+
+    #[test]
+    fn tiles_take_the_shape_of_their_decoded_image() {
+        let server = MockServer::start();
+        server.add("/wide.png", png_bytes(300, 100));
+        let mut config = test_config(&server);
+        config.text_only = false;
+        // The feed claims the fixture's image is 500x281.
+        let mut tile = tile_metadata("Moana");
+        tile.image_url = format!("{}/wide.png", server.base_url);
+        let mut harness = Harness::create_with_size(thumbnail_of(tile, &config), WINDOW_SIZE);
+        let _ = harness.render();
+        let _ = harness.render();
+
+        let root = harness.root_widget();
+        let thumbnail = root.downcast::<Thumbnail>().unwrap();
+        assert_eq!(thumbnail.aspect_ratio, 3.0);
+        let image_size = thumbnail.inner.layout_rect().size();
+        assert!((image_size.width / image_size.height - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn settled_animations_stop_requesting_steps() {
+        let mut config = offline_config();
+        config.animation_fps = Some(30);
+        let frame_interval = Duration::from_secs_f64(1.0 / 30.0);
+        let selection = Rc::new(Cell::new(None));
+        let clock = Rc::new(FakeClock::new());
+        let thumbnail = Thumbnail::new(
+            0,
+            0,
+            tile_metadata("Moana"),
+            &config,
+            selection.clone(),
+            Rc::new(RefCell::new(SessionState::load(&config))),
+            clock.clone(),
+        );
+        let mut harness = Harness::create_with_size(thumbnail, WINDOW_SIZE);
+        let animating = |harness: &Harness| {
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            (
+                thumbnail.last_step.is_some(),
+                thumbnail.frame_timer != TimerToken::INVALID,
+            )
+        };
+
+        selection.set(Some((0, 0)));
+        harness.submit_command(CHANGE_SELECTED_ITEM);
+        assert_eq!(animating(&harness), (true, true));
+
+        // Steps come from the timer, at most 30 times a second.
+        let mut steps = 0;
+        while animating(&harness).0 {
+            clock.advance(frame_interval);
+            harness.move_timers_forward(frame_interval);
+            steps += 1;
+            assert!(steps < 10, "the animation never settled");
+        }
+        let progress = {
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            thumbnail.selected_progress
+        };
+        assert_eq!(progress, 1.0);
+        // Settled: no timer is left to wake the tile up again.
+        assert_eq!(animating(&harness), (false, false));
+
+        // Stray frames, eg drawn for another widget, don't restart anything.
+        harness.process_event(Event::AnimFrame(frame_interval.as_nanos() as u64));
+        clock.advance(frame_interval);
+        harness.move_timers_forward(frame_interval);
+        assert_eq!(animating(&harness), (false, false));
+    }
+
+    #[test]
+    fn image_fetches_use_the_image_timeout() {
+        let server = MockServer::start();
+        let aspect_ratio = |path: &str, image_timeout| {
+            server.add(path, png_bytes(300, 100));
+            server.delay(path, Duration::from_millis(500));
+            let mut config = test_config(&server);
+            config.text_only = false;
+            config.http.retry.max_attempts = 1;
+            config.http.image_timeout = image_timeout;
+            let mut tile = tile_metadata("Moana");
+            tile.image_url = format!("{}{}", server.base_url, path);
+            let mut harness = Harness::create_with_size(thumbnail_of(tile, &config), WINDOW_SIZE);
+            let _ = harness.render();
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            thumbnail.aspect_ratio
+        };
+
+        // Timed out: the tile keeps the feed's shape, and shows its title card.
+        assert_eq!(
+            aspect_ratio("/timed_out.png", Duration::from_millis(100)),
+            500.0 / 281.0
+        );
+        assert_eq!(aspect_ratio("/loaded.png", Duration::from_secs(5)), 3.0);
+    }
+
+    #[test]
+    fn selection_and_pan_animations_toggle_independently() {
+        // (reduce_motion, selection_animation, pan_animation), and whether the
+        // selection then animates, and the pan.
+        let cases = [
+            ((false, None, None), true, true),
+            ((true, None, None), false, false),
+            ((false, Some(false), None), false, true),
+            ((false, None, Some(false)), true, false),
+            ((true, Some(true), None), true, false),
+            ((true, None, Some(true)), false, true),
+        ];
+        for (case, animates_selection, animates_pan) in cases {
+            let (reduce_motion, selection_animation, pan_animation) = case;
+            let mut config = offline_config();
+            config.reduce_motion = reduce_motion;
+            config.selection_animation = selection_animation;
+            config.pan_animation = pan_animation;
+            assert_eq!(config.selection_animation_enabled(), animates_selection);
+            assert_eq!(config.pan_animation_enabled(), animates_pan);
+
+            let selection = Rc::new(Cell::new(None));
+            let thumbnail = Thumbnail::new(
+                0,
+                0,
+                tile_metadata("Moana"),
+                &config,
+                selection.clone(),
+                Rc::new(RefCell::new(SessionState::load(&config))),
+                Rc::new(FakeClock::new()),
+            );
+            let mut harness = Harness::create_with_size(thumbnail, WINDOW_SIZE);
+            selection.set(Some((0, 0)));
+            harness.submit_command(CHANGE_SELECTED_ITEM);
+
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            // Without the selection animation, the tile pops to its selected size.
+            // The clock doesn't move, so an animated one hasn't grown yet.
+            let progress = if animates_selection { 0.0 } else { 1.0 };
+            assert_eq!(thumbnail.selected_progress, progress, "{:?}", case);
+            // With pan animations, the grid glides to the tile instead.
+            assert_eq!(thumbnail.pan_to_selection, !animates_pan, "{:?}", case);
+        }
+    }
+
+    #[test]
+    fn dwelling_on_a_tile_shows_its_preview_until_deselected() {
+        let server = MockServer::start();
+        server.add("/tile.png", png_bytes(300, 100));
+        server.add("/preview.png", png_bytes(300, 100));
+        let dwell = Duration::from_millis(800);
+        let mut config = test_config(&server);
+        config.text_only = false;
+        config.spotlight_dwell = Some(dwell);
+        let mut tile = tile_metadata("Moana");
+        tile.image_url = format!("{}/tile.png", server.base_url);
+        tile.preview_image_url = Some(format!("{}/preview.png", server.base_url));
+        let selection = Rc::new(Cell::new(None));
+        let thumbnail = Thumbnail::new(
+            0,
+            0,
+            tile,
+            &config,
+            selection.clone(),
+            Rc::new(RefCell::new(SessionState::load(&config))),
+            Rc::new(SystemClock),
+        );
+        let mut harness = Harness::create_with_size(thumbnail, WINDOW_SIZE);
+        let _ = harness.render();
+        let preview_state = |harness: &Harness| {
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            (thumbnail.preview.is_some(), thumbnail.showing_preview)
+        };
+
+        selection.set(Some((0, 0)));
+        harness.submit_command(CHANGE_SELECTED_ITEM);
+        harness.move_timers_forward(dwell / 2);
+        // The preview isn't even loaded before the dwell is over.
+        assert_eq!(preview_state(&harness), (false, false));
+
+        harness.move_timers_forward(dwell);
+        let _ = harness.render();
+        assert_eq!(preview_state(&harness), (true, true));
+
+        // Moving on brings the regular artwork back, and keeps the preview for next time.
+        selection.set(Some((0, 1)));
+        harness.submit_command(CHANGE_SELECTED_ITEM);
+        assert_eq!(preview_state(&harness), (true, false));
+    }
+
+    // The brightest channel of any pixel of `image` inside `area`.
+    fn brightest(image: &ImageBuf, area: Rect) -> u8 {
+        let mut brightest = 0;
+        for (y, row) in image.pixel_colors().enumerate() {
+            for (x, pixel) in row.enumerate() {
+                if area.contains(Point::new(x as f64 + 0.5, y as f64 + 0.5)) {
+                    let (red, green, blue, _) = pixel.as_rgba8();
+                    brightest = brightest.max(red).max(green).max(blue);
+                }
+            }
+        }
+        brightest
+    }
+
+    #[test]
+    fn glow_only_selection_paints_a_glow_without_scaling() {
+        let mut config = offline_config();
+        config.text_only = true;
+        config.reduce_motion = true;
+        config.selection_style = SelectionStyle {
+            border: false,
+            scale: false,
+            glow: true,
+        };
+        let selection = Rc::new(Cell::new(None));
+        let thumbnail = Thumbnail::new(
+            0,
+            0,
+            tile_metadata("Moana"),
+            &config,
+            selection.clone(),
+            Rc::new(RefCell::new(SessionState::load(&config))),
+            Rc::new(SystemClock),
+        );
+        let mut harness = Harness::create_with_size(thumbnail, WINDOW_SIZE);
+        let unselected = harness.render();
+        let image_rect = |harness: &Harness| {
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            thumbnail.inner.layout_rect()
+        };
+        let unselected_rect = image_rect(&harness);
+        // A thin band just above the image, where only the glow reaches.
+        let above = Rect::new(
+            unselected_rect.x0,
+            unselected_rect.y0 - 3.0,
+            unselected_rect.x1,
+            unselected_rect.y0 - 1.0,
+        );
+
+        selection.set(Some((0, 0)));
+        harness.submit_command(CHANGE_SELECTED_ITEM);
+        let selected = harness.render();
+        assert_eq!(image_rect(&harness), unselected_rect);
+        assert!(
+            brightest(&selected, above) > brightest(&unselected, above),
+            "no glow above {:?}",
+            unselected_rect
+        );
+    }
+
+    #[test]
+    fn the_brand_chip_goes_in_the_configured_corner() {
+        let mut tile = tile_metadata("Avengers");
+        tile.brand = Some(TileBrand {
+            name: Some("Marvel".to_string()),
+            logo_url: None,
+        });
+        let chip_size = Size::new(40.0, 12.0);
+        for corner in [
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ] {
+            let mut config = offline_config();
+            config.brand_chip_corner = Some(corner);
+            let mut harness =
+                Harness::create_with_size(thumbnail_of(tile.clone(), &config), WINDOW_SIZE);
+            let _ = harness.render();
+
+            let root = harness.root_widget();
+            let thumbnail = root.downcast::<Thumbnail>().unwrap();
+            let image_rect = thumbnail.inner.layout_rect();
+            let chip = Rect::from_origin_size(thumbnail.brand_chip_origin(chip_size), chip_size);
+            let center = image_rect.center();
+            let (left, top) = match corner {
+                Corner::TopLeft => (true, true),
+                Corner::TopRight => (false, true),
+                Corner::BottomLeft => (true, false),
+                Corner::BottomRight => (false, false),
+            };
+            assert_eq!(chip.x1 <= center.x, left, "{:?}: {:?}", corner, chip);
+            assert_eq!(chip.x0 >= center.x, !left, "{:?}: {:?}", corner, chip);
+            assert_eq!(chip.y1 <= center.y, top, "{:?}: {:?}", corner, chip);
+            assert_eq!(chip.y0 >= center.y, !top, "{:?}: {:?}", corner, chip);
+            // Snug in the corner, within the padding.
+            let corner_x = if left { image_rect.x0 } else { image_rect.x1 };
+            let corner_y = if top { image_rect.y0 } else { image_rect.y1 };
+            let chip_x = if left { chip.x0 } else { chip.x1 };
+            let chip_y = if top { chip.y0 } else { chip.y1 };
+            assert!(
+                (chip_x - corner_x).abs() <= BRAND_CHIP_PADDING,
+                "{:?}",
+                corner
+            );
+            assert!(
+                (chip_y - corner_y).abs() <= BRAND_CHIP_PADDING,
+                "{:?}",
+                corner
+            );
+        }
+
+        // Without a corner, brands aren't shown at all.
+        let mut config = offline_config();
+        config.brand_chip_corner = None;
+        assert_eq!(thumbnail_of(tile, &config).brand, None);
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use image::ImageFormat as FileFormat;
+use once_cell::sync::{Lazy, OnceCell};
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::ImageFormat;
+use widget_cruncher::promise::PromiseToken;
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{AsWidgetPod, Image, WidgetPod};
+use widget_cruncher::{Command, ImageBuf, Point, Selector, Target};
+
+use crate::config::{AppConfig, LoadMode};
+use crate::image_cache::{ImageCache, DECODED_IMAGES};
+use crate::net::{fetch_bytes, FetchError};
+use crate::title_card::TitleCard;
+use crate::workers::WorkerPool;
+
+// Sent by a TileImage to itself in LoadMode::Synchronous, since it can't
+// fetch its image from `lifecycle`.
+const LOAD_IMAGE: Selector = Selector::new("load_image");
+
+// Sent by a TileImage once its image has decoded, with its URL and the image's
+// size in pixels. The feed's dimensions can be missing or wrong, so the Thumbnail
+// showing the image sizes itself to these instead.
+pub const IMAGE_DIMENSIONS: Selector<(String, Size)> = Selector::new("image_dimensions");
+
+// Formats the CDN serves artwork in. Anything else is skipped.
+const SUPPORTED_FORMATS: [FileFormat; 4] = [
+    FileFormat::Jpeg,
+    FileFormat::Png,
+    FileFormat::WebP,
+    FileFormat::Avif,
+];
+
+// Background loads in progress, by URL. A load for a URL that's already loading
+// waits for that one instead, so artwork shown by several tiles at once is only
+// fetched and decoded once. Once it's decoded, DECODED_IMAGES takes over.
+static IN_FLIGHT: Lazy<Mutex<HashMap<String, Arc<SharedLoad>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Caps how many images load at once, separately from the WorkerPool of the
+// rows. See `configure_image_workers`.
+static IMAGE_WORKERS: OnceCell<WorkerPool> = OnceCell::new();
+
+// Filled in once by the first load of a URL: Some(None) if it failed.
+#[derive(Default)]
+struct SharedLoad {
+    image: Mutex<Option<Option<ImageBuf>>>,
+    done: Condvar,
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Fetch(FetchError),
+    // The bytes aren't in one of SUPPORTED_FORMATS.
+    Unsupported,
+    Decode(image::ImageError),
+}
+
+// The image of a tile. Replaces WebImage, so that we pick how artwork is decoded.
+// Shows nothing while loading, and the tile's TitleCard if the image can't be
+// fetched or decoded.
+pub struct TileImage {
+    pub url: String,
+    pub title: String,
+    pub timeout: Duration,
+    pub load_mode: LoadMode,
+    pub cache: Option<ImageCache>,
+
+    pub image_promise: PromiseToken<Result<ImageBuf, ImageError>>,
+    // None until the image resolves.
+    pub inner: Option<WidgetPod<Box<dyn Widget>>>,
+    // Whether we hold a pin on our image in DECODED_IMAGES, released on drop.
+    pub pinned: bool,
+}
+
+// Works out the format from the bytes themselves, since the CDN doesn't always
+// send an accurate Content-Type.
+pub fn decode_image(bytes: &[u8]) -> Result<ImageBuf, ImageError> {
+    let format = image::guess_format(bytes).map_err(|_| ImageError::Unsupported)?;
+    if !SUPPORTED_FORMATS.contains(&format) {
+        return Err(ImageError::Unsupported);
+    }
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(ImageError::Decode)?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(ImageBuf::from_raw(
+        image.into_raw(),
+        ImageFormat::RgbaSeparate,
+        width as usize,
+        height as usize,
+    ))
+}
+
+// Images are only added to the cache once they've decoded. A cached file that
+// doesn't decode (say, truncated by a full disk) is fetched again and replaced,
+// instead of breaking its tile on every launch.
+fn load_image(
+    url: &str,
+    timeout: Duration,
+    cache: Option<&ImageCache>,
+) -> Result<ImageBuf, ImageError> {
+    if let Some(bytes) = cache.and_then(|cache| cache.get(url)) {
+        match decode_image(&bytes) {
+            Ok(image) => return Ok(image),
+            Err(err) => tracing::warn!("Fetching cached image {} again: {}", url, err),
+        }
+    }
+    let bytes = fetch_bytes(url, timeout).map_err(ImageError::Fetch)?;
+    let image = decode_image(&bytes)?;
+    if let Some(cache) = cache {
+        if let Err(err) = cache.put(url, &bytes) {
+            tracing::warn!("Failed to cache image {}: {}", url, err);
+        }
+    }
+    Ok(image)
+}
+
+// Sets the limit of IMAGE_WORKERS. Like `configure_client`, only the first call
+// counts, and it has to happen before the first image loads.
+pub fn configure_image_workers(limit: Option<usize>) {
+    IMAGE_WORKERS.get_or_init(|| WorkerPool::new(limit));
+}
+
+fn image_workers() -> &'static WorkerPool {
+    IMAGE_WORKERS.get_or_init(|| WorkerPool::new(None))
+}
+
+// Same as `load_image`, but shares the work with other loads of the same URL
+// running at the same time. Loads wait for a slot of IMAGE_WORKERS, except the
+// ones waiting on another load.
+fn load_shared_image(
+    url: &str,
+    timeout: Duration,
+    cache: Option<&ImageCache>,
+) -> Result<ImageBuf, ImageError> {
+    let (load, first) = {
+        let mut in_flight = IN_FLIGHT.lock().unwrap();
+        match in_flight.get(url) {
+            Some(load) => (load.clone(), false),
+            None => {
+                let load = Arc::new(SharedLoad::default());
+                in_flight.insert(url.to_string(), load.clone());
+                (load, true)
+            }
+        }
+    };
+
+    if !first {
+        let mut image = load.image.lock().unwrap();
+        while image.is_none() {
+            image = load.done.wait(image).unwrap();
+        }
+        if let Some(Some(image)) = &*image {
+            return Ok(image.clone());
+        }
+        // The first load failed. Ours might still work, and it gets its own error if not.
+        drop(image);
+        return image_workers().run(|| load_image(url, timeout, cache));
+    }
+
+    let result = image_workers().run(|| load_image(url, timeout, cache));
+    *load.image.lock().unwrap() = Some(result.as_ref().ok().cloned());
+    IN_FLIGHT.lock().unwrap().remove(url);
+    load.done.notify_all();
+    result
+}
+
+// --- METHODS ---
+
+impl TileImage {
+    pub fn new(url: String, title: String, config: &AppConfig) -> Self {
+        Self {
+            url,
+            title,
+            timeout: config.http.image_timeout,
+            load_mode: config.load_mode,
+            cache: config.image_cache_dir.clone().map(ImageCache::new),
+            image_promise: PromiseToken::empty(),
+            inner: None,
+            pinned: false,
+        }
+    }
+
+    // Returns the command telling the Thumbnail the image's size.
+    fn show_image(&mut self, image: ImageBuf) -> Command {
+        DECODED_IMAGES.with(|images| images.borrow_mut().pin(&self.url));
+        self.pinned = true;
+        let dimensions = IMAGE_DIMENSIONS.with((self.url.clone(), image.size()));
+        self.inner = Some(WidgetPod::new(Box::new(Image::new(image))));
+        dimensions
+    }
+
+    fn on_image_loaded(&mut self, ctx: &mut EventCtx, image: Result<ImageBuf, ImageError>) {
+        match image {
+            Ok(image) => {
+                DECODED_IMAGES.with(|images| images.borrow_mut().insert(&self.url, image.clone()));
+                let dimensions = self.show_image(image);
+                ctx.submit_command(dimensions);
+            }
+            Err(err) => {
+                tracing::warn!("Skipping image {}: {}", self.url, err);
+                let title_card: Box<dyn Widget> = Box::new(TitleCard::new(self.title.clone()));
+                self.inner = Some(WidgetPod::new(title_card));
+            }
+        }
+        ctx.children_changed();
+        ctx.request_layout();
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for TileImage {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        match event {
+            // This happens after the callback passed to `ctx.compute_in_background` returns
+            Event::PromiseResult(result) => {
+                if let Some(image) = result.try_get(self.image_promise) {
+                    self.on_image_loaded(ctx, image);
+                    return;
+                }
+            }
+            Event::Command(command) if command.is(LOAD_IMAGE) => {
+                let image = load_image(&self.url, self.timeout, self.cache.as_ref());
+                self.on_image_loaded(ctx, image);
+                return;
+            }
+            _ => {}
+        }
+        if let Some(inner) = &mut self.inner {
+            inner.on_event(ctx, event, env);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.init();
+        if let LifeCycle::WidgetAdded = event {
+            let decoded = DECODED_IMAGES.with(|images| images.borrow_mut().get(&self.url));
+            match (decoded, self.load_mode) {
+                // Already decoded for a previous thumbnail of the same tile.
+                (Some(image), _) => {
+                    let dimensions = self.show_image(image);
+                    ctx.submit_command(dimensions);
+                    ctx.children_changed();
+                }
+                (None, LoadMode::Background) => {
+                    let url = self.url.clone();
+                    let timeout = self.timeout;
+                    let cache = self.cache.clone();
+                    self.image_promise = ctx.compute_in_background(move |_| {
+                        load_shared_image(&url, timeout, cache.as_ref())
+                    });
+                }
+                (None, LoadMode::Synchronous) => {
+                    ctx.submit_command(
+                        Command::from(LOAD_IMAGE).to(Target::Widget(ctx.widget_id())),
+                    );
+                }
+            }
+        }
+        if let Some(inner) = &mut self.inner {
+            inner.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        if let Some(inner) = &mut self.inner {
+            inner.layout(ctx, bc, env);
+            inner.set_origin(ctx, env, Point::ORIGIN);
+        }
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if let Some(inner) = &mut self.inner {
+            inner.paint(ctx, env);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> = SmallVec::new();
+        if let Some(inner) = &self.inner {
+            children.push(inner as &dyn AsWidgetPod);
+        }
+        children
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> = SmallVec::new();
+        if let Some(inner) = &mut self.inner {
+            children.push(inner as &mut dyn AsWidgetPod);
+        }
+        children
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TileImage")
+    }
+}
+
+impl Drop for TileImage {
+    fn drop(&mut self) {
+        // The cache may already be gone if we're dropped while the thread exits.
+        if self.pinned {
+            let _ = DECODED_IMAGES.try_with(|images| images.borrow_mut().unpin(&self.url));
+        }
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Fetch(err) => write!(f, "{}", err),
+            ImageError::Unsupported => write!(f, "unsupported image format"),
+            ImageError::Decode(err) => write!(f, "couldn't decode image: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use widget_cruncher::testing::Harness;
+    use widget_cruncher::Color;
+
+    use super::*;
+    use crate::testing::{paints_color, test_config, MockServer};
+    use crate::title_card::title_color;
+
+    // A 3x1 gray image, as lossy WebP.
+    const WEBP_IMAGE: [u8; 42] = [
+        0x52, 0x49, 0x46, 0x46, 0x22, 0x00, 0x00, 0x00, 0x57, 0x45, 0x42, 0x50, 0x56, 0x50, 0x38,
+        0x20, 0x16, 0x00, 0x00, 0x00, 0x30, 0x01, 0x00, 0x9d, 0x01, 0x2a, 0x03, 0x00, 0x01, 0x00,
+        0x0e, 0xc0, 0xfe, 0x25, 0xa4, 0x00, 0x03, 0x70, 0x00, 0x00, 0x00, 0x00,
+    ];
+    const WEBP_COLOR: Color = Color::rgb8(0x82, 0x82, 0x82);
+    const TILE_SIZE: Size = Size::new(300.0, 100.0);
+
+    // Draws the TileImage of the document served at `path`, once it has loaded.
+    fn render_tile_image(server: &MockServer, path: &str) -> ImageBuf {
+        let mut config = test_config(server);
+        config.text_only = false;
+        let url = format!("{}{}", server.base_url, path);
+        let tile_image = TileImage::new(url, "Moana".to_string(), &config);
+        let mut harness = Harness::create_with_size(tile_image, TILE_SIZE);
+        let _ = harness.render();
+        harness.render()
+    }
+
+    // Registers a load of `url` as in progress, like the first load of it would,
+    // and starts a second load that waits for it.
+    fn start_waiting_load(
+        url: &str,
+    ) -> (
+        Arc<SharedLoad>,
+        thread::JoinHandle<Result<ImageBuf, ImageError>>,
+    ) {
+        let load = Arc::new(SharedLoad::default());
+        IN_FLIGHT
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), load.clone());
+        let waiting_url = url.to_string();
+        let waiting =
+            thread::spawn(move || load_shared_image(&waiting_url, Duration::from_secs(5), None));
+        (load, waiting)
+    }
+
+    fn finish_load(url: &str, load: &SharedLoad, image: Option<ImageBuf>) {
+        *load.image.lock().unwrap() = Some(image);
+        IN_FLIGHT.lock().unwrap().remove(url);
+        load.done.notify_all();
+    }
+
+    #[test]
+    fn loads_of_the_same_url_share_the_first_one() {
+        let server = MockServer::start();
+        let url = format!("{}/shared.png", server.base_url);
+        let (load, waiting) = start_waiting_load(&url);
+
+        let image = ImageBuf::from_raw(vec![0xff; 4 * 3 * 2], ImageFormat::RgbaSeparate, 3, 2);
+        finish_load(&url, &load, Some(image));
+
+        let shared = waiting.join().unwrap().unwrap();
+        assert_eq!((shared.width(), shared.height()), (3, 2));
+        // The image was neither fetched nor decoded a second time.
+        assert_eq!(server.request_count("/shared.png"), 0);
+    }
+
+    #[test]
+    fn loads_waiting_on_a_failed_one_try_again() {
+        let server = MockServer::start();
+        let url = format!("{}/missing.png", server.base_url);
+        let (load, waiting) = start_waiting_load(&url);
+
+        finish_load(&url, &load, None);
+
+        // The mock server's 404 body isn't an image.
+        let result = waiting.join().unwrap();
+        assert!(matches!(result, Err(ImageError::Unsupported)));
+        assert_eq!(server.request_count("/missing.png"), 1);
+    }
+
+    #[test]
+    fn webp_images_are_decoded() {
+        let image = decode_image(&WEBP_IMAGE).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 1));
+
+        let server = MockServer::start();
+        server.add("/tile.webp", WEBP_IMAGE.to_vec());
+        let rendered = render_tile_image(&server, "/tile.webp");
+        assert!(paints_color(&rendered, TILE_SIZE.to_rect(), WEBP_COLOR));
+        assert!(!paints_color(
+            &rendered,
+            TILE_SIZE.to_rect(),
+            title_color("Moana")
+        ));
+    }
+
+    #[test]
+    fn unsupported_images_fall_back_to_the_title_card() {
+        assert!(matches!(
+            decode_image(b"not an image"),
+            Err(ImageError::Unsupported)
+        ));
+
+        let server = MockServer::start();
+        server.add("/tile.bin", b"not an image".to_vec());
+        let rendered = render_tile_image(&server, "/tile.bin");
+        assert!(paints_color(
+            &rendered,
+            TILE_SIZE.to_rect(),
+            title_color("Moana")
+        ));
+    }
+}
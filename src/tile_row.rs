@@ -0,0 +1,699 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::{AsWidgetPod, Spinner, WidgetPod};
+use widget_cruncher::{Point, Rect, Selector, TimerToken};
+
+use crate::clock::SharedClock;
+use crate::config::{AppConfig, TileSort};
+use crate::content_set::{TileMetadata, RELOAD_ROW};
+use crate::error_card::ErrorCard;
+use crate::session::SharedSession;
+use crate::thumbnail::{
+    cell_size, FocusTarget, SharedSelection, Thumbnail, BASE_SCALE, CHANGE_FOCUS,
+    CHANGE_SELECTED_ITEM, THUMBNAIL_MAX_SIZE, THUMBNAIL_OVERFLOW,
+};
+use crate::tile_image::TileImage;
+use crate::title_card::TitleCard;
+
+// Sent by a ContentSet when a page of tiles arrives. The payload is the row
+// the tiles belong to, and the new tiles.
+pub const APPEND_TILES: Selector<(usize, Vec<TileMetadata>)> = Selector::new("append_tiles");
+
+// Sent by a ContentSet when its first page was reloaded. The payload is the row,
+// how many tiles the previous first page had, and the tiles of the new one.
+pub const REPLACE_FIRST_PAGE: Selector<(usize, usize, Vec<TileMetadata>)> =
+    Selector::new("replace_first_page");
+
+// The title of the card shown in rows whose items all lack a usable tile.
+pub const UNAVAILABLE_TEXT: &str = "Content unavailable";
+
+// Sent by a ContentSet whose items all lack a usable tile. The payload is the row.
+pub const SHOW_UNAVAILABLE: Selector<usize> = Selector::new("show_unavailable");
+
+// Sent by a ContentSet whose first page failed to load. The payload is the row,
+// and the error.
+pub const SHOW_LOAD_ERROR: Selector<(usize, String)> = Selector::new("show_load_error");
+
+// How many cells wide the ErrorCard of a failed row is, so that the error fits.
+const ERROR_CARD_CELLS: f64 = 3.0;
+
+// Changes the order of a row's tiles. The payload is the row, and its new sort.
+pub const SET_SORT: Selector<(usize, TileSort)> = Selector::new("set_sort");
+
+// Sent by a ContentSet when the column at the middle of its viewport changes, so
+// that the live window follows a row scrolled without moving the selection, like
+// with the mouse wheel. The payload is the row, and that column.
+pub const SET_WINDOW_CENTER: Selector<(usize, usize)> = Selector::new("set_window_center");
+
+// Adds the selected tile to the favorites, or removes it.
+pub const TOGGLE_FAVORITE: Selector = Selector::new("toggle_favorite");
+
+// The horizontal list of thumbnails inside a ContentSet.
+// Unlike a Flex row, we can keep appending to it as more pages are loaded,
+// and it can lay its tiles out right-to-left.
+// The row keeps a THUMBNAIL_OVERFLOW margin around its tiles, so that the
+// selected tile can grow past its cell without being cut off by the ClipBox.
+//
+// Rows can have thousands of tiles, so we only keep Thumbnail widgets for the
+// tiles around the row's selected column; the other cells are left empty, and
+// their widgets are recreated when the selection comes back near them.
+pub struct TileRow {
+    pub row: usize,
+    pub config: Arc<AppConfig>,
+
+    // Shown until the first page of tiles arrives. It only becomes visible once
+    // `placeholder_timer` fires; see `AppConfig::spinner_delay`. Until then, its
+    // space is still taken, so the grid doesn't move when it appears.
+    pub placeholder: Option<WidgetPod<Spinner>>,
+    pub placeholder_visible: bool,
+    pub placeholder_timer: TimerToken,
+
+    // Shown in place of the tiles after SHOW_UNAVAILABLE: the configured
+    // artwork, or a "Content unavailable" card. It can't be selected.
+    pub unavailable_card: Option<WidgetPod<Box<dyn Widget>>>,
+    // Shown in place of the spinner after SHOW_LOAD_ERROR, until the row is reloaded.
+    pub error_card: Option<WidgetPod<ErrorCard>>,
+
+    // Every tile of the row, and the widgets of the ones that are live, by column.
+    // `tile_data` is kept sorted by `sort`. `feed_order` has the same tiles, as
+    // they came in the feed, so that the sort can be changed afterwards.
+    pub sort: TileSort,
+    pub feed_order: Vec<TileMetadata>,
+    pub tile_data: Vec<TileMetadata>,
+    pub tiles: Vec<Option<WidgetPod<Thumbnail>>>,
+
+    // The column the live window is centered on: the last one selected in this
+    // row, or the one in the middle of the viewport if the row was scrolled since.
+    pub window_center: usize,
+    // Used to give recreated thumbnails the right state.
+    pub selection: SharedSelection,
+    pub session: SharedSession,
+    pub clock: SharedClock,
+    pub focused_column: Option<usize>,
+    // The width of every cell, as of the last layout. See `thumbnail::cell_size`.
+    pub cell_size: f64,
+}
+
+// Horizontal position of a cell inside the row, not counting THUMBNAIL_OVERFLOW.
+// In right-to-left mode, the first tile is on the right end of the row.
+pub fn cell_x(column: usize, cell_count: usize, cell_size: f64, rtl: bool) -> f64 {
+    if rtl {
+        (cell_count - column - 1) as f64 * cell_size
+    } else {
+        column as f64 * cell_size
+    }
+}
+
+// Sorts `tiles`, in feed order, by `sort`. Tiles without a title or year go last.
+// Sorts are stable, so tiles that compare equal stay in feed order.
+pub fn sort_tiles(tiles: &mut [TileMetadata], sort: TileSort) {
+    match sort {
+        TileSort::FeedOrder => {}
+        TileSort::Title => tiles.sort_by(|a, b| match (&a.title, &b.title) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }),
+        TileSort::Year => tiles.sort_by_key(|tile| std::cmp::Reverse(tile.release_year)),
+    }
+}
+
+// --- METHODS ---
+
+impl TileRow {
+    pub fn new(
+        row: usize,
+        config: Arc<AppConfig>,
+        selection: SharedSelection,
+        session: SharedSession,
+        clock: SharedClock,
+    ) -> Self {
+        Self {
+            row,
+            config,
+            placeholder: Some(WidgetPod::new(Spinner::new())),
+            placeholder_visible: config.spinner_delay.is_none(),
+            placeholder_timer: TimerToken::INVALID,
+            unavailable_card: None,
+            error_card: None,
+            sort: TileSort::FeedOrder,
+            feed_order: Vec::new(),
+            tile_data: Vec::new(),
+            tiles: Vec::new(),
+            window_center: 0,
+            selection,
+            session,
+            clock,
+            focused_column: None,
+            cell_size: THUMBNAIL_MAX_SIZE,
+        }
+    }
+
+    pub fn with_sort(mut self, sort: TileSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    fn sort_tiles(&mut self) {
+        self.tile_data = self.feed_order.clone();
+        sort_tiles(&mut self.tile_data, self.sort);
+    }
+
+    // Columns which should have a live Thumbnail.
+    pub fn live_window(&self) -> Range<usize> {
+        let margin = self.config.virtualization_margin;
+        let start = self.window_center.saturating_sub(margin);
+        let end = (self.window_center + margin + 1).min(self.tile_data.len());
+        start..end.max(start)
+    }
+
+    // Creates the widgets entering the live window, and drops the ones leaving it.
+    fn update_live_tiles(&mut self, ctx: &mut EventCtx) {
+        let window = self.live_window();
+        let mut changed = false;
+        for (column, (slot, tile)) in self.tiles.iter_mut().zip(&self.tile_data).enumerate() {
+            let wanted = window.contains(&column);
+            if wanted && slot.is_none() {
+                let thumbnail = Thumbnail::new(
+                    self.row,
+                    column,
+                    tile.clone(),
+                    &self.config,
+                    self.selection.clone(),
+                    self.session.clone(),
+                    self.clock.clone(),
+                )
+                .with_state(self.focused_column == Some(column));
+                *slot = Some(WidgetPod::new(thumbnail));
+                changed = true;
+            } else if !wanted && slot.is_some() {
+                *slot = None;
+                changed = true;
+            }
+        }
+        if changed {
+            ctx.children_changed();
+            ctx.request_layout();
+        }
+    }
+
+    // The image of every live tile, as (column, rect in our coordinates), in the
+    // order `paint` draws them.
+    pub fn tile_rects(&self) -> Vec<(usize, Rect)> {
+        let (selected, unselected): (Vec<_>, Vec<_>) = self
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(|(column, slot)| slot.as_ref().map(|tile| (column, tile)))
+            .partition(|(_, tile)| tile.widget().selected_progress > 0.0);
+        unselected
+            .into_iter()
+            .chain(selected)
+            .map(|(column, tile)| {
+                let image_rect = tile.widget().inner.layout_rect();
+                (column, image_rect + tile.layout_rect().origin().to_vec2())
+            })
+            .collect()
+    }
+
+    fn live_tiles_mut(&mut self) -> impl Iterator<Item = &mut WidgetPod<Thumbnail>> {
+        self.tiles.iter_mut().filter_map(|slot| slot.as_mut())
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for TileRow {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        ctx.init();
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.on_event(ctx, event, env);
+        }
+        for tile in self.live_tiles_mut() {
+            tile.on_event(ctx, event, env);
+        }
+        if let Some(unavailable_card) = &mut self.unavailable_card {
+            unavailable_card.on_event(ctx, event, env);
+        }
+        if let Some(error_card) = &mut self.error_card {
+            error_card.on_event(ctx, event, env);
+        }
+
+        match event {
+            Event::Timer(token) if *token == self.placeholder_timer => {
+                self.placeholder_timer = TimerToken::INVALID;
+                self.placeholder_visible = true;
+                ctx.request_paint();
+            }
+            Event::Command(command) => {
+                if let Some((row, new_tiles)) = command.try_get(APPEND_TILES) {
+                    if *row == self.row {
+                        self.feed_order.extend(new_tiles.iter().cloned());
+                        self.tile_data.extend(new_tiles.iter().cloned());
+                        if self.sort != TileSort::FeedOrder {
+                            // New tiles can land anywhere in the row, so every live
+                            // thumbnail may now be showing the wrong tile.
+                            self.sort_tiles();
+                            self.tiles.clear();
+                        }
+                        self.tiles.resize_with(self.tile_data.len(), || None);
+                        self.placeholder = None;
+                        self.update_live_tiles(ctx);
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                if let Some((row, old_len, new_tiles)) = command.try_get(REPLACE_FIRST_PAGE) {
+                    if *row == self.row {
+                        let old_tile_data = std::mem::take(&mut self.tile_data);
+                        let old_len = (*old_len).min(self.feed_order.len());
+                        self.feed_order.splice(..old_len, new_tiles.iter().cloned());
+                        self.sort_tiles();
+                        // Thumbnails of tiles that didn't change are kept as they are,
+                        // so refreshing a row doesn't reload all of its images.
+                        self.tiles.resize_with(self.tile_data.len(), || None);
+                        for (column, slot) in self.tiles.iter_mut().enumerate() {
+                            if old_tile_data.get(column) != Some(&self.tile_data[column]) {
+                                *slot = None;
+                            }
+                        }
+                        self.update_live_tiles(ctx);
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                if let Some(row) = command.try_get(SHOW_UNAVAILABLE) {
+                    if *row == self.row {
+                        let card: Box<dyn Widget> = match &self.config.unavailable_artwork_url {
+                            Some(url) if !self.config.text_only => Box::new(TileImage::new(
+                                url.clone(),
+                                UNAVAILABLE_TEXT.to_string(),
+                                &self.config,
+                            )),
+                            _ => Box::new(TitleCard::new(UNAVAILABLE_TEXT.to_string())),
+                        };
+                        self.unavailable_card = Some(WidgetPod::new(card));
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                if let Some((row, error)) = command.try_get(SHOW_LOAD_ERROR) {
+                    if *row == self.row {
+                        self.placeholder = None;
+                        let error_card = ErrorCard::new(error.clone(), &self.config);
+                        self.error_card = Some(WidgetPod::new(error_card));
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                // The ContentSet reloads its first page, so we show the spinner again.
+                if let Some(row) = command.try_get(RELOAD_ROW) {
+                    if *row == self.row && self.error_card.is_some() {
+                        self.error_card = None;
+                        self.placeholder = Some(WidgetPod::new(Spinner::new()));
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                if let Some((row, sort)) = command.try_get(SET_SORT) {
+                    if *row == self.row && *sort != self.sort {
+                        self.sort = *sort;
+                        self.sort_tiles();
+                        // Same as when tiles are appended to a sorted row.
+                        self.tiles.clear();
+                        self.tiles.resize_with(self.tile_data.len(), || None);
+                        self.update_live_tiles(ctx);
+                        ctx.children_changed();
+                        ctx.request_layout();
+                    }
+                }
+                if command.is(TOGGLE_FAVORITE) {
+                    match self.selection.get() {
+                        Some((row, column)) if row == self.row && column < self.tile_data.len() => {
+                            let tile = &self.tile_data[column];
+                            self.session.borrow_mut().favorites.toggle(tile);
+                            // Thumbnails read the favorites when they paint.
+                            ctx.request_paint();
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some((row, column)) = command.try_get(SET_WINDOW_CENTER) {
+                    if *row == self.row && *column != self.window_center {
+                        self.window_center = *column;
+                        self.update_live_tiles(ctx);
+                    }
+                }
+                if command.is(CHANGE_SELECTED_ITEM) {
+                    match self.selection.get() {
+                        Some((row, column)) if row == self.row => {
+                            self.window_center = column;
+                            self.update_live_tiles(ctx);
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(focus) = command.try_get(CHANGE_FOCUS) {
+                    self.focused_column = match *focus {
+                        FocusTarget::Tile(row, column) if row == self.row => Some(column),
+                        _ => None,
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        ctx.init();
+        if let LifeCycle::WidgetAdded = event {
+            if let Some(delay) = self.config.spinner_delay {
+                self.placeholder_timer = ctx.request_timer(delay);
+            }
+        }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.lifecycle(ctx, event, env);
+        }
+        for tile in self.live_tiles_mut() {
+            tile.lifecycle(ctx, event, env);
+        }
+        if let Some(unavailable_card) = &mut self.unavailable_card {
+            unavailable_card.lifecycle(ctx, event, env);
+        }
+        if let Some(error_card) = &mut self.error_card {
+            error_card.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        self.cell_size = cell_size(env);
+        let cell = self.cell_size;
+        if let Some(placeholder) = &mut self.placeholder {
+            let side = cell / 2.0;
+            let placeholder_size =
+                placeholder.layout(ctx, &BoxConstraints::tight(Size::new(side, side)), env);
+            placeholder.set_origin(ctx, env, Point::ORIGIN);
+            return bc.constrain(placeholder_size);
+        }
+
+        if let Some(unavailable_card) = &mut self.unavailable_card {
+            // Drawn like an unselected tile in the first cell.
+            let side = cell * BASE_SCALE;
+            unavailable_card.layout(ctx, &BoxConstraints::tight(Size::new(side, side)), env);
+            let inset = THUMBNAIL_OVERFLOW + (cell - side) / 2.0;
+            unavailable_card.set_origin(ctx, env, Point::new(inset, inset));
+            return bc.constrain(Size::new(
+                cell + 2.0 * THUMBNAIL_OVERFLOW,
+                cell + 2.0 * THUMBNAIL_OVERFLOW,
+            ));
+        }
+
+        if let Some(error_card) = &mut self.error_card {
+            // As tall as an unselected tile, in the first cells.
+            let side = cell * BASE_SCALE;
+            let inset = THUMBNAIL_OVERFLOW + (cell - side) / 2.0;
+            let width = ERROR_CARD_CELLS * cell - (cell - side);
+            error_card.layout(ctx, &BoxConstraints::tight(Size::new(width, side)), env);
+            error_card.set_origin(ctx, env, Point::new(inset, inset));
+            return bc.constrain(Size::new(
+                ERROR_CARD_CELLS * cell + 2.0 * THUMBNAIL_OVERFLOW,
+                cell + 2.0 * THUMBNAIL_OVERFLOW,
+            ));
+        }
+
+        // Every cell is as wide, whether its widget is live or not.
+        let cell_count = self.tiles.len();
+        let content_width = cell_count as f64 * cell;
+
+        let rtl = self.config.text_direction.is_rtl();
+        for (column, slot) in self.tiles.iter_mut().enumerate() {
+            if let Some(tile) = slot {
+                tile.layout(ctx, &bc.loosen(), env);
+                let cell_x = cell_x(column, cell_count, cell, rtl);
+                tile.set_origin(
+                    ctx,
+                    env,
+                    Point::new(THUMBNAIL_OVERFLOW + cell_x, THUMBNAIL_OVERFLOW),
+                );
+            }
+        }
+        bc.constrain(Size::new(
+            content_width + 2.0 * THUMBNAIL_OVERFLOW,
+            cell + 2.0 * THUMBNAIL_OVERFLOW,
+        ))
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if let Some(placeholder) = &mut self.placeholder {
+            if self.placeholder_visible {
+                placeholder.paint(ctx, env);
+            }
+        }
+        if let Some(unavailable_card) = &mut self.unavailable_card {
+            unavailable_card.paint(ctx, env);
+        }
+        if let Some(error_card) = &mut self.error_card {
+            error_card.paint(ctx, env);
+        }
+        // Tiles that are (or were just) selected are painted last, so that they
+        // show above their neighbors while they're bigger than their cell.
+        for tile in self.live_tiles_mut() {
+            if tile.widget().selected_progress == 0.0 {
+                tile.paint(ctx, env);
+            }
+        }
+        for tile in self.live_tiles_mut() {
+            if tile.widget().selected_progress > 0.0 {
+                tile.paint(ctx, env);
+            }
+        }
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&dyn AsWidgetPod; 16]> = SmallVec::new();
+        if let Some(placeholder) = &self.placeholder {
+            children.push(placeholder as &dyn AsWidgetPod);
+        }
+        if let Some(unavailable_card) = &self.unavailable_card {
+            children.push(unavailable_card as &dyn AsWidgetPod);
+        }
+        if let Some(error_card) = &self.error_card {
+            children.push(error_card as &dyn AsWidgetPod);
+        }
+        children.extend(
+            self.tiles
+                .iter()
+                .filter_map(|slot| slot.as_ref())
+                .map(|tile| tile as &dyn AsWidgetPod),
+        );
+        children
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        let mut children: SmallVec<[&mut dyn AsWidgetPod; 16]> = SmallVec::new();
+        if let Some(placeholder) = &mut self.placeholder {
+            children.push(placeholder as &mut dyn AsWidgetPod);
+        }
+        if let Some(unavailable_card) = &mut self.unavailable_card {
+            children.push(unavailable_card as &mut dyn AsWidgetPod);
+        }
+        if let Some(error_card) = &mut self.error_card {
+            children.push(error_card as &mut dyn AsWidgetPod);
+        }
+        children.extend(
+            self.tiles
+                .iter_mut()
+                .filter_map(|slot| slot.as_mut())
+                .map(|tile| tile as &mut dyn AsWidgetPod),
+        );
+        children
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TileRow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use widget_cruncher::testing::Harness;
+
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::session::SessionState;
+    use crate::testing::{offline_config, tile_metadata, WINDOW_SIZE};
+
+    // A tile per (title, release year), in that order.
+    fn tiles(tiles: &[(Option<&str>, Option<u32>)]) -> Vec<TileMetadata> {
+        tiles
+            .iter()
+            .enumerate()
+            .map(|(index, (title, year))| TileMetadata {
+                title: title.map(|title| title.to_string()),
+                release_year: *year,
+                ..tile_metadata(&format!("tile-{}", index))
+            })
+            .collect()
+    }
+
+    fn titles(tiles: &[TileMetadata]) -> Vec<Option<&str>> {
+        tiles.iter().map(|tile| tile.title.as_deref()).collect()
+    }
+
+    const FEED: [(Option<&str>, Option<u32>); 4] = [
+        (Some("Moana"), Some(2016)),
+        (None, Some(2019)),
+        (Some("Encanto"), None),
+        (Some("Frozen"), Some(2013)),
+    ];
+
+    #[test]
+    fn feed_order_is_kept() {
+        let mut sorted = tiles(&FEED);
+        sort_tiles(&mut sorted, TileSort::FeedOrder);
+        assert_eq!(
+            titles(&sorted),
+            [Some("Moana"), None, Some("Encanto"), Some("Frozen")]
+        );
+    }
+
+    #[test]
+    fn title_sort_is_alphabetical_with_untitled_tiles_last() {
+        let mut sorted = tiles(&FEED);
+        sort_tiles(&mut sorted, TileSort::Title);
+        assert_eq!(
+            titles(&sorted),
+            [Some("Encanto"), Some("Frozen"), Some("Moana"), None]
+        );
+    }
+
+    #[test]
+    fn year_sort_is_newest_first_with_undated_tiles_last() {
+        let mut sorted = tiles(&FEED);
+        sort_tiles(&mut sorted, TileSort::Year);
+        assert_eq!(
+            titles(&sorted),
+            [None, Some("Moana"), Some("Frozen"), Some("Encanto")]
+        );
+    }
+
+    #[test]
+    fn equal_tiles_stay_in_feed_order() {
+        let mut sorted = tiles(&[
+            (Some("B"), Some(2000)),
+            (Some("A"), Some(2000)),
+            (Some("C"), Some(2000)),
+        ]);
+        sort_tiles(&mut sorted, TileSort::Year);
+        assert_eq!(titles(&sorted), [Some("B"), Some("A"), Some("C")]);
+    }
+
+    // A TileRow of `tile_count` tiles, with the first one selected.
+    fn long_row(config: AppConfig, tile_count: usize) -> (Harness, SharedSelection) {
+        let config = Arc::new(config);
+        let selection: SharedSelection = Rc::new(Cell::new(Some((0, 0))));
+        let session = Rc::new(RefCell::new(SessionState::load(&config)));
+        let row = TileRow::new(0, config, selection.clone(), session, Rc::new(SystemClock));
+        let mut harness = Harness::create_with_size(row, WINDOW_SIZE);
+        let tiles = (0..tile_count)
+            .map(|column| tile_metadata(&format!("Tile {}", column)))
+            .collect();
+        harness.submit_command(APPEND_TILES.with((0, tiles)));
+        let _ = harness.render();
+        (harness, selection)
+    }
+
+    fn live_columns(harness: &Harness) -> Vec<usize> {
+        let root = harness.root_widget();
+        let tile_row = root
+            .downcast::<TileRow>()
+            .expect("the root isn't a TileRow");
+        tile_row
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_some())
+            .map(|(column, _)| column)
+            .collect()
+    }
+
+    #[test]
+    fn long_rows_only_keep_the_thumbnails_near_the_selection() {
+        let mut config = offline_config();
+        config.text_only = true;
+        config.virtualization_margin = 12;
+        let (mut harness, selection) = long_row(config, 5000);
+        assert_eq!(live_columns(&harness), (0..13).collect::<Vec<_>>());
+
+        // The widgets follow the selection, and the ones it left are dropped.
+        selection.set(Some((0, 2500)));
+        harness.submit_command(CHANGE_SELECTED_ITEM);
+        let _ = harness.render();
+        assert_eq!(live_columns(&harness), (2488..2513).collect::<Vec<_>>());
+
+        // Same when the row is scrolled without moving the selection.
+        harness.submit_command(SET_WINDOW_CENTER.with((0, 4999)));
+        let _ = harness.render();
+        assert_eq!(live_columns(&harness), (4987..5000).collect::<Vec<_>>());
+    }
+
+    // A TileRow still waiting for its first page, loading for `spinner_delay`.
+    fn loading_row(spinner_delay: Duration) -> Harness {
+        let mut config = offline_config();
+        config.spinner_delay = Some(spinner_delay);
+        let config = Arc::new(config);
+        let session = Rc::new(RefCell::new(SessionState::load(&config)));
+        let row = TileRow::new(
+            0,
+            config,
+            Rc::new(Cell::new(None)),
+            session,
+            Rc::new(SystemClock),
+        );
+        let mut harness = Harness::create_with_size(row, WINDOW_SIZE);
+        let _ = harness.render();
+        harness
+    }
+
+    fn spinner_shown(harness: &Harness) -> bool {
+        let root = harness.root_widget();
+        let tile_row = root.downcast::<TileRow>().unwrap();
+        tile_row.placeholder.is_some() && tile_row.placeholder_visible
+    }
+
+    #[test]
+    fn rows_that_load_within_the_grace_period_never_show_a_spinner() {
+        let spinner_delay = Duration::from_millis(300);
+        let mut harness = loading_row(spinner_delay);
+        harness.move_timers_forward(spinner_delay / 3);
+        assert!(!spinner_shown(&harness));
+
+        harness.submit_command(APPEND_TILES.with((0, vec![tile_metadata("Moana")])));
+        harness.move_timers_forward(spinner_delay);
+        let _ = harness.render();
+        assert!(!spinner_shown(&harness));
+    }
+
+    #[test]
+    fn slow_rows_show_a_spinner_after_the_grace_period() {
+        let spinner_delay = Duration::from_millis(300);
+        let mut harness = loading_row(spinner_delay);
+        harness.move_timers_forward(spinner_delay / 3);
+        assert!(!spinner_shown(&harness));
+
+        harness.move_timers_forward(spinner_delay);
+        assert!(spinner_shown(&harness));
+    }
+}
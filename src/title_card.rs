@@ -0,0 +1,131 @@
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+
+use widget_cruncher::piet::{FontFamily, Text, TextLayout, TextLayoutBuilder};
+use widget_cruncher::widget::prelude::*;
+use widget_cruncher::widget::AsWidgetPod;
+use widget_cruncher::{Color, Point};
+
+// Stands in for a tile's image in text-only mode: the title, on a background
+// whose color is derived from the title, so that neighboring cards can be told apart.
+pub struct TitleCard {
+    pub title: String,
+    pub background: Color,
+}
+
+impl TitleCard {
+    pub fn new(title: String) -> Self {
+        let background = title_color(&title);
+        Self { title, background }
+    }
+}
+
+// Backgrounds of title cards. They're all dark enough for white text to stay
+// readable, and spread around the color wheel so neighbors rarely look alike.
+const CARD_PALETTE: [Color; 12] = [
+    Color::rgb8(0x8c, 0x2f, 0x39),
+    Color::rgb8(0x9a, 0x4a, 0x1c),
+    Color::rgb8(0x7a, 0x62, 0x12),
+    Color::rgb8(0x4f, 0x6b, 0x1f),
+    Color::rgb8(0x1f, 0x6b, 0x3a),
+    Color::rgb8(0x14, 0x6b, 0x64),
+    Color::rgb8(0x1a, 0x5a, 0x7f),
+    Color::rgb8(0x25, 0x3f, 0x8a),
+    Color::rgb8(0x4a, 0x32, 0x8c),
+    Color::rgb8(0x6e, 0x2a, 0x80),
+    Color::rgb8(0x85, 0x26, 0x5f),
+    Color::rgb8(0x4a, 0x4f, 0x5c),
+];
+
+// Picks a color of CARD_PALETTE from the title, so a title always gets the same
+// one. This uses FNV-1a rather than the standard library's hasher, whose output
+// can change between Rust versions and platforms.
+pub fn title_color(title: &str) -> Color {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = title.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    CARD_PALETTE[(hash % CARD_PALETTE.len() as u64) as usize]
+}
+
+// --- TRAIT IMPL ---
+
+impl Widget for TitleCard {
+    fn on_event(&mut self, _ctx: &mut EventCtx, _event: &Event, _env: &Env) {}
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _env: &Env) {}
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _env: &Env) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
+        const PADDING: f64 = 8.0;
+
+        let rect = ctx.size().to_rect();
+        ctx.fill(rect, &self.background);
+
+        let text = ctx
+            .text()
+            .new_text_layout(self.title.clone())
+            .font(FontFamily::SYSTEM_UI, 16.0)
+            .text_color(Color::WHITE)
+            .max_width(rect.width() - 2.0 * PADDING)
+            .build()
+            .unwrap();
+        let text_size = text.size();
+        ctx.draw_text(
+            &text,
+            Point::new(PADDING, (rect.height() - text_size.height).max(0.0) / 2.0),
+        );
+    }
+
+    fn children(&self) -> SmallVec<[&dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    fn children_mut(&mut self) -> SmallVec<[&mut dyn AsWidgetPod; 16]> {
+        SmallVec::new()
+    }
+
+    // This isn't useful for the application itself, but it makes traces more readable
+    // when debugging
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TitleCard")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_always_get_the_same_color() {
+        // Pinned, so that a change of hash shows up here rather than as cards
+        // changing color between versions.
+        assert_eq!(title_color(""), CARD_PALETTE[5]);
+        assert_eq!(title_color("Moana"), CARD_PALETTE[5]);
+        assert_eq!(title_color("Frozen"), CARD_PALETTE[11]);
+        assert_eq!(title_color("Encanto"), CARD_PALETTE[1]);
+        assert_eq!(
+            TitleCard::new("Frozen".to_string()).background,
+            CARD_PALETTE[11]
+        );
+    }
+
+    #[test]
+    fn titles_spread_over_the_palette() {
+        let used = (0..100)
+            .map(|index| title_color(&format!("Title {}", index)))
+            .fold(Vec::new(), |mut used, color| {
+                if !used.contains(&color) {
+                    used.push(color);
+                }
+                used
+            });
+        assert_eq!(used.len(), CARD_PALETTE.len());
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt;
+
+use crate::api::{fetch_home, fetch_set, Lenient};
+use crate::config::AppConfig;
+use crate::content_set::{content_set_url, parse_tile, ContentSetMetadata};
+use crate::net::{CancelHandle, FetchError};
+use crate::root_widget::{parse_container, ContainerError};
+
+// What `validate_feed` found wrong with the feed, if anything.
+// Meant to be run in CI against the live feed, so that shape changes get caught
+// before they show up as empty rows in the app.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub containers: usize,
+    pub parsed: usize,
+
+    // Containers we couldn't build a row from, as (index in the feed, reason).
+    pub dropped: Vec<(usize, ContainerError)>,
+
+    // Sets where some items have no usable tile, as (ref id, dropped items, total items).
+    pub bad_tiles: Vec<(String, usize, usize)>,
+
+    // Documents that don't have the shape of the `api` types, with what's wrong.
+    pub schema_mismatches: Vec<String>,
+
+    pub fetch_errors: Vec<String>,
+}
+
+impl ValidationReport {
+    // Documents that fetched fine but didn't parse are schema mismatches.
+    fn push_error(&mut self, document: &str, url: &str, err: FetchError) {
+        match err {
+            FetchError::Json(_) | FetchError::Schema(_) => self
+                .schema_mismatches
+                .push(format!("{}: {}", document, err)),
+            _ => self.fetch_errors.push(format!("{}: {}", url, err)),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.dropped.is_empty()
+            && self.bad_tiles.is_empty()
+            && self.schema_mismatches.is_empty()
+            && self.fetch_errors.is_empty()
+    }
+}
+
+// Fetches the collection and every set it references, and reports everything
+// the app would silently skip.
+pub fn validate_feed(config: &AppConfig) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let home = match fetch_home(
+        &config.collection_url,
+        config.http.home_timeout,
+        &CancelHandle::default(),
+    ) {
+        Ok(home) => home,
+        Err(err) => {
+            report.push_error("home", &config.collection_url, err);
+            return report;
+        }
+    };
+    let containers = &home.data.standard_collection.containers;
+
+    report.containers = containers.len();
+    for (index, container) in containers.iter().enumerate() {
+        match parse_container(container) {
+            Ok(metadata) => {
+                report.parsed += 1;
+                validate_set(&mut report, config, &metadata);
+            }
+            Err(reason) => report.dropped.push((index, reason)),
+        }
+    }
+    report
+}
+
+fn validate_set(report: &mut ValidationReport, config: &AppConfig, metadata: &ContentSetMetadata) {
+    let ref_id = &metadata.ref_id;
+    let url = content_set_url(config, metadata, 0);
+    let set = match fetch_set(
+        &url,
+        "CuratedSet",
+        config.http.set_timeout,
+        &CancelHandle::default(),
+    ) {
+        Ok(set) => set,
+        Err(err) => {
+            report.push_error(&format!("set {}", ref_id), &url, err);
+            return;
+        }
+    };
+    let items = &set.items;
+
+    let bad_items = items
+        .iter()
+        .filter(|item| match item {
+            Lenient::Parsed(item) => parse_tile(item).is_none(),
+            Lenient::Malformed(_) => true,
+        })
+        .count();
+    if bad_items > 0 {
+        report
+            .bad_tiles
+            .push((ref_id.to_string(), bad_items, items.len()));
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContainerError::Malformed => write!(f, "malformed container"),
+            ContainerError::MissingTitle => write!(f, "missing title"),
+            ContainerError::MissingRefId => write!(f, "missing refId"),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} containers, {} parsed, {} dropped",
+            self.containers,
+            self.parsed,
+            self.dropped.len()
+        )?;
+        for (index, reason) in &self.dropped {
+            writeln!(f, "dropped container #{}: {}", index, reason)?;
+        }
+        for (ref_id, bad_items, total_items) in &self.bad_tiles {
+            writeln!(
+                f,
+                "set {}: {} of {} items have no usable tile",
+                ref_id, bad_items, total_items
+            )?;
+        }
+        for mismatch in &self.schema_mismatches {
+            writeln!(f, "schema mismatch: {}", mismatch)?;
+        }
+        for error in &self.fetch_errors {
+            writeln!(f, "fetch error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{home_json, home_path, set_path, test_config, tile_json, MockServer};
+
+    #[test]
+    fn a_healthy_feed_is_ok() {
+        let server = MockServer::start();
+        server.add_catalog(&[("trending", "Trending"), ("new", "New")], 3);
+        let report = validate_feed(&test_config(&server));
+        assert_eq!((report.containers, report.parsed), (2, 2));
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn everything_the_app_would_skip_is_reported() {
+        let server = MockServer::start();
+        let mut home = home_json(&[("trending", "Trending"), ("broken", "Broken")]);
+        let containers = home["data"]["StandardCollection"]["containers"]
+            .as_array_mut()
+            .unwrap();
+        containers.push(json!({ "set": 42 }));
+        containers.push(json!({ "set": { "refId": "untitled" } }));
+        let title = json!({ "full": { "set": { "default": { "content": "No ref" } } } });
+        containers.push(json!({ "set": { "text": { "title": title } } }));
+        server.add(&home_path(), home.to_string());
+
+        // A good tile, a tile without artwork, and an item that isn't a tile at all.
+        let mut imageless = tile_json("Imageless");
+        imageless.as_object_mut().unwrap().remove("image");
+        let trending = json!({
+            "data": { "CuratedSet": { "items": [tile_json("Good"), imageless, 42] } }
+        });
+        server.add(&set_path("trending"), trending.to_string());
+        server.add(&set_path("broken"), json!({ "data": {} }).to_string());
+
+        let report = validate_feed(&test_config(&server));
+        assert_eq!((report.containers, report.parsed), (5, 2));
+        assert_eq!(
+            report.dropped,
+            [
+                (2, ContainerError::Malformed),
+                (3, ContainerError::MissingTitle),
+                (4, ContainerError::MissingRefId),
+            ]
+        );
+        assert_eq!(report.bad_tiles, [("trending".to_string(), 2, 3)]);
+        assert_eq!(
+            report.schema_mismatches,
+            ["set broken: unexpected JSON: data.CuratedSet is missing"]
+        );
+        assert!(report.fetch_errors.is_empty());
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn a_home_that_isnt_a_collection_is_a_schema_mismatch() {
+        // Nothing is served, so the home document is the mock server's `{}` 404.
+        let server = MockServer::start();
+        let report = validate_feed(&test_config(&server));
+        assert_eq!(report.containers, 0);
+        assert_eq!(report.schema_mismatches.len(), 1);
+        assert!(report.schema_mismatches[0].starts_with("home: "));
+        assert!(!report.is_ok());
+    }
+}
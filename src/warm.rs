@@ -0,0 +1,228 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::config::AppConfig;
+use crate::content_set::{content_set_url, load_content_set};
+use crate::image_cache::ImageCache;
+use crate::net::{fetch_bytes, CancelHandle};
+use crate::root_widget::load_collection;
+use crate::tile_image::decode_image;
+
+// Used when the config doesn't cap background or image workers.
+const DEFAULT_WARM_WORKERS: usize = 4;
+
+// What `warm_caches` did.
+#[derive(Debug, Default)]
+pub struct WarmSummary {
+    pub sets: usize,
+    pub images_fetched: usize,
+    pub images_already_cached: usize,
+    // Size of the images fetched by this run.
+    pub bytes: u64,
+    pub failures: Vec<String>,
+}
+
+// Fetches the collection, every page of every set, and every tile image, and
+// saves the images to the image cache. Meant to provision kiosks before they
+// go offline, without opening a window.
+// The JSON feeds themselves aren't cached, since the app always fetches them.
+pub fn warm_caches(config: &AppConfig) -> WarmSummary {
+    let mut summary = WarmSummary::default();
+    let cache = match &config.image_cache_dir {
+        Some(dir) => ImageCache::new(dir.clone()),
+        None => {
+            summary
+                .failures
+                .push("image_cache_dir isn't set, so there's nothing to warm".to_string());
+            return summary;
+        }
+    };
+    let set_workers = config
+        .background_workers
+        .unwrap_or(DEFAULT_WARM_WORKERS)
+        .max(1);
+    let image_workers = config.image_workers.unwrap_or(DEFAULT_WARM_WORKERS).max(1);
+
+    let cancel = CancelHandle::default();
+    let rows = match load_collection(&config.collection_url, config.http.home_timeout, &cancel) {
+        Ok(rows) => rows,
+        Err(err) => {
+            summary
+                .failures
+                .push(format!("{}: {}", config.collection_url, err));
+            return summary;
+        }
+    };
+
+    let summary = Mutex::new(summary);
+    let image_urls = Mutex::new(BTreeSet::new());
+    for_each_parallel(&rows, set_workers, "sets", |row| {
+        let mut offset = Some(0);
+        while let Some(page_offset) = offset {
+            let url = content_set_url(config, row, page_offset);
+            match load_content_set(
+                &url,
+                &row.set_type,
+                config.dedupe_tiles,
+                config.http.set_timeout,
+                &cancel,
+            ) {
+                Ok(page) => {
+                    let mut image_urls = image_urls.lock().unwrap();
+                    image_urls.extend(page.tiles.into_iter().map(|tile| tile.image_url));
+                    offset = page.next_offset;
+                }
+                Err(err) => {
+                    summary
+                        .lock()
+                        .unwrap()
+                        .failures
+                        .push(format!("{}: {}", url, err));
+                    return;
+                }
+            }
+        }
+        summary.lock().unwrap().sets += 1;
+    });
+
+    let image_urls = image_urls
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for_each_parallel(&image_urls, image_workers, "images", |url| {
+        if cache.contains(url) {
+            summary.lock().unwrap().images_already_cached += 1;
+            return;
+        }
+        // Only images the app can display are worth keeping.
+        let result = fetch_bytes(url, config.http.image_timeout)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| match decode_image(&bytes) {
+                Ok(_) => Ok(bytes),
+                Err(err) => Err(err.to_string()),
+            })
+            .and_then(|bytes| {
+                cache.put(url, &bytes).map_err(|err| err.to_string())?;
+                Ok(bytes)
+            });
+        let mut summary = summary.lock().unwrap();
+        match result {
+            Ok(bytes) => {
+                summary.images_fetched += 1;
+                summary.bytes += bytes.len() as u64;
+            }
+            Err(err) => summary.failures.push(format!("{}: {}", url, err)),
+        }
+    });
+
+    summary.into_inner().unwrap()
+}
+
+// Runs `work` on every item, `workers` at a time, and prints progress to stderr.
+fn for_each_parallel<T: Sync>(items: &[T], workers: usize, label: &str, work: impl Fn(&T) + Sync) {
+    let next_item = AtomicUsize::new(0);
+    let done_items = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..workers.min(items.len()) {
+            scope.spawn(|| {
+                while let Some(item) = items.get(next_item.fetch_add(1, Ordering::Relaxed)) {
+                    work(item);
+                    let done = done_items.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprint!("\r{}: {}/{}", label, done, items.len());
+                }
+            });
+        }
+    });
+    if !items.is_empty() {
+        eprintln!();
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl fmt::Display for WarmSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} sets, {} images fetched ({} bytes), {} already cached, {} failures",
+            self.sets,
+            self.images_fetched,
+            self.bytes,
+            self.images_already_cached,
+            self.failures.len()
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "failed: {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{
+        home_json, home_path, png_bytes, set_path, test_config, tile_json, MockServer,
+    };
+
+    // A tile whose image is served by `server`.
+    fn served_tile(server: &MockServer, title: &str) -> Value {
+        let mut tile = tile_json(title);
+        tile["image"]["tile"]["1.78"]["program"]["default"]["url"] =
+            json!(format!("{}/images/{}.png", server.base_url, title));
+        tile
+    }
+
+    #[test]
+    fn warming_fills_the_image_cache() {
+        let server = MockServer::start();
+        server.add(
+            &home_path(),
+            home_json(&[("trending", "Trending"), ("new", "New")]).to_string(),
+        );
+        // "shared" is in both rows, but is only fetched once.
+        for (ref_id, titles) in [
+            ("trending", ["cached", "shared"]),
+            ("new", ["shared", "broken"]),
+        ] {
+            let items: Vec<Value> = titles
+                .iter()
+                .map(|title| served_tile(&server, title))
+                .collect();
+            let set = json!({ "data": { "CuratedSet": { "items": items } } });
+            server.add(&set_path(ref_id), set.to_string());
+        }
+        let shared_image = png_bytes(30, 20);
+        server.add("/images/cached.png", png_bytes(30, 20));
+        server.add("/images/shared.png", shared_image.clone());
+        server.add("/images/broken.png", "not an image");
+
+        let dir = std::env::temp_dir().join(format!("warm-{}", std::process::id()));
+        let mut config = test_config(&server);
+        config.image_cache_dir = Some(dir.clone());
+        let cache = ImageCache::new(dir.clone());
+        let cached_url = format!("{}/images/cached.png", server.base_url);
+        cache.put(&cached_url, &png_bytes(30, 20)).unwrap();
+
+        let summary = warm_caches(&config);
+        assert_eq!(summary.sets, 2);
+        assert_eq!(summary.images_already_cached, 1);
+        assert_eq!(summary.images_fetched, 1);
+        assert_eq!(summary.bytes, shared_image.len() as u64);
+        assert_eq!(summary.failures.len(), 1, "{:?}", summary.failures);
+        assert!(summary.failures[0].contains("broken.png"));
+
+        let shared_url = format!("{}/images/shared.png", server.base_url);
+        assert_eq!(cache.get(&shared_url), Some(shared_image));
+        assert!(!cache.contains(&format!("{}/images/broken.png", server.base_url)));
+        assert_eq!(server.request_count("/images/shared.png"), 1);
+        // The cached image wasn't fetched again.
+        assert_eq!(server.request_count("/images/cached.png"), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
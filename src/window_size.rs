@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use widget_cruncher::Size;
+
+use crate::paths::write_file;
+
+// The size of the window when the app was last used, so that it reopens the same.
+// Saved to `path` as "width height", in logical pixels.
+pub struct WindowSize {
+    pub path: Option<PathBuf>,
+    pub saved: Option<Size>,
+    // Whether `saved` changed since it was last saved. See `SessionState::flush`.
+    pub dirty: bool,
+}
+
+impl WindowSize {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let saved = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| parse_size(&contents));
+        Self {
+            path,
+            saved,
+            dirty: false,
+        }
+    }
+
+    // Called every time the window is resized.
+    pub fn record(&mut self, size: Size) {
+        if self.saved != Some(size) {
+            self.saved = Some(size);
+            self.dirty = true;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if self.dirty {
+            self.dirty = false;
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        let (path, size) = match (&self.path, self.saved) {
+            (Some(path), Some(size)) => (path, size),
+            _ => return,
+        };
+        let contents = format!("{} {}\n", size.width, size.height);
+        if let Err(err) = write_file(path, contents) {
+            tracing::warn!("Failed to save window size to {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn parse_size(contents: &str) -> Option<Size> {
+    let mut parts = contents.split_whitespace();
+    let width: f64 = parts.next()?.parse().ok()?;
+    let height: f64 = parts.next()?.parse().ok()?;
+    if width > 0.0 && height > 0.0 {
+        Some(Size::new(width, height))
+    } else {
+        None
+    }
+}
+
+// Shrinks `size` to fit `display`, eg if the window was last used on a bigger monitor.
+pub fn clamp_to_display(size: Size, display: Size) -> Size {
+    Size::new(
+        size.width.min(display.width),
+        size.height.min(display.height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_sizes_round_trip_and_clamp_to_smaller_displays() {
+        let dir = std::env::temp_dir().join(format!("window-size-{}", std::process::id()));
+        let path = dir.join("window_size");
+        let mut window_size = WindowSize::load(Some(path.clone()));
+        assert_eq!(window_size.saved, None);
+        window_size.record(Size::new(2560.0, 1440.0));
+        window_size.flush();
+
+        let reloaded = WindowSize::load(Some(path));
+        let saved = reloaded.saved.unwrap();
+        assert_eq!(saved, Size::new(2560.0, 1440.0));
+        // Reopened on a laptop screen.
+        let laptop = Size::new(1920.0, 1080.0);
+        assert_eq!(clamp_to_display(saved, laptop), laptop);
+        // Sizes that fit are left alone.
+        let small = Size::new(800.0, 600.0);
+        assert_eq!(clamp_to_display(small, laptop), small);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_sizes_are_ignored() {
+        assert_eq!(parse_size("1280 720\n"), Some(Size::new(1280.0, 720.0)));
+        assert_eq!(parse_size("1280"), None);
+        assert_eq!(parse_size("0 720"), None);
+        assert_eq!(parse_size("wide tall"), None);
+    }
+}
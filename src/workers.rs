@@ -0,0 +1,230 @@
+use std::cmp::{Ordering, Reverse};
+use std::sync::{Condvar, Mutex};
+use std::time::Instant;
+
+use crate::net::rate_limited_until;
+
+// How many loads can run at once while the CDN is rate limiting us, whatever the limit.
+const RATE_LIMITED_WORKERS: usize = 1;
+
+// Caps how many of our loads run at the same time.
+// The framework's background executor starts a thread per `compute_in_background`
+// call, so a feed with dozens of rows would otherwise fetch them all at once.
+// Loads past the limit wait for a slot before they start fetching, and get one
+// in order of their LoadPriority.
+// After a 429 response, the limit drops to RATE_LIMITED_WORKERS for a while.
+pub struct WorkerPool {
+    // If None, loads are only held back while rate limited.
+    pub limit: Option<usize>,
+    state: Mutex<PoolState>,
+    freed: Condvar,
+}
+
+struct PoolState {
+    busy: usize,
+    waiting: Vec<LoadPriority>,
+}
+
+// Which waiting load gets the next free slot. Loads with a higher `hint` go
+// first, and loads with the same hint go in feed order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadPriority {
+    pub hint: i64,
+    pub row: usize,
+}
+
+impl WorkerPool {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            // A limit of 0 would block every load forever.
+            limit: limit.map(|limit| limit.max(1)),
+            state: Mutex::new(PoolState {
+                busy: 0,
+                waiting: Vec::new(),
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    // Runs `work` on the calling thread once a slot is free, ahead of every
+    // other waiting load.
+    pub fn run<T>(&self, work: impl FnOnce() -> T) -> T {
+        self.run_with_priority(LoadPriority::FIRST, work)
+    }
+
+    // Runs `work` on the calling thread once a slot is free and no waiting load
+    // has a higher priority.
+    pub fn run_with_priority<T>(&self, priority: LoadPriority, work: impl FnOnce() -> T) -> T {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.waiting.push(priority);
+            loop {
+                let throttled_until = rate_limited_until();
+                let limit = match throttled_until {
+                    Some(_) => Some(RATE_LIMITED_WORKERS),
+                    None => self.limit,
+                };
+                let slot_free = limit.map_or(true, |limit| state.busy < limit);
+                let next_in_line = state.waiting.iter().all(|waiting| *waiting <= priority);
+                if slot_free && next_in_line {
+                    break;
+                }
+                // While throttled, no slot may free up before the throttling ends.
+                state = match throttled_until {
+                    Some(until) => {
+                        let timeout = until.saturating_duration_since(Instant::now());
+                        self.freed.wait_timeout(state, timeout).unwrap().0
+                    }
+                    None => self.freed.wait(state).unwrap(),
+                };
+            }
+            let index = state
+                .waiting
+                .iter()
+                .position(|waiting| *waiting == priority)
+                .unwrap();
+            state.waiting.swap_remove(index);
+            state.busy += 1;
+        }
+        // Frees the slot even if `work` panics.
+        let _slot = Slot(self);
+        work()
+    }
+}
+
+impl LoadPriority {
+    // For loads the rows depend on, like the collection itself.
+    pub const FIRST: LoadPriority = LoadPriority {
+        hint: i64::MAX,
+        row: 0,
+    };
+
+    pub fn new(hint: Option<i64>, row: usize) -> Self {
+        Self {
+            hint: hint.unwrap_or(0),
+            row,
+        }
+    }
+}
+
+struct Slot<'a>(&'a WorkerPool);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.busy -= 1;
+        // Every waiter checks whether it's next in line.
+        self.0.freed.notify_all();
+    }
+}
+
+// --- TRAIT IMPL ---
+
+impl Ord for LoadPriority {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.hint, Reverse(self.row)).cmp(&(other.hint, Reverse(other.row)))
+    }
+}
+
+impl PartialOrd for LoadPriority {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn loads_never_exceed_the_limit() {
+        let pool = Arc::new(WorkerPool::new(Some(2)));
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+        let loads: Vec<_> = (0..8)
+            .map(|row| {
+                let pool = pool.clone();
+                let running = running.clone();
+                let most_running = most_running.clone();
+                thread::spawn(move || {
+                    pool.run_with_priority(LoadPriority::new(None, row), || {
+                        let now_running = running.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+                        most_running.fetch_max(now_running, AtomicOrdering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        running.fetch_sub(1, AtomicOrdering::SeqCst);
+                    })
+                })
+            })
+            .collect();
+        for load in loads {
+            load.join().unwrap();
+        }
+
+        // Every load ran, two at a time at most.
+        let most_running = most_running.load(AtomicOrdering::SeqCst);
+        assert!((1..=2).contains(&most_running), "{}", most_running);
+        assert_eq!(running.load(AtomicOrdering::SeqCst), 0);
+    }
+
+    // Runs `count` loads on `pool` from their own threads, and returns them with
+    // the most loads that ran at once.
+    fn run_loads(
+        pool: &Arc<WorkerPool>,
+        count: usize,
+        load_time: Duration,
+    ) -> (Vec<thread::JoinHandle<()>>, Arc<AtomicUsize>) {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+        let loads = (0..count)
+            .map(|row| {
+                let pool = pool.clone();
+                let running = running.clone();
+                let most_running = most_running.clone();
+                thread::spawn(move || {
+                    pool.run_with_priority(LoadPriority::new(None, row), || {
+                        let now_running = running.fetch_add(1, AtomicOrdering::SeqCst) + 1;
+                        most_running.fetch_max(now_running, AtomicOrdering::SeqCst);
+                        thread::sleep(load_time);
+                        running.fetch_sub(1, AtomicOrdering::SeqCst);
+                    })
+                })
+            })
+            .collect();
+        (loads, most_running)
+    }
+
+    #[test]
+    fn image_and_set_pools_have_separate_limits() {
+        let image_pool = Arc::new(WorkerPool::new(Some(1)));
+        let set_pool = Arc::new(WorkerPool::new(Some(2)));
+
+        // A slow image holds the only image slot while every set loads.
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let slow_image = {
+            let image_pool = image_pool.clone();
+            thread::spawn(move || image_pool.run(|| released.recv().unwrap()))
+        };
+        thread::sleep(Duration::from_millis(20));
+        let (image_loads, most_images) = run_loads(&image_pool, 4, Duration::from_millis(10));
+        let (set_loads, most_sets) = run_loads(&set_pool, 6, Duration::from_millis(20));
+        for load in set_loads {
+            load.join().unwrap();
+        }
+        let most_sets = most_sets.load(AtomicOrdering::SeqCst);
+        assert!((1..=2).contains(&most_sets), "{}", most_sets);
+        // None of the other images started while the slow one was loading.
+        assert_eq!(most_images.load(AtomicOrdering::SeqCst), 0);
+
+        release.send(()).unwrap();
+        slow_image.join().unwrap();
+        for load in image_loads {
+            load.join().unwrap();
+        }
+        assert_eq!(most_images.load(AtomicOrdering::SeqCst), 1);
+    }
+}